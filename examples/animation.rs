@@ -0,0 +1,44 @@
+#[cfg(feature = "default")]
+extern crate sensehat_screen;
+
+#[cfg(feature = "default")]
+use sensehat_screen::{Animator, Display, FontCollection, FrameTiming, PixelColor, Screen, Scroll};
+#[cfg(feature = "default")]
+use std::thread;
+#[cfg(feature = "default")]
+use std::time::Duration;
+
+#[cfg(not(feature = "default"))]
+fn main() {
+    unimplemented!("This examples needs the 'default' features.");
+}
+
+#[cfg(feature = "default")]
+fn main() {
+    // Connect to our LED Matrix screen.
+    let mut screen = Screen::open_sense_hat().unwrap();
+
+    // Render a `FontString` as a vector of pixel frames.
+    let fonts = FontCollection::new();
+    let sanitized = fonts.sanitize_str(" Loop! ").unwrap();
+    let pixel_frames = sanitized.pixel_frames(PixelColor::MAGENTA, PixelColor::BLACK);
+
+    // Create a `Scroll` from the pixel frame vector.
+    let scroll = Scroll::new(&pixel_frames);
+
+    // Play the `FrameSequence` at 8 frames per second, over and over.
+    let mut animator = Animator::new(scroll.right_to_left(), FrameTiming::fps(8.0));
+    animator.set_looping(true);
+
+    // Stop the animation from another thread, after 20 seconds.
+    let handle = animator.handle();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_secs(20));
+        handle.stop();
+    });
+
+    let frames = animator.play(&mut screen).unwrap();
+    println!("Played {} frames", frames);
+
+    screen.clear().unwrap();
+}
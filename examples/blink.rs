@@ -47,7 +47,7 @@ fn main() {
     ];
     const OFF: [u8; 128] = [0x00; 128];
 
-    let mut screen = Screen::open("/dev/fb1").unwrap();
+    let mut screen = Screen::open_sense_hat().unwrap();
 
     let on_frame = FrameLine::from_slice(&ON);
     let off_frame = FrameLine::from_slice(&OFF);
@@ -56,27 +56,27 @@ fn main() {
     let green_frame = FrameLine::from_slice(&GREEN);
     let blue_frame = FrameLine::from_slice(&BLUE);
 
-    screen.write_frame(&on_frame);
+    screen.write_frame(&on_frame).unwrap();
     thread::sleep(Duration::from_millis(100));
-    screen.write_frame(&off_frame);
+    screen.write_frame(&off_frame).unwrap();
     thread::sleep(Duration::from_millis(100));
-    screen.write_frame(&on_frame);
+    screen.write_frame(&on_frame).unwrap();
     thread::sleep(Duration::from_millis(100));
-    screen.write_frame(&off_frame);
+    screen.write_frame(&off_frame).unwrap();
     thread::sleep(Duration::from_millis(100));
-    screen.write_frame(&on_frame);
+    screen.write_frame(&on_frame).unwrap();
     thread::sleep(Duration::from_millis(100));
-    screen.write_frame(&off_frame);
+    screen.write_frame(&off_frame).unwrap();
     thread::sleep(Duration::from_millis(100));
 
     for _ in 0..=9 {
-        screen.write_frame(&red_frame);
+        screen.write_frame(&red_frame).unwrap();
         thread::sleep(Duration::from_millis(1_500));
-        screen.write_frame(&green_frame);
+        screen.write_frame(&green_frame).unwrap();
         thread::sleep(Duration::from_millis(1_500));
-        screen.write_frame(&blue_frame);
+        screen.write_frame(&blue_frame).unwrap();
         thread::sleep(Duration::from_millis(1_500));
     }
 
-    screen.write_frame(&off_frame);
+    screen.write_frame(&off_frame).unwrap();
 }
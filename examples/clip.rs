@@ -13,7 +13,7 @@ fn main() {
 
 #[cfg(feature = "default")]
 fn main() {
-    let mut screen = Screen::open("/dev/fb1").unwrap();
+    let mut screen = Screen::open_sense_hat().unwrap();
 
     let letters = "a e i o u ";
     let letter_color = PixelColor::YELLOW.dim(0.5);
@@ -41,7 +41,7 @@ fn main() {
     });
 
     for frame in &frame_reel {
-        screen.write_frame(&frame.frame_line());
+        screen.write_frame(&frame.frame_line()).unwrap();
         ::std::thread::sleep(::std::time::Duration::from_millis(750));
     }
 }
@@ -0,0 +1,31 @@
+#[cfg(feature = "default")]
+extern crate sensehat_screen;
+
+#[cfg(feature = "default")]
+use sensehat_screen::{ColorMode, Dither, Display, Image, TerminalScreen};
+#[cfg(feature = "default")]
+use std::env;
+#[cfg(feature = "default")]
+use std::io;
+
+#[cfg(not(feature = "default"))]
+fn main() {
+    unimplemented!("This examples needs the 'default' features.");
+}
+
+#[cfg(feature = "default")]
+fn main() {
+    // Decode the PPM, BMP, or PNG image given as the only argument.
+    let path = env::args().nth(1)
+                          .expect("Usage: image <path to a PPM, BMP, or PNG image>");
+    let image = Image::open(&path).unwrap();
+    println!("{}: {}x{} pixels", path, image.width(), image.height());
+
+    // Log the image, resampled to 8x8, with each dither, one below the other.
+    let mut terminal = TerminalScreen::with_writer(io::stdout(), ColorMode::detect());
+    terminal.set_redraw_in_place(false);
+    for &dither in &[Dither::None, Dither::FloydSteinberg, Dither::Ordered] {
+        println!("{:?}", dither);
+        terminal.write_pixel_frame(&image.resample(dither)).unwrap();
+    }
+}
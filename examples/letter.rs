@@ -14,7 +14,7 @@ fn main() {
 
 #[cfg(feature = "default")]
 fn main() {
-    let mut screen = Screen::open("/dev/fb1").unwrap();
+    let mut screen = Screen::open_sense_hat().unwrap();
 
     let white_50_pct = PixelColor::WHITE.dim(0.5);
 
@@ -25,7 +25,7 @@ fn main() {
     for unicode in screen_text.chars() {
         if let Some(symbol) = FONT_COLLECTION.get(unicode) {
             let frame = font_to_frame(&symbol.byte_array(), white_50_pct);
-            screen.write_frame(&frame);
+            screen.write_frame(&frame).unwrap();
         }
         thread::sleep(Duration::from_millis(800));
     }
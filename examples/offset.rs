@@ -12,7 +12,7 @@ fn main() {
 }
 #[cfg(feature = "default")]
 fn main() {
-    let mut screen = Screen::open("/dev/fb1").unwrap();
+    let mut screen = Screen::open_sense_hat().unwrap();
     let fonts = FontCollection::new();
 
     for &(sym, color) in &[('þ', PixelColor::CYAN), ('ß', PixelColor::WHITE.dim(0.5))] {
@@ -23,12 +23,12 @@ fn main() {
         // reaching the offset = 0 position, which renders the entire symbol on
         // the screen.
         for i in 0..=8 {
-            screen.write_frame(&symbol.offset(Offset::left(8 - i)).frame_line());
+            screen.write_frame(&symbol.offset(Offset::left(8 - i)).frame_line()).unwrap();
             ::std::thread::sleep(::std::time::Duration::from_millis(500));
         }
         // Slides the displayed symbol to the right until it disappears.
         for i in 0..=8 {
-            screen.write_frame(&symbol.offset(Offset::right(i)).frame_line());
+            screen.write_frame(&symbol.offset(Offset::right(i)).frame_line()).unwrap();
             ::std::thread::sleep(::std::time::Duration::from_millis(500));
         }
 
@@ -36,14 +36,14 @@ fn main() {
         // reaching the offset = 0 position, which renders the entire symbol on
         // the screen.
         for i in 0..=8 {
-            screen.write_frame(&symbol.offset(Offset::top(8 - i)).frame_line());
+            screen.write_frame(&symbol.offset(Offset::top(8 - i)).frame_line()).unwrap();
             ::std::thread::sleep(::std::time::Duration::from_millis(500));
         }
         // Slides the displayed symbol to the bottom until it disappears.
         for i in 0..=8 {
-            screen.write_frame(&symbol.offset(Offset::bottom(i)).frame_line());
+            screen.write_frame(&symbol.offset(Offset::bottom(i)).frame_line()).unwrap();
             ::std::thread::sleep(::std::time::Duration::from_millis(500));
         }
     }
-    screen.write_frame(&PixelFrame::new(&[PixelColor::BLACK; 64]).frame_line());
+    screen.write_frame(&PixelFrame::new(&[PixelColor::BLACK; 64]).frame_line()).unwrap();
 }
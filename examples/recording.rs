@@ -0,0 +1,35 @@
+#[cfg(feature = "default")]
+extern crate sensehat_screen;
+
+#[cfg(feature = "default")]
+use sensehat_screen::recording::replay;
+#[cfg(feature = "default")]
+use sensehat_screen::{Animator, FontCollection, FrameTiming, PixelColor, RecordingFormat,
+                      RecordingScreen, Scroll, TerminalScreen};
+#[cfg(feature = "default")]
+use std::env;
+
+#[cfg(not(feature = "default"))]
+fn main() {
+    unimplemented!("This examples needs the 'default' features.");
+}
+
+#[cfg(feature = "default")]
+fn main() {
+    let path = env::temp_dir().join("sensehat-screen-session.jsonl");
+
+    // Record a scroll, played in the terminal, as JSON lines.
+    let mut recorder =
+        RecordingScreen::create(TerminalScreen::new(), &path, RecordingFormat::JsonLines).unwrap();
+    let fonts = FontCollection::new();
+    let sanitized = fonts.sanitize_str(" Recorded! ").unwrap();
+    let pixel_frames = sanitized.pixel_frames(PixelColor::YELLOW, PixelColor::BLACK);
+    let scroll = Scroll::new(&pixel_frames);
+    let mut animator = Animator::new(scroll.right_to_left(), FrameTiming::fps(8.0));
+    animator.play(&mut recorder).unwrap();
+    let (mut terminal, _) = recorder.into_inner().unwrap();
+
+    // Replay the session, four times as fast.
+    let frames = replay(&path, &mut terminal, 4.0).unwrap();
+    println!("replayed {} frames from {}", frames, path.display());
+}
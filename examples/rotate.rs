@@ -12,7 +12,7 @@ fn main() {
 }
 #[cfg(feature = "default")]
 fn main() {
-    let mut screen = Screen::open("/dev/fb1").unwrap();
+    let mut screen = Screen::open_sense_hat().unwrap();
     let fonts = FontCollection::new();
 
     for &(sym, color) in &[('Ñ', PixelColor::YELLOW), ('ó', PixelColor::MAGENTA)] {
@@ -22,15 +22,15 @@ fn main() {
         let symbol_180 = symbol.rotate(Rotate::Ccw180);
         let symbol_270 = symbol.rotate(Rotate::Ccw270);
         for _ in 0..=4 {
-            screen.write_frame(&symbol.frame_line());
+            screen.write_frame(&symbol.frame_line()).unwrap();
             ::std::thread::sleep(::std::time::Duration::from_millis(500));
-            screen.write_frame(&symbol_90.frame_line());
+            screen.write_frame(&symbol_90.frame_line()).unwrap();
             ::std::thread::sleep(::std::time::Duration::from_millis(500));
-            screen.write_frame(&symbol_180.frame_line());
+            screen.write_frame(&symbol_180.frame_line()).unwrap();
             ::std::thread::sleep(::std::time::Duration::from_millis(500));
-            screen.write_frame(&symbol_270.frame_line());
+            screen.write_frame(&symbol_270.frame_line()).unwrap();
             ::std::thread::sleep(::std::time::Duration::from_millis(500));
         }
-        screen.write_frame(&PixelFrame::new(&[PixelColor::BLACK; 64]).frame_line());
+        screen.write_frame(&PixelFrame::new(&[PixelColor::BLACK; 64]).frame_line()).unwrap();
     }
 }
@@ -0,0 +1,33 @@
+#[cfg(feature = "default")]
+extern crate sensehat_screen;
+
+#[cfg(feature = "default")]
+use sensehat_screen::{Animator, FontCollection, FrameTiming, LedShape, PixelColor, ScaledScreen,
+                      Scroll};
+#[cfg(feature = "default")]
+use std::env;
+
+#[cfg(not(feature = "default"))]
+fn main() {
+    unimplemented!("This examples needs the 'default' features.");
+}
+
+#[cfg(feature = "default")]
+fn main() {
+    // Preview on the framebuffer given as the only argument, or on the HDMI display.
+    let path = env::args().nth(1).unwrap_or_else(|| "/dev/fb0".to_string());
+    let mut preview = ScaledScreen::open(&path).unwrap();
+    preview.set_shape(LedShape::Round);
+    preview.set_gap(preview.geometry().height / 64);
+    println!("Previewing on {}: {:?}", path, preview.geometry());
+
+    // Render a `FontString` as a vector of pixel frames.
+    let fonts = FontCollection::new();
+    let sanitized = fonts.sanitize_str(" Preview ").unwrap();
+    let pixel_frames = sanitized.pixel_frames(PixelColor::YELLOW, PixelColor::BLACK);
+
+    // Play the scroll once, at 8 frames per second.
+    let scroll = Scroll::new(&pixel_frames);
+    let mut animator = Animator::new(scroll.right_to_left(), FrameTiming::fps(8.0));
+    animator.play(&mut preview).unwrap();
+}
@@ -12,7 +12,7 @@ fn main() {
 #[cfg(feature = "default")]
 fn main() {
     // Connect to our LED Matrix screen.
-    let mut screen = Screen::open("/dev/fb1").unwrap();
+    let mut screen = Screen::open_sense_hat().unwrap();
 
     // Get the default `FontCollection`.
     let fonts = FontCollection::new();
@@ -29,7 +29,7 @@ fn main() {
     scroll.right_to_left().for_each(|frame| {
         println!("Now printing:");
         println!("{:?}", frame);
-        screen.write_frame(&frame.frame_line());
+        screen.write_frame(&frame.frame_line()).unwrap();
         ::std::thread::sleep(::std::time::Duration::from_millis(250));
     });
 }
@@ -0,0 +1,26 @@
+#[cfg(feature = "default")]
+extern crate sensehat_screen;
+
+#[cfg(feature = "default")]
+use sensehat_screen::{Animator, FontCollection, FrameTiming, PixelColor, Scroll, TerminalScreen};
+
+#[cfg(not(feature = "default"))]
+fn main() {
+    unimplemented!("This examples needs the 'default' features.");
+}
+
+#[cfg(feature = "default")]
+fn main() {
+    // Draw on the standard output, with the colors supported by the terminal.
+    let mut terminal = TerminalScreen::new();
+
+    // Render a `FontString` as a vector of pixel frames.
+    let fonts = FontCollection::new();
+    let sanitized = fonts.sanitize_str(" Hello, terminal! ").unwrap();
+    let pixel_frames = sanitized.pixel_frames(PixelColor::CYAN, PixelColor::BLACK);
+
+    // Play the scroll once, at 8 frames per second, redrawing each frame in place.
+    let scroll = Scroll::new(&pixel_frames);
+    let mut animator = Animator::new(scroll.right_to_left(), FrameTiming::fps(8.0));
+    animator.play(&mut terminal).unwrap();
+}
@@ -0,0 +1,450 @@
+//! Frame-rate controlled animations for the LED Matrix.
+//!
+//! An `Animator` plays any iterator of `PixelFrame`s, such as the `FrameSequence` returned by
+//! `Scroll::right_to_left`, on a `Display`, sleeping the right amount of time between frames.
+//!
+//! ```no_run
+//! # extern crate sensehat_screen;
+//! # use sensehat_screen::{Animator, FrameTiming, MemoryScreen, PixelFrame};
+//! # fn main() {
+//!     // any `Display`, such as a `Screen` on the Sense HAT
+//!     let mut screen = MemoryScreen::new();
+//!     let frames = vec![PixelFrame::RED, PixelFrame::GREEN, PixelFrame::BLUE];
+//!     let mut animator = Animator::new(frames.into_iter(), FrameTiming::fps(2.0));
+//!     animator.set_looping(true);
+//!
+//!     // stop the animation from another thread
+//!     let handle = animator.handle();
+//!     ::std::thread::spawn(move || {
+//!         ::std::thread::sleep(::std::time::Duration::from_secs(10));
+//!         handle.stop();
+//!     });
+//!
+//!     animator.play(&mut screen).unwrap();
+//! # }
+//! ```
+use super::display::Display;
+use super::error::Result;
+use super::PixelFrame;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long a paused animation sleeps before checking if it was resumed.
+const PAUSE_POLL: Duration = Duration::from_millis(10);
+
+/// Most frames that a looping `Animator` keeps in memory, to show them again once its source
+/// runs out. A longer, or endless, source is played once, without keeping its frames.
+pub const MAX_LOOP_FRAMES: usize = 4096;
+
+/// A source of time for playing animations.
+pub trait Clock {
+    /// Time elapsed since some fixed point, such as the creation of the clock.
+    fn now(&self) -> Duration;
+    /// Block the current thread for the given duration.
+    fn sleep(&self, duration: Duration);
+}
+
+/// A `Clock` that measures real time, and really sleeps.
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    /// Create a new `SystemClock`, starting now.
+    pub fn new() -> Self {
+        SystemClock { start: Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// A `Clock` that only moves forward when told to, or when slept on. Sleeping returns
+/// immediately. Clones share the same time, which makes it useful for testing animations.
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    /// Create a new `ManualClock`, starting at zero.
+    pub fn new() -> Self {
+        ManualClock::default()
+    }
+
+    /// Move the clock forward by the given duration.
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.advance(duration);
+        thread::yield_now();
+    }
+}
+
+/// How long each frame of an animation is shown.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FrameTiming {
+    /// Every frame is shown for the same duration.
+    Interval(Duration),
+    /// Each frame is shown for its own duration. When there are more frames than durations,
+    /// the durations are cycled.
+    PerFrame(Vec<Duration>),
+}
+
+impl FrameTiming {
+    /// Show every frame for the same duration, at `fps` frames per second.
+    ///
+    /// # Panics
+    /// If `fps` is not a positive number.
+    pub fn fps(fps: f32) -> Self {
+        assert!(fps > 0.0);
+        FrameTiming::Interval(Duration::from_secs_f64(1.0 / f64::from(fps)))
+    }
+
+    /// Returns the duration of the frame at `index`.
+    pub fn duration(&self, index: usize) -> Duration {
+        match *self {
+            FrameTiming::Interval(interval) => interval,
+            FrameTiming::PerFrame(ref durations) if durations.is_empty() => Duration::default(),
+            FrameTiming::PerFrame(ref durations) => durations[index % durations.len()],
+        }
+    }
+}
+
+/// A handle to pause, resume, or stop an `Animator`, from any thread.
+#[derive(Clone, Debug, Default)]
+pub struct AnimationHandle {
+    paused: Arc<AtomicBool>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl AnimationHandle {
+    /// Pause the animation, the current frame stays on the display.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Resume a paused animation.
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Stop the animation. `Animator::play` returns after the current frame.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if the animation is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Returns `true` if the animation was stopped.
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+}
+
+/// Plays a sequence of `PixelFrame`s on a `Display`, with drift-corrected timing.
+///
+/// Frames are scheduled from the moment the animation starts, so the time spent writing each
+/// frame does not add up over long animations.
+#[derive(Debug)]
+pub struct Animator<I, C = SystemClock> {
+    frames: I,
+    timing: FrameTiming,
+    clock: C,
+    looping: bool,
+    handle: AnimationHandle,
+}
+
+impl<I: Iterator<Item = PixelFrame>> Animator<I, SystemClock> {
+    /// Create a new `Animator` for the given frames, shown with the given timing.
+    pub fn new(frames: I, timing: FrameTiming) -> Self {
+        Animator::with_clock(frames, timing, SystemClock::new())
+    }
+}
+
+impl<I: Iterator<Item = PixelFrame>, C: Clock> Animator<I, C> {
+    /// Create a new `Animator` that measures time, and sleeps, with the given clock.
+    pub fn with_clock(frames: I, timing: FrameTiming, clock: C) -> Self {
+        Animator { frames,
+                   timing,
+                   clock,
+                   looping: false,
+                   handle: AnimationHandle::default(), }
+    }
+
+    /// Set whether the animation starts over once the last frame is shown.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Returns a handle to pause, resume, or stop the animation from another thread.
+    pub fn handle(&self) -> AnimationHandle {
+        self.handle.clone()
+    }
+
+    /// Play the animation on the display. Blocks until every frame has been shown, or until
+    /// the animation is stopped. Returns the number of frames written.
+    ///
+    /// When looping, the frames of the first pass are kept in memory, to be shown again, up to
+    /// `MAX_LOOP_FRAMES`. A source with more frames, such as an endless generated clock, is
+    /// not kept, and plays through once.
+    pub fn play<D: Display + ?Sized>(&mut self, display: &mut D) -> Result<usize> {
+        let mut played = Vec::new();
+        let mut keep = self.looping;
+        let mut written = 0;
+        let mut deadline = self.clock.now();
+        let mut index = 0;
+        loop {
+            if self.handle.is_stopped() {
+                break;
+            }
+            let frame = match self.frames.next() {
+                Some(frame) => {
+                    if keep && played.len() < MAX_LOOP_FRAMES {
+                        played.push(frame);
+                    } else if keep {
+                        keep = false;
+                        played = Vec::new();
+                    }
+                    frame
+                }
+                None if keep && !played.is_empty() => played[index % played.len()],
+                None => break,
+            };
+            display.write_pixel_frame(&frame)?;
+            written += 1;
+            deadline += self.timing.duration(index);
+            index += 1;
+            self.wait_until(&mut deadline);
+        }
+        Ok(written)
+    }
+
+    // Sleep until the deadline, or for as long as the animation is paused. The deadline moves
+    // forward by the time spent paused.
+    fn wait_until(&self, deadline: &mut Duration) {
+        let now = self.clock.now();
+        if *deadline > now {
+            self.clock.sleep(*deadline - now);
+        }
+        if self.handle.is_paused() {
+            let paused_at = self.clock.now();
+            while self.handle.is_paused() && !self.handle.is_stopped() {
+                self.clock.sleep(PAUSE_POLL);
+            }
+            *deadline += self.clock.now() - paused_at;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use display::MemoryScreen;
+
+    // A manual clock that records every sleep.
+    #[derive(Clone, Default)]
+    struct SlowWriteClock {
+        clock: ManualClock,
+        sleeps: Arc<Mutex<Vec<Duration>>>,
+    }
+
+    impl Clock for SlowWriteClock {
+        fn now(&self) -> Duration {
+            self.clock.now()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.sleeps.lock().unwrap().push(duration);
+            self.clock.sleep(duration);
+        }
+    }
+
+    // A display that takes 3 ms of the clock to write each frame.
+    struct SlowDisplay {
+        clock: ManualClock,
+        screen: MemoryScreen,
+    }
+
+    impl Display for SlowDisplay {
+        fn write_frame(&mut self, frame: &::FrameLine) -> Result<()> {
+            self.clock.advance(Duration::from_millis(3));
+            self.screen.write_frame(frame)
+        }
+    }
+
+    const FRAMES: [PixelFrame; 3] = [PixelFrame::RED, PixelFrame::GREEN, PixelFrame::BLUE];
+
+    #[test]
+    fn frame_timing_from_fps_has_equal_intervals() {
+        let timing = FrameTiming::fps(4.0);
+        assert_eq!(timing.duration(0), Duration::from_millis(250));
+        assert_eq!(timing.duration(99), Duration::from_millis(250));
+    }
+
+    #[test]
+    #[should_panic]
+    fn frame_timing_from_zero_fps_will_panic() {
+        let _ = FrameTiming::fps(0.0);
+    }
+
+    #[test]
+    fn frame_timing_cycles_per_frame_durations() {
+        let timing = FrameTiming::PerFrame(vec![Duration::from_millis(10),
+                                                Duration::from_millis(20)]);
+        assert_eq!(timing.duration(0), Duration::from_millis(10));
+        assert_eq!(timing.duration(1), Duration::from_millis(20));
+        assert_eq!(timing.duration(2), Duration::from_millis(10));
+    }
+
+    #[test]
+    fn manual_clock_advances_when_slept_on() {
+        let clock = ManualClock::new();
+        let shared = clock.clone();
+        clock.sleep(Duration::from_millis(5));
+        shared.advance(Duration::from_millis(1));
+        assert_eq!(clock.now(), Duration::from_millis(6));
+    }
+
+    #[test]
+    fn animator_plays_every_frame_once() {
+        let clock = ManualClock::new();
+        let mut screen = MemoryScreen::new();
+        let mut animator = Animator::with_clock(FRAMES.to_vec().into_iter(),
+                                                FrameTiming::fps(10.0),
+                                                clock.clone());
+        assert_eq!(animator.play(&mut screen).unwrap(), 3);
+        let expected = FRAMES.iter().map(|f| f.frame_line()).collect::<Vec<_>>();
+        assert_eq!(screen.frames(), expected.as_slice());
+        assert_eq!(clock.now(), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn animator_corrects_for_time_spent_writing_frames() {
+        let clock = SlowWriteClock::default();
+        let mut display = SlowDisplay { clock: clock.clock.clone(),
+                                        screen: MemoryScreen::new(), };
+        let timing = FrameTiming::PerFrame(vec![Duration::from_millis(10),
+                                                Duration::from_millis(20)]);
+        let mut animator =
+            Animator::with_clock(FRAMES.to_vec().into_iter(), timing, clock.clone());
+        animator.play(&mut display).unwrap();
+        assert_eq!(*clock.sleeps.lock().unwrap(),
+                   vec![Duration::from_millis(7),
+                        Duration::from_millis(17),
+                        Duration::from_millis(7)]);
+        assert_eq!(clock.now(), Duration::from_millis(40));
+    }
+
+    #[test]
+    fn animator_loops_until_stopped() {
+        let clock = ManualClock::new();
+        let mut screen = MemoryScreen::new();
+        let mut animator = Animator::with_clock(FRAMES.to_vec().into_iter(),
+                                                FrameTiming::fps(100.0),
+                                                clock.clone());
+        animator.set_looping(true);
+        let handle = animator.handle();
+
+        struct StopAfter<'a> {
+            screen: &'a mut MemoryScreen,
+            handle: AnimationHandle,
+            frames: usize,
+        }
+        impl<'a> Display for StopAfter<'a> {
+            fn write_frame(&mut self, frame: &::FrameLine) -> Result<()> {
+                self.screen.write_frame(frame)?;
+                if self.screen.frames().len() == self.frames {
+                    self.handle.stop();
+                }
+                Ok(())
+            }
+        }
+
+        let mut display = StopAfter { screen: &mut screen,
+                                      handle,
+                                      frames: 7, };
+        assert_eq!(animator.play(&mut display).unwrap(), 7);
+        let expected = FRAMES.iter()
+                             .cycle()
+                             .take(7)
+                             .map(|f| f.frame_line())
+                             .collect::<Vec<_>>();
+        assert_eq!(screen.frames(), expected.as_slice());
+    }
+
+    #[test]
+    fn animator_plays_sources_too_long_to_keep_once() {
+        let mut screen = MemoryScreen::new();
+        let frames = FRAMES.iter().cloned().cycle().take(MAX_LOOP_FRAMES + 1);
+        let mut animator =
+            Animator::with_clock(frames, FrameTiming::fps(100.0), ManualClock::new());
+        animator.set_looping(true);
+        assert_eq!(animator.play(&mut screen).unwrap(), MAX_LOOP_FRAMES + 1);
+    }
+
+    #[test]
+    fn animator_does_not_play_once_stopped() {
+        let mut screen = MemoryScreen::new();
+        let mut animator = Animator::with_clock(FRAMES.to_vec().into_iter(),
+                                                FrameTiming::fps(10.0),
+                                                ManualClock::new());
+        animator.handle().stop();
+        assert_eq!(animator.play(&mut screen).unwrap(), 0);
+        assert!(screen.frames().is_empty());
+    }
+
+    #[test]
+    fn animator_waits_while_paused() {
+        let clock = ManualClock::new();
+        let mut screen = MemoryScreen::new();
+        let mut animator = Animator::with_clock(FRAMES.to_vec().into_iter(),
+                                                FrameTiming::fps(10.0),
+                                                clock.clone());
+        let handle = animator.handle();
+        handle.pause();
+        assert!(handle.is_paused());
+        let resumer = {
+            let handle = handle.clone();
+            let clock = clock.clone();
+            thread::spawn(move || {
+                while clock.now() < Duration::from_secs(1) {
+                    thread::yield_now();
+                }
+                handle.resume();
+            })
+        };
+        assert_eq!(animator.play(&mut screen).unwrap(), 3);
+        resumer.join().unwrap();
+        assert!(!handle.is_paused());
+        assert!(clock.now() >= Duration::from_secs(1));
+        assert_eq!(screen.frames().len(), 3);
+    }
+}
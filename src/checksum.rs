@@ -0,0 +1,45 @@
+//! Checksums of the PNG, and zlib, formats.
+
+// The CRC-32 used by PNG chunks.
+pub fn crc32<I: IntoIterator<Item = u8>>(bytes: I) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in bytes {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { 0xEDB8_8320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+// The Adler-32 checksum of zlib streams.
+pub fn adler32(bytes: &[u8]) -> u32 {
+    adler32_update(1, bytes)
+}
+
+// The Adler-32 checksum `adler` of some data, updated with the bytes that follow it.
+pub fn adler32_update(adler: u32, bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (adler & 0xFFFF, adler >> 16);
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += u32::from(byte);
+            b += a;
+        }
+        a %= 65_521;
+        b %= 65_521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_match_known_values() {
+        assert_eq!(crc32(b"IEND".iter().cloned()), 0xAE42_6082);
+        assert_eq!(crc32(b"123456789".iter().cloned()), 0xCBF4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        assert_eq!(adler32_update(adler32(b"Wiki"), b"pedia"), 0x11E6_0398);
+    }
+}
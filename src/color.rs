@@ -1,4 +1,25 @@
 //! RGB color for LED pixels, with RGB565 rendering support.
+#[path = "color_blend.rs"]
+pub mod blend;
+#[path = "color_brightness.rs"]
+mod brightness;
+#[path = "color_format.rs"]
+pub mod format;
+#[path = "color_gradient.rs"]
+pub mod gradient;
+#[path = "color_hsv.rs"]
+mod hsv;
+#[path = "color_ops.rs"]
+mod ops;
+#[path = "color_palette.rs"]
+pub mod palette;
+#[path = "color_parse.rs"]
+mod parse;
+#[path = "color_quantize.rs"]
+mod quantize;
+
+pub use self::parse::ParseColorError;
+
 /// A single LED pixel color, with RGB565 rendering.
 ///
 /// ```
@@ -16,7 +37,7 @@
 ///     // convert directly from Rgb565
 ///     let blue_rgb565 = Rgb565::from_rgb(0, 0, 0xFF);
 ///     let blue: PixelColor = blue_rgb565.into();
-///     assert_eq!(blue, PixelColor::new(0, 0, 0xF8));
+///     assert_eq!(blue, PixelColor::BLUE);
 ///
 ///     // INTO
 ///     // convert directly into a 3-bytes tuple
@@ -37,9 +58,10 @@ use std::fmt;
 
 /// 24-bit RGB color pixel.
 ///
-/// This is the fundamental representation for RGB colors.
-#[derive(Copy, Clone, Default, PartialEq)]
-#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+/// This is the fundamental representation for RGB colors. It is displayed, and serialized
+/// with `serde-support`, as `#RRGGBB`, and parsed from `#RGB`, `#RRGGBB`, `rgb(r, g, b)`, or a
+/// CSS color name.
+#[derive(Copy, Clone, Default, Eq, Hash, PartialEq)]
 pub struct PixelColor {
     pub red: u8,
     pub green: u8,
@@ -52,6 +74,12 @@ impl fmt::Debug for PixelColor {
     }
 }
 
+impl fmt::Display for PixelColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.red, self.green, self.blue)
+    }
+}
+
 impl PixelColor {
     pub const BLACK: PixelColor = PixelColor { red: 0,
                                                green: 0,
@@ -112,13 +140,8 @@ impl PixelColor {
     ///
     /// The `scale` value should be between 0 and 1. Values outside this range
     /// are clamped.
-    pub fn dim(self, mut scale: f32) -> PixelColor {
-        if scale > 1.0 {
-            scale = 1.0;
-        }
-        if scale < 0.0 {
-            scale = 0.0;
-        }
+    pub fn dim(self, scale: f32) -> PixelColor {
+        let scale = scale.clamp(0.0, 1.0);
         fn scale_byte(b: u8, scale: f32) -> u8 {
             (f32::from(b) * scale) as u8
         }
@@ -141,9 +164,9 @@ impl From<(u8, u8, u8)> for PixelColor {
     }
 }
 
-impl Into<(u8, u8, u8)> for PixelColor {
-    fn into(self) -> (u8, u8, u8) {
-        (self.red, self.green, self.blue)
+impl From<PixelColor> for (u8, u8, u8) {
+    fn from(color: PixelColor) -> Self {
+        (color.red, color.green, color.blue)
     }
 }
 
@@ -175,7 +198,8 @@ impl Into<(u8, u8, u8)> for PixelColor {
 pub struct Rgb565(u16);
 
 impl Rgb565 {
-    /// Create `Rgb565` instance from red, green, and blue `u8` values.
+    /// Create `Rgb565` instance from red, green, and blue `u8` values, dropping the low bits of
+    /// every channel. Use `Rgb565::from_rgb_rounded` for the nearest color instead.
     pub fn from_rgb(red: u8, green: u8, blue: u8) -> Self {
         let r = u16::from((red >> 3) & 0x1F);
         let g = u16::from((green >> 2) & 0x3F);
@@ -184,11 +208,12 @@ impl Rgb565 {
         Rgb565(rgb)
     }
 
-    /// Create `(u8, u8, u8)` instance from a `Rgb565` instance.
+    /// Create `(u8, u8, u8)` instance from a `Rgb565` instance. The high bits of every channel
+    /// are repeated in its low bits, so that the greatest values expand to `0xFF`.
     pub fn to_rgb(self) -> (u8, u8, u8) {
-        let red = (((self.0 & 0b1111_1000_0000_0000) >> 11) << 3) as u8;
-        let green = (((self.0 & 0b0000_0111_1110_0000) >> 5) << 2) as u8;
-        let blue = ((self.0 & 0b0000_0000_0001_1111) << 3) as u8;
+        let red = expand((self.0 & 0b1111_1000_0000_0000) >> 11, 5);
+        let green = expand((self.0 & 0b0000_0111_1110_0000) >> 5, 6);
+        let blue = expand(self.0 & 0b0000_0000_0001_1111, 5);
         (red, green, blue)
     }
 
@@ -225,9 +250,38 @@ impl Rgb565 {
     }
 }
 
-impl Into<u16> for Rgb565 {
-    fn into(self) -> u16 {
-        self.0
+// Expand a channel `level`, of `bits` bits, to a byte, by repeating its high bits.
+pub(super) fn expand(level: u16, bits: u32) -> u8 {
+    ((level << (8 - bits)) | (level >> (2 * bits - 8))) as u8
+}
+
+// The level, of a channel of `bits` bits, that `expand`s to the byte nearest to `value`, which
+// is clamped to the `0.0..=255.0` range.
+pub(super) fn nearest_level(value: f32, bits: u32) -> u16 {
+    let value = value.clamp(0.0, 255.0);
+    let max = (1 << bits) - 1;
+    // the expanded levels are within a byte of their scaled value, so the nearest one is next
+    // to the scaled `value`.
+    let scaled = (value * max as f32 / 255.0).round() as u16;
+    let distance = |level: u16| (f32::from(expand(level, bits)) - value).abs();
+    (scaled.saturating_sub(1)..=(scaled + 1).min(max)).min_by(|&a, &b| {
+                                                          distance(a).total_cmp(&distance(b))
+                                                      })
+                                                      .unwrap_or(scaled)
+}
+
+// Clamp `value` to the `0.0..=1.0` range, with `NaN` as `0.0`.
+pub(super) fn unit(value: f32) -> f32 {
+    if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    }
+}
+
+impl From<Rgb565> for u16 {
+    fn from(color: Rgb565) -> Self {
+        color.0
     }
 }
 
@@ -237,9 +291,9 @@ impl From<u16> for Rgb565 {
     }
 }
 #[cfg(not(feature = "big-endian"))]
-impl Into<[u8; 2]> for Rgb565 {
-    fn into(self) -> [u8; 2] {
-        Rgb565::split_le(self)
+impl From<Rgb565> for [u8; 2] {
+    fn from(color: Rgb565) -> Self {
+        color.split_le()
     }
 }
 
@@ -251,9 +305,9 @@ impl From<[u8; 2]> for Rgb565 {
 }
 
 #[cfg(feature = "big-endian")]
-impl Into<[u8; 2]> for Rgb565 {
-    fn into(self) -> [u8; 2] {
-        Rgb565::split_be(self)
+impl From<Rgb565> for [u8; 2] {
+    fn from(color: Rgb565) -> Self {
+        color.split_be()
     }
 }
 
@@ -270,9 +324,9 @@ impl From<(u8, u8, u8)> for Rgb565 {
     }
 }
 
-impl Into<(u8, u8, u8)> for Rgb565 {
-    fn into(self) -> (u8, u8, u8) {
-        self.to_rgb()
+impl From<Rgb565> for (u8, u8, u8) {
+    fn from(color: Rgb565) -> Self {
+        color.to_rgb()
     }
 }
 
@@ -318,10 +372,10 @@ mod tests {
                    PixelColor::new(0x00, 0x00, 0x00));
         // white 5-bit, 6-bit, 5-bit resolution
         assert_eq!(PixelColor::from_rgb565_bytes([0xFF, 0xFF]),
-                   PixelColor::new(0xF8, 0xFC, 0xF8));
+                   PixelColor::WHITE);
         // 100% green - 6-bit resolution
         assert_eq!(PixelColor::from_rgb565_bytes([0xE0, 0x07]),
-                   PixelColor::new(0x00, 0xFC, 0x00));
+                   PixelColor::GREEN);
     }
 
     #[cfg(feature = "big-endian")]
@@ -332,10 +386,27 @@ mod tests {
                    PixelColor::new(0x00, 0x00, 0x00));
         // white 5-bit, 6-bit, 5-bit resolution
         assert_eq!(PixelColor::from_rgb565_bytes([0xFF, 0xFF]),
-                   PixelColor::new(0xF8, 0xFC, 0xF8));
+                   PixelColor::WHITE);
         // 100% green - 6-bit resolution
         assert_eq!(PixelColor::from_rgb565_bytes([0x07, 0xE0]),
-                   PixelColor::new(0x00, 0xFC, 0x00));
+                   PixelColor::GREEN);
+    }
+
+    #[test]
+    fn nearest_levels_expand_to_the_nearest_byte() {
+        for &bits in &[5, 6] {
+            for value in 0..=255u8 {
+                let distance = |level: u16| {
+                    (i16::from(expand(level, bits)) - i16::from(value)).abs()
+                };
+                let nearest = (0..1 << bits).map(distance).min().unwrap();
+                assert_eq!(distance(nearest_level(f32::from(value), bits)), nearest);
+                assert!(nearest <= 4);
+            }
+        }
+        assert_eq!(nearest_level(-1.0, 5), 0);
+        assert_eq!(nearest_level(300.0, 6), 63);
+        assert_eq!(nearest_level(f32::NAN, 5), 0);
     }
 
     #[cfg(not(feature = "big-endian"))]
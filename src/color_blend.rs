@@ -0,0 +1,225 @@
+//! Colors with transparency, and blend modes for compositing them.
+//!
+//! `RgbaColor` has a straight, not premultiplied, alpha channel, where `0` is fully
+//! transparent, and `0xFF` is fully opaque. Blending is done in 8-bit integer arithmetic, with
+//! rounding, so that blending with a fully transparent color leaves the backdrop unchanged.
+use super::PixelColor;
+use std::fmt;
+
+/// 32-bit RGBA color, a `PixelColor` with an alpha channel.
+///
+/// ```
+/// # extern crate sensehat_screen;
+/// # use sensehat_screen::{BlendMode, PixelColor, RgbaColor};
+/// # fn main() {
+///     let glass = RgbaColor::from_color(PixelColor::RED, 0x80);
+///     assert_eq!(glass.over_color(PixelColor::BLUE), PixelColor::new(0x80, 0, 0x7F));
+///     assert_eq!(RgbaColor::TRANSPARENT.over_color(PixelColor::BLUE), PixelColor::BLUE);
+///
+///     let light = RgbaColor::from(PixelColor::new(0, 0x80, 0xFF));
+///     assert_eq!(BlendMode::Add.blend(PixelColor::new(0x40, 0x80, 0x20), light),
+///                PixelColor::new(0x40, 0xFF, 0xFF));
+/// # }
+/// ```
+#[derive(Copy, Clone, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+pub struct RgbaColor {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: u8,
+}
+
+impl fmt::Debug for RgbaColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "#{:02X}{:02X}{:02X}{:02X}",
+               self.red, self.green, self.blue, self.alpha)
+    }
+}
+
+impl RgbaColor {
+    /// A fully transparent black.
+    pub const TRANSPARENT: RgbaColor = RgbaColor { red: 0,
+                                                   green: 0,
+                                                   blue: 0,
+                                                   alpha: 0, };
+
+    /// Create a new color with transparency.
+    pub fn new(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        Self { red,
+               green,
+               blue,
+               alpha, }
+    }
+
+    /// Create a new color from a `PixelColor`, and an alpha value.
+    pub fn from_color(color: PixelColor, alpha: u8) -> Self {
+        RgbaColor::new(color.red, color.green, color.blue, alpha)
+    }
+
+    /// Returns the red, green, and blue channels of this color, ignoring its alpha.
+    pub fn color(self) -> PixelColor {
+        PixelColor::new(self.red, self.green, self.blue)
+    }
+
+    /// Composites this color over `below`, with the Porter–Duff "over" operator. The result
+    /// is only transparent where both colors are.
+    pub fn over(self, below: RgbaColor) -> RgbaColor {
+        let (source, backdrop) = (u32::from(self.alpha), u32::from(below.alpha));
+        // the alpha of the result, scaled by `255 * 255`.
+        let alpha = source * 255 + backdrop * (255 - source);
+        if alpha == 0 {
+            return RgbaColor::TRANSPARENT;
+        }
+        let channel = |top: u8, bottom: u8| {
+            let sum = u32::from(top) * source * 255 + u32::from(bottom) * backdrop * (255 - source);
+            ((sum + alpha / 2) / alpha) as u8
+        };
+        RgbaColor::new(channel(self.red, below.red),
+                       channel(self.green, below.green),
+                       channel(self.blue, below.blue),
+                       ((alpha + 127) / 255) as u8)
+    }
+
+    /// Composites this color over an opaque `backdrop`. Same as blending with
+    /// `BlendMode::Normal`.
+    pub fn over_color(self, backdrop: PixelColor) -> PixelColor {
+        BlendMode::Normal.blend(backdrop, self)
+    }
+}
+
+impl From<PixelColor> for RgbaColor {
+    fn from(color: PixelColor) -> Self {
+        RgbaColor::from_color(color, 0xFF)
+    }
+}
+
+impl From<(u8, u8, u8, u8)> for RgbaColor {
+    fn from(color: (u8, u8, u8, u8)) -> Self {
+        RgbaColor::new(color.0, color.1, color.2, color.3)
+    }
+}
+
+impl From<RgbaColor> for (u8, u8, u8, u8) {
+    fn from(color: RgbaColor) -> Self {
+        (color.red, color.green, color.blue, color.alpha)
+    }
+}
+
+/// How the channels of a source color are combined with those of the backdrop it is
+/// drawn over. The result is then mixed with the backdrop by the alpha of the source.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum BlendMode {
+    /// The source replaces the backdrop, as with the Porter–Duff "over" operator.
+    #[default]
+    Normal,
+    /// The channels are added, and saturate at `0xFF`. Lightens, like overlapping lights.
+    Add,
+    /// The channels are multiplied. Darkens, like overlapping filters.
+    Multiply,
+    /// The inverted channels are multiplied, and inverted back. Lightens, never saturating.
+    Screen,
+    /// The absolute difference of the channels. Blending the same color gives black.
+    Difference,
+}
+
+impl BlendMode {
+    /// Blend a `source` color over an opaque `backdrop`.
+    pub fn blend(self, backdrop: PixelColor, source: RgbaColor) -> PixelColor {
+        let alpha = u32::from(source.alpha);
+        let channel = |bottom: u8, top: u8| {
+            let blended = u32::from(self.blend_channel(bottom, top));
+            ((u32::from(bottom) * (255 - alpha) + blended * alpha + 127) / 255) as u8
+        };
+        PixelColor::new(channel(backdrop.red, source.red),
+                        channel(backdrop.green, source.green),
+                        channel(backdrop.blue, source.blue))
+    }
+
+    // Blend a single, opaque, channel.
+    fn blend_channel(self, bottom: u8, top: u8) -> u8 {
+        match self {
+            BlendMode::Normal => top,
+            BlendMode::Add => bottom.saturating_add(top),
+            BlendMode::Multiply => multiply(bottom, top),
+            BlendMode::Screen => 255 - multiply(255 - bottom, 255 - top),
+            BlendMode::Difference => bottom.max(top) - bottom.min(top),
+        }
+    }
+}
+
+// Multiplies two channels, as if they were in the `0.0..=1.0` range, rounding to nearest.
+fn multiply(a: u8, b: u8) -> u8 {
+    ((u32::from(a) * u32::from(b) + 127) / 255) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [BlendMode; 5] = [BlendMode::Normal,
+                                   BlendMode::Add,
+                                   BlendMode::Multiply,
+                                   BlendMode::Screen,
+                                   BlendMode::Difference];
+
+    #[test]
+    fn opaque_colors_are_blended_by_each_mode() {
+        let backdrop = PixelColor::new(0x40, 0x80, 0xFF);
+        let source = RgbaColor::from(PixelColor::new(0xFF, 0x80, 0x40));
+        assert_eq!(BlendMode::Normal.blend(backdrop, source), PixelColor::new(0xFF, 0x80, 0x40));
+        assert_eq!(BlendMode::Add.blend(backdrop, source), PixelColor::new(0xFF, 0xFF, 0xFF));
+        assert_eq!(BlendMode::Multiply.blend(backdrop, source),
+                   PixelColor::new(0x40, 0x40, 0x40));
+        assert_eq!(BlendMode::Screen.blend(backdrop, source), PixelColor::new(0xFF, 0xC0, 0xFF));
+        assert_eq!(BlendMode::Difference.blend(backdrop, source),
+                   PixelColor::new(0xBF, 0x00, 0xBF));
+    }
+
+    #[test]
+    fn neutral_colors_leave_the_backdrop_unchanged() {
+        let backdrop = PixelColor::new(0x12, 0x9A, 0xED);
+        assert_eq!(BlendMode::Add.blend(backdrop, PixelColor::BLACK.into()), backdrop);
+        assert_eq!(BlendMode::Multiply.blend(backdrop, PixelColor::WHITE.into()), backdrop);
+        assert_eq!(BlendMode::Screen.blend(backdrop, PixelColor::BLACK.into()), backdrop);
+        assert_eq!(BlendMode::Difference.blend(backdrop, PixelColor::BLACK.into()), backdrop);
+        for &mode in &MODES {
+            assert_eq!(mode.blend(backdrop, RgbaColor::new(0xFF, 0x80, 0x40, 0)), backdrop);
+        }
+    }
+
+    #[test]
+    fn translucent_colors_are_mixed_with_the_backdrop() {
+        let half_white = RgbaColor::from_color(PixelColor::WHITE, 0x80);
+        assert_eq!(BlendMode::Normal.blend(PixelColor::BLACK, half_white),
+                   PixelColor::new(0x80, 0x80, 0x80));
+        assert_eq!(BlendMode::Difference.blend(PixelColor::WHITE, half_white),
+                   PixelColor::new(0x7F, 0x7F, 0x7F));
+        assert_eq!(half_white.over_color(PixelColor::RED), PixelColor::new(0xFF, 0x80, 0x80));
+    }
+
+    #[test]
+    fn porter_duff_over_combines_alpha() {
+        let red = RgbaColor::from_color(PixelColor::RED, 0x80);
+        let blue = RgbaColor::from_color(PixelColor::BLUE, 0x80);
+        assert_eq!(red.over(blue), RgbaColor::new(0xAA, 0, 0x55, 0xC0));
+        assert_eq!(red.over(PixelColor::BLUE.into()), RgbaColor::new(0x80, 0, 0x7F, 0xFF));
+        assert_eq!(red.over(RgbaColor::TRANSPARENT), red);
+        assert_eq!(RgbaColor::TRANSPARENT.over(blue), blue);
+        assert_eq!(RgbaColor::TRANSPARENT.over(RgbaColor::TRANSPARENT),
+                   RgbaColor::TRANSPARENT);
+        // over an opaque color, it is the same as the normal blend mode.
+        assert_eq!(red.over(PixelColor::GREEN.into()).color(),
+                   red.over_color(PixelColor::GREEN));
+    }
+
+    #[test]
+    fn rgba_colors_convert_to_and_from_tuples() {
+        let color = RgbaColor::from((1, 2, 3, 4));
+        assert_eq!(color, RgbaColor::new(1, 2, 3, 4));
+        assert_eq!(<(u8, u8, u8, u8)>::from(color), (1, 2, 3, 4));
+        assert_eq!(color.color(), PixelColor::new(1, 2, 3));
+        assert_eq!(format!("{:?}", color), "#01020304");
+    }
+}
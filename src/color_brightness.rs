@@ -0,0 +1,145 @@
+//! Perceptual brightness for `PixelColor`.
+//!
+//! Brightness is given as a CIE lightness, in the `0.0..=1.0` range, so that equal steps of
+//! brightness look like equal steps to the eye. Channels are scaled in linear light, which
+//! keeps the hue of a color, and rounded to the nearest level that the 5-6-5 LED Matrix
+//! displays, so that dim colors don't shift hue, or go dark, when `Rgb565` drops their low
+//! bits.
+use super::{expand, nearest_level, PixelColor};
+
+lazy_static! {
+    // The linear light of every sRGB channel value.
+    static ref LINEAR: [f32; 256] = {
+        let mut table = [0.0; 256];
+        for (value, linear) in table.iter_mut().enumerate() {
+            *linear = srgb_to_linear(value as f32 / 255.0);
+        }
+        table
+    };
+}
+
+impl PixelColor {
+    /// Returns this color at a perceptual `brightness`, where `1.0` is the color itself, and
+    /// `0.5` looks half as bright. Values outside the `0.0..=1.0` range are clamped.
+    ///
+    /// Unlike `dim`, which scales, and truncates, every channel, the result is rounded to the
+    /// nearest color that the LED Matrix displays.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::PixelColor;
+    /// # fn main() {
+    ///     assert_eq!(PixelColor::RED.with_brightness(1.0), PixelColor::RED);
+    ///     assert_eq!(PixelColor::RED.with_brightness(0.0), PixelColor::BLACK);
+    ///     // `dim` truncates this red to 5, which `Rgb565` shows as black.
+    ///     assert_eq!(PixelColor::RED.dim(0.02), PixelColor::new(5, 0, 0));
+    ///     assert_eq!(PixelColor::RED.with_brightness(0.02), PixelColor::new(8, 0, 0));
+    /// # }
+    /// ```
+    pub fn with_brightness(self, brightness: f32) -> PixelColor {
+        let luminance = lightness_to_luminance(brightness.clamp(0.0, 1.0));
+        let channel = |value: u8, bits: u32| {
+            let srgb = linear_to_srgb(to_linear(value) * luminance) * 255.0;
+            expand(nearest_level(srgb, bits), bits)
+        };
+        PixelColor::new(channel(self.red, 5), channel(self.green, 6), channel(self.blue, 5))
+    }
+}
+
+// The linear light of a channel value, in the `0.0..=1.0` range.
+pub(super) fn to_linear(value: u8) -> f32 {
+    LINEAR[value as usize]
+}
+
+// The channel value nearest to a linear light, in the `0.0..=1.0` range.
+pub(super) fn from_linear(linear: f32) -> u8 {
+    (linear_to_srgb(linear.clamp(0.0, 1.0)) * 255.0).round() as u8
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(linear: f32) -> f32 {
+    if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// The relative luminance of a CIE lightness, both in the `0.0..=1.0` range.
+fn lightness_to_luminance(lightness: f32) -> f32 {
+    let lightness = lightness * 100.0;
+    if lightness > 8.0 {
+        ((lightness + 16.0) / 116.0).powi(3)
+    } else {
+        lightness / 903.3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use color::hsv::tests::hue_distance;
+    use color::Rgb565;
+
+    #[test]
+    fn full_brightness_keeps_displayable_colors() {
+        for &color in &[PixelColor::WHITE,
+                        PixelColor::RED,
+                        PixelColor::GREEN,
+                        PixelColor::BLUE,
+                        PixelColor::YELLOW,
+                        PixelColor::CYAN,
+                        PixelColor::MAGENTA,
+                        PixelColor::BLACK]
+        {
+            assert_eq!(color.with_brightness(1.0), color);
+            assert_eq!(color.with_brightness(2.0), color);
+            assert_eq!(color.with_brightness(0.0), PixelColor::BLACK);
+        }
+    }
+
+    #[test]
+    fn brightness_follows_cie_lightness() {
+        // a lightness of 50% is the middle gray of CIE L*a*b*, at sRGB 119.
+        let gray = PixelColor::WHITE.with_brightness(0.5);
+        assert_eq!(gray, PixelColor::new(115, 117, 115));
+        let mut previous = PixelColor::BLACK;
+        for step in 1..=20 {
+            let color = PixelColor::WHITE.with_brightness(step as f32 / 20.0);
+            assert!(color.green > previous.green);
+            previous = color;
+        }
+    }
+
+    #[test]
+    fn hue_is_preserved_across_rgb565_quantization() {
+        for hue in (0..360).step_by(15) {
+            let color = PixelColor::from_hsv(hue as f32, 1.0, 1.0);
+            let (expected, _, _) = color.to_hsv();
+            for &(brightness, tolerance) in &[(0.25, 8.0), (0.5, 3.0), (0.75, 3.0), (1.0, 1.5)] {
+                let displayed = PixelColor::from(Rgb565::from(color.with_brightness(brightness)));
+                let (hue, _, _) = displayed.to_hsv();
+                assert!(hue_distance(hue, expected) <= tolerance,
+                        "hue {} shifted to {} at brightness {}",
+                        expected,
+                        hue,
+                        brightness);
+            }
+        }
+    }
+
+    #[test]
+    fn dim_colors_stay_lit() {
+        for &color in &[PixelColor::RED, PixelColor::GREEN, PixelColor::BLUE] {
+            let displayed = PixelColor::from(Rgb565::from(color.with_brightness(0.02)));
+            assert_ne!(displayed, PixelColor::BLACK);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Pixel formats of framebuffer devices.
+//!
+//! The Sense HAT driver uses RGB565, but emulated, or alternative, LED framebuffers may use
+//! another layout. `PixelFormat` is negotiated at runtime, from the bits-per-pixel, and the
+//! offset and length of the red, green, and blue channels reported by the device. Pixels are
+//! stored in the native byte order of the machine, as the kernel does.
+use super::{PixelColor, Rgb565};
+
+/// The layout of a pixel in the memory of a framebuffer device.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum PixelFormat {
+    /// 16 bits per pixel, with red in the most significant bits. Used by the Sense HAT.
+    #[default]
+    Rgb565,
+    /// 16 bits per pixel, with blue in the most significant bits.
+    Bgr565,
+    /// 24 bits per pixel, with 8 bits for each of red, green, and blue.
+    Rgb888,
+    /// 32 bits per pixel, with 8 bits for each of red, green, and blue, and 8 unused bits.
+    Xrgb8888,
+}
+
+impl PixelFormat {
+    /// Find the pixel format given its bits-per-pixel, and the `(offset, length)` of its red,
+    /// green, and blue channels, as found in the variable screen information of a framebuffer.
+    ///
+    /// A 16-bit device that leaves every channel empty is taken to be RGB565. Returns `None`
+    /// for any layout that is not supported.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::PixelFormat;
+    /// # fn main() {
+    ///     let format = PixelFormat::negotiate(32, (16, 8), (8, 8), (0, 8));
+    ///     assert_eq!(format, Some(PixelFormat::Xrgb8888));
+    ///     assert_eq!(PixelFormat::negotiate(12, (8, 4), (4, 4), (0, 4)), None);
+    /// # }
+    /// ```
+    pub fn negotiate(bits_per_pixel: u32,
+                     red: (u32, u32),
+                     green: (u32, u32),
+                     blue: (u32, u32))
+                     -> Option<Self> {
+        match (bits_per_pixel, red, green, blue) {
+            (16, (0, 0), (0, 0), (0, 0)) | (16, (11, 5), (5, 6), (0, 5)) => {
+                Some(PixelFormat::Rgb565)
+            }
+            (16, (0, 5), (5, 6), (11, 5)) => Some(PixelFormat::Bgr565),
+            (24, (16, 8), (8, 8), (0, 8)) => Some(PixelFormat::Rgb888),
+            (32, (16, 8), (8, 8), (0, 8)) => Some(PixelFormat::Xrgb8888),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of bits used by each pixel.
+    pub fn bits_per_pixel(self) -> u32 {
+        self.bytes_per_pixel() as u32 * 8
+    }
+
+    /// Returns the number of bytes used by each pixel.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Rgb565 | PixelFormat::Bgr565 => 2,
+            PixelFormat::Rgb888 => 3,
+            PixelFormat::Xrgb8888 => 4,
+        }
+    }
+
+    /// Returns the number of bytes used by a frame of 64 pixels.
+    pub fn frame_bytes(self) -> usize {
+        self.bytes_per_pixel() * 64
+    }
+
+    /// Encode a color into the first `bytes_per_pixel` bytes of `bytes`.
+    ///
+    /// # Panics
+    /// If `bytes` is shorter than `bytes_per_pixel`.
+    pub fn encode(self, color: Rgb565, bytes: &mut [u8]) {
+        self.encode_color(PixelColor::from(color), bytes);
+    }
+
+    /// Encode a `PixelColor` into the first `bytes_per_pixel` bytes of `bytes`. Formats with
+    /// 8 bits per channel keep every bit of the color.
+    ///
+    /// # Panics
+    /// If `bytes` is shorter than `bytes_per_pixel`.
+    pub fn encode_color(self, color: PixelColor, bytes: &mut [u8]) {
+        let size = self.bytes_per_pixel();
+        let value = self.pixel_value(color);
+        let native = value.to_ne_bytes();
+        bytes[..size].copy_from_slice(native_bytes(&native, size));
+    }
+
+    /// Decode a color from the first `bytes_per_pixel` bytes of `bytes`.
+    ///
+    /// # Panics
+    /// If `bytes` is shorter than `bytes_per_pixel`.
+    pub fn decode(self, bytes: &[u8]) -> Rgb565 {
+        let size = self.bytes_per_pixel();
+        let mut native = [0u8; 4];
+        let start = if cfg!(target_endian = "little") { 0 } else { 4 - size };
+        native[start..start + size].copy_from_slice(&bytes[..size]);
+        let value = u32::from_ne_bytes(native);
+        match self {
+            PixelFormat::Rgb565 => Rgb565::from(value as u16),
+            PixelFormat::Bgr565 => {
+                let red = (value & 0x1F) as u8;
+                let green = ((value >> 5) & 0x3F) as u8;
+                let blue = ((value >> 11) & 0x1F) as u8;
+                Rgb565::from_rgb(red << 3, green << 2, blue << 3)
+            }
+            PixelFormat::Rgb888 | PixelFormat::Xrgb8888 => {
+                Rgb565::from_rgb((value >> 16) as u8, (value >> 8) as u8, value as u8)
+            }
+        }
+    }
+
+    // The value of the pixel, in the least significant bits of a `u32`.
+    fn pixel_value(self, color: PixelColor) -> u32 {
+        let (red, green, blue) = color.into();
+        let (red, green, blue) = (u32::from(red), u32::from(green), u32::from(blue));
+        match self {
+            PixelFormat::Rgb565 => u32::from(u16::from(Rgb565::from(color))),
+            PixelFormat::Bgr565 => ((blue >> 3) << 11) | ((green >> 2) << 5) | (red >> 3),
+            PixelFormat::Rgb888 | PixelFormat::Xrgb8888 => (red << 16) | (green << 8) | blue,
+        }
+    }
+}
+
+// The `size` bytes of a native-endian `u32` that hold its least significant bits.
+fn native_bytes(native: &[u8; 4], size: usize) -> &[u8] {
+    if cfg!(target_endian = "little") {
+        &native[..size]
+    } else {
+        &native[4 - size..]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMATS: [PixelFormat; 4] = [PixelFormat::Rgb565,
+                                       PixelFormat::Bgr565,
+                                       PixelFormat::Rgb888,
+                                       PixelFormat::Xrgb8888];
+
+    #[test]
+    fn pixel_format_is_negotiated_from_channel_layout() {
+        assert_eq!(PixelFormat::negotiate(16, (11, 5), (5, 6), (0, 5)),
+                   Some(PixelFormat::Rgb565));
+        assert_eq!(PixelFormat::negotiate(16, (0, 5), (5, 6), (11, 5)),
+                   Some(PixelFormat::Bgr565));
+        assert_eq!(PixelFormat::negotiate(24, (16, 8), (8, 8), (0, 8)),
+                   Some(PixelFormat::Rgb888));
+        assert_eq!(PixelFormat::negotiate(32, (16, 8), (8, 8), (0, 8)),
+                   Some(PixelFormat::Xrgb8888));
+    }
+
+    #[test]
+    fn pixel_format_defaults_to_rgb565_for_empty_16_bit_channels() {
+        assert_eq!(PixelFormat::negotiate(16, (0, 0), (0, 0), (0, 0)),
+                   Some(PixelFormat::Rgb565));
+        assert_eq!(PixelFormat::negotiate(32, (0, 0), (0, 0), (0, 0)), None);
+    }
+
+    #[test]
+    fn pixel_format_rejects_unsupported_layouts() {
+        assert_eq!(PixelFormat::negotiate(32, (0, 8), (8, 8), (16, 8)), None);
+        assert_eq!(PixelFormat::negotiate(8, (5, 3), (2, 3), (0, 2)), None);
+    }
+
+    #[test]
+    fn pixel_format_sizes() {
+        let sizes = FORMATS.iter()
+                           .map(|format| (format.bits_per_pixel(), format.frame_bytes()))
+                           .collect::<Vec<_>>();
+        assert_eq!(sizes, vec![(16, 128), (16, 128), (24, 192), (32, 256)]);
+    }
+
+    #[test]
+    fn pixel_format_encodes_native_endian_pixel_values() {
+        let color = Rgb565::from_rgb(0xF8, 0x04, 0x10);
+        let mut bytes = [0u8; 4];
+        PixelFormat::Rgb565.encode(color, &mut bytes);
+        assert_eq!(&bytes[..2], &0xF822u16.to_ne_bytes()[..]);
+        PixelFormat::Bgr565.encode(color, &mut bytes);
+        assert_eq!(&bytes[..2], &0x103Fu16.to_ne_bytes()[..]);
+        PixelFormat::Xrgb8888.encode(color, &mut bytes);
+        assert_eq!(bytes, 0x00FF_0410u32.to_ne_bytes());
+    }
+
+    #[test]
+    fn pixel_format_encodes_rgb888_in_three_bytes() {
+        let mut bytes = [0xAAu8; 4];
+        PixelFormat::Rgb888.encode(Rgb565::from_rgb(0xF8, 0x04, 0x10), &mut bytes);
+        if cfg!(target_endian = "little") {
+            assert_eq!(bytes, [0x10, 0x04, 0xFF, 0xAA]);
+        } else {
+            assert_eq!(bytes, [0xFF, 0x04, 0x10, 0xAA]);
+        }
+    }
+
+    #[test]
+    fn pixel_format_encodes_every_bit_of_a_pixel_color() {
+        let color = PixelColor::new(0x12, 0x34, 0x56);
+        let mut bytes = [0u8; 4];
+        PixelFormat::Xrgb8888.encode_color(color, &mut bytes);
+        assert_eq!(bytes, 0x0012_3456u32.to_ne_bytes());
+        PixelFormat::Rgb565.encode_color(color, &mut bytes);
+        assert_eq!(PixelFormat::Rgb565.decode(&bytes), Rgb565::from(color));
+    }
+
+    #[test]
+    fn pixel_format_decodes_what_it_encodes() {
+        let colors = [Rgb565::from_rgb(0xF8, 0, 0),
+                      Rgb565::from_rgb(0, 0xFC, 0),
+                      Rgb565::from_rgb(0, 0, 0xF8),
+                      Rgb565::from_rgb(0x88, 0x44, 0x20)];
+        for &format in &FORMATS {
+            for &color in &colors {
+                let mut bytes = [0u8; 4];
+                format.encode(color, &mut bytes);
+                assert_eq!(format.decode(&bytes), color, "{:?}", format);
+            }
+        }
+    }
+}
@@ -0,0 +1,253 @@
+//! Color gradients with multiple stops.
+//!
+//! A `Gradient` maps a position, in the `0.0..=1.0` range, to a color, interpolating between
+//! the two stops around it in the `ColorSpace` of the gradient.
+use super::brightness::{from_linear, to_linear};
+use super::{unit, PixelColor};
+
+/// The color space that a `Gradient` interpolates in.
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ColorSpace {
+    /// Each of the red, green, and blue channels is interpolated on its own.
+    #[default]
+    Rgb,
+    /// Hue, saturation, and value are interpolated, going around the shorter way of the hue
+    /// circle. Keeps colors saturated, such as for rainbows.
+    Hsv,
+    /// The channels are interpolated in linear light, which looks more even than `Rgb`, and
+    /// doesn't go darker in the middle.
+    LinearLight,
+}
+
+impl ColorSpace {
+    /// Returns the color at `t` of the way from `start` to `end`, with `t` clamped to the
+    /// `0.0..=1.0` range.
+    pub fn interpolate(self, start: PixelColor, end: PixelColor, t: f32) -> PixelColor {
+        let t = unit(t);
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        match self {
+            ColorSpace::Rgb => PixelColor::lerp(start, end, t),
+            ColorSpace::Hsv => {
+                let (mut start_hue, mut start_saturation, start_value) = start.to_hsv();
+                let (mut end_hue, mut end_saturation, end_value) = end.to_hsv();
+                // grays have no hue, and black no saturation, so they take those of the
+                // other color.
+                if start_saturation == 0.0 {
+                    start_hue = end_hue;
+                } else if end_saturation == 0.0 {
+                    end_hue = start_hue;
+                }
+                if start_value == 0.0 {
+                    start_saturation = end_saturation;
+                } else if end_value == 0.0 {
+                    end_saturation = start_saturation;
+                }
+                let turn = (end_hue - start_hue + 540.0) % 360.0 - 180.0;
+                PixelColor::from_hsv(start_hue + turn * t,
+                                     lerp(start_saturation, end_saturation),
+                                     lerp(start_value, end_value))
+            }
+            ColorSpace::LinearLight => {
+                let channel = |a: u8, b: u8| from_linear(lerp(to_linear(a), to_linear(b)));
+                PixelColor::new(channel(start.red, end.red),
+                                channel(start.green, end.green),
+                                channel(start.blue, end.blue))
+            }
+        }
+    }
+}
+
+/// A color gradient, with one or more color stops, at positions in the `0.0..=1.0` range.
+///
+/// ```
+/// # extern crate sensehat_screen;
+/// # use sensehat_screen::{ColorSpace, Gradient, PixelColor};
+/// # fn main() {
+///     let gradient = Gradient::new(&[PixelColor::BLACK, PixelColor::WHITE]);
+///     assert_eq!(gradient.sample(0.5), PixelColor::new(128, 128, 128));
+///
+///     let gradient = gradient.with_space(ColorSpace::LinearLight);
+///     assert_eq!(gradient.sample(0.5), PixelColor::new(188, 188, 188));
+///
+///     let temperature = Gradient::temperature();
+///     assert_eq!(temperature.sample(0.0), PixelColor::GREEN);
+///     assert_eq!(temperature.sample(0.5), PixelColor::YELLOW);
+///     assert_eq!(temperature.sample(1.0), PixelColor::RED);
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(f32, PixelColor)>,
+    space: ColorSpace,
+}
+
+impl Gradient {
+    /// Create a new gradient, interpolated in RGB, with its colors evenly spaced.
+    ///
+    /// # Panics
+    /// If `colors` is empty.
+    pub fn new(colors: &[PixelColor]) -> Self {
+        assert!(!colors.is_empty(), "a gradient needs at least one color");
+        let last = (colors.len() - 1).max(1) as f32;
+        let stops = colors.iter()
+                          .enumerate()
+                          .map(|(index, &color)| (index as f32 / last, color))
+                          .collect::<Vec<(f32, PixelColor)>>();
+        Gradient::from_stops(&stops)
+    }
+
+    /// Create a new gradient, interpolated in RGB, from `(position, color)` stops, in any
+    /// order. Positions are clamped to the `0.0..=1.0` range. Stops at the same position
+    /// make a hard edge.
+    ///
+    /// # Panics
+    /// If `stops` is empty.
+    pub fn from_stops(stops: &[(f32, PixelColor)]) -> Self {
+        assert!(!stops.is_empty(), "a gradient needs at least one color");
+        let mut stops = stops.iter()
+                             .map(|&(position, color)| (unit(position), color))
+                             .collect::<Vec<(f32, PixelColor)>>();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Gradient { stops,
+                   space: ColorSpace::Rgb, }
+    }
+
+    /// A rainbow, from red, through yellow, green, cyan, blue, and magenta, back to red,
+    /// interpolated in HSV.
+    pub fn rainbow() -> Self {
+        Gradient::new(&[PixelColor::RED,
+                        PixelColor::YELLOW,
+                        PixelColor::GREEN,
+                        PixelColor::CYAN,
+                        PixelColor::BLUE,
+                        PixelColor::MAGENTA,
+                        PixelColor::RED]).with_space(ColorSpace::Hsv)
+    }
+
+    /// A temperature scale, from green, through yellow, to red.
+    pub fn temperature() -> Self {
+        Gradient::new(&[PixelColor::GREEN, PixelColor::YELLOW, PixelColor::RED])
+    }
+
+    /// Returns this gradient, interpolated in the given color space.
+    pub fn with_space(mut self, space: ColorSpace) -> Self {
+        self.space = space;
+        self
+    }
+
+    /// Returns the color space this gradient is interpolated in.
+    pub fn space(&self) -> ColorSpace {
+        self.space
+    }
+
+    /// Returns the `(position, color)` stops of this gradient, sorted by position.
+    pub fn stops(&self) -> &[(f32, PixelColor)] {
+        &self.stops
+    }
+
+    /// Returns the color at `position`, clamped to the `0.0..=1.0` range. Positions before
+    /// the first stop, or after the last, have the color of that stop.
+    pub fn sample(&self, position: f32) -> PixelColor {
+        let position = unit(position);
+        // the number of stops at, or before, the position.
+        let after = self.stops.partition_point(|stop| stop.0 <= position);
+        if after == 0 {
+            return self.stops[0].1;
+        }
+        if after == self.stops.len() {
+            return self.stops[after - 1].1;
+        }
+        let (start, end) = (self.stops[after - 1], self.stops[after]);
+        let t = (position - start.0) / (end.0 - start.0);
+        self.space.interpolate(start.1, end.1, t)
+    }
+
+    /// Returns `count` colors, evenly sampled from the start to the end of this gradient.
+    pub fn colors(&self, count: usize) -> Vec<PixelColor> {
+        let last = (count.max(2) - 1) as f32;
+        (0..count).map(|index| self.sample(index as f32 / last))
+                  .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_are_interpolated_in_each_space() {
+        let (red, blue) = (PixelColor::RED, PixelColor::BLUE);
+        assert_eq!(ColorSpace::Rgb.interpolate(red, blue, 0.5), PixelColor::new(128, 0, 128));
+        assert_eq!(ColorSpace::Hsv.interpolate(red, blue, 0.5), PixelColor::MAGENTA);
+        assert_eq!(ColorSpace::LinearLight.interpolate(red, blue, 0.5),
+                   PixelColor::new(188, 0, 188));
+        for &space in &[ColorSpace::Rgb, ColorSpace::Hsv, ColorSpace::LinearLight] {
+            assert_eq!(space.interpolate(red, blue, 0.0), red);
+            assert_eq!(space.interpolate(red, blue, 1.0), blue);
+            assert_eq!(space.interpolate(red, blue, -1.0), red);
+            assert_eq!(space.interpolate(red, blue, 2.0), blue);
+        }
+    }
+
+    #[test]
+    fn hsv_interpolation_takes_the_shorter_way_and_the_hue_of_colors() {
+        // from magenta to yellow, through red, not through cyan.
+        let magenta_to_yellow = ColorSpace::Hsv.interpolate(PixelColor::MAGENTA,
+                                                            PixelColor::YELLOW,
+                                                            0.5);
+        assert_eq!(magenta_to_yellow, PixelColor::RED);
+        let fade = ColorSpace::Hsv.interpolate(PixelColor::BLACK, PixelColor::GREEN, 0.5);
+        assert_eq!(fade, PixelColor::new(0, 128, 0));
+        let wash = ColorSpace::Hsv.interpolate(PixelColor::BLUE, PixelColor::WHITE, 0.5);
+        assert_eq!(wash, PixelColor::new(128, 128, 255));
+    }
+
+    #[test]
+    fn gradients_are_sampled_between_their_stops() {
+        let gradient = Gradient::from_stops(&[(1.0, PixelColor::WHITE),
+                                              (0.25, PixelColor::BLACK),
+                                              (0.75, PixelColor::RED)]);
+        assert_eq!(gradient.stops()[0], (0.25, PixelColor::BLACK));
+        assert_eq!(gradient.sample(0.0), PixelColor::BLACK);
+        assert_eq!(gradient.sample(0.25), PixelColor::BLACK);
+        assert_eq!(gradient.sample(0.5), PixelColor::new(128, 0, 0));
+        assert_eq!(gradient.sample(0.75), PixelColor::RED);
+        assert_eq!(gradient.sample(0.875), PixelColor::new(255, 128, 128));
+        assert_eq!(gradient.sample(1.5), PixelColor::WHITE);
+        assert_eq!(gradient.sample(f32::NAN), PixelColor::BLACK);
+    }
+
+    #[test]
+    fn stops_at_the_same_position_make_a_hard_edge() {
+        let gradient = Gradient::from_stops(&[(0.0, PixelColor::RED),
+                                              (0.5, PixelColor::RED),
+                                              (0.5, PixelColor::BLUE),
+                                              (1.0, PixelColor::BLUE)]);
+        assert_eq!(gradient.sample(0.49), PixelColor::RED);
+        assert_eq!(gradient.sample(0.5), PixelColor::BLUE);
+    }
+
+    #[test]
+    fn gradient_of_a_single_color_is_solid() {
+        let gradient = Gradient::new(&[PixelColor::CYAN]);
+        assert_eq!(gradient.colors(3), vec![PixelColor::CYAN; 3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn gradient_without_colors_panics() {
+        Gradient::new(&[]);
+    }
+
+    #[test]
+    fn rainbow_goes_around_the_hue_circle() {
+        let rainbow = Gradient::rainbow();
+        assert_eq!(rainbow.space(), ColorSpace::Hsv);
+        let colors = rainbow.colors(13);
+        for (index, color) in colors.iter().enumerate() {
+            let (hue, saturation, value) = color.to_hsv();
+            assert!((hue - (index as f32 * 30.0) % 360.0).abs() < 1.0);
+            assert_eq!((saturation, value), (1.0, 1.0));
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! HSV, and HSL, conversions for `PixelColor`.
+//!
+//! Hues are given in degrees, and wrap around, so that `-120.0`, `240.0`, and `600.0` are the
+//! same blue hue. Saturation, value, and lightness are given in the `0.0..=1.0` range, and
+//! values outside this range are clamped.
+use super::{unit, PixelColor};
+
+impl PixelColor {
+    /// Create a new LED pixel color from its hue, in degrees, saturation, and value.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::PixelColor;
+    /// # fn main() {
+    ///     assert_eq!(PixelColor::from_hsv(120.0, 1.0, 1.0), PixelColor::GREEN);
+    ///     assert_eq!(PixelColor::from_hsv(-60.0, 1.0, 1.0), PixelColor::MAGENTA);
+    ///     assert_eq!(PixelColor::from_hsv(0.0, 0.0, 0.5), PixelColor::new(128, 128, 128));
+    /// # }
+    /// ```
+    pub fn from_hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        let (saturation, value) = (unit(saturation), unit(value));
+        let chroma = value * saturation;
+        from_hue_chroma(hue, chroma, value - chroma)
+    }
+
+    /// Create a new LED pixel color from its hue, in degrees, saturation, and lightness.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::PixelColor;
+    /// # fn main() {
+    ///     assert_eq!(PixelColor::from_hsl(240.0, 1.0, 0.5), PixelColor::BLUE);
+    ///     assert_eq!(PixelColor::from_hsl(240.0, 1.0, 1.0), PixelColor::WHITE);
+    /// # }
+    /// ```
+    pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        let (saturation, lightness) = (unit(saturation), unit(lightness));
+        let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+        from_hue_chroma(hue, chroma, lightness - chroma / 2.0)
+    }
+
+    /// Returns the `(hue, saturation, value)` of this color, with the hue in the `0.0..360.0`
+    /// range. Grays have a hue, and saturation, of `0.0`.
+    pub fn to_hsv(self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+        (hue, saturation, max)
+    }
+
+    /// Returns the `(hue, saturation, lightness)` of this color, with the hue in the
+    /// `0.0..360.0` range. Grays have a hue, and saturation, of `0.0`.
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let (hue, max, min) = self.hue_max_min();
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+        (hue, unit(saturation), lightness)
+    }
+
+    /// Returns this color with its hue rotated by `degrees`, keeping its saturation, and
+    /// lightness. Useful for rainbow effects, and hue cycling.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::PixelColor;
+    /// # fn main() {
+    ///     assert_eq!(PixelColor::RED.rotate_hue(120.0), PixelColor::GREEN);
+    ///     assert_eq!(PixelColor::RED.rotate_hue(-120.0), PixelColor::BLUE);
+    /// # }
+    /// ```
+    pub fn rotate_hue(self, degrees: f32) -> PixelColor {
+        let (hue, saturation, lightness) = self.to_hsl();
+        PixelColor::from_hsl(hue + degrees, saturation, lightness)
+    }
+
+    /// Returns this color with `amount` added to its HSL saturation, which is clamped to
+    /// the `0.0..=1.0` range.
+    pub fn saturate(self, amount: f32) -> PixelColor {
+        let (hue, saturation, lightness) = self.to_hsl();
+        PixelColor::from_hsl(hue, saturation + amount, lightness)
+    }
+
+    /// Returns this color with `amount` taken from its HSL saturation, which is clamped to
+    /// the `0.0..=1.0` range. A color desaturated by `1.0` is a gray of the same lightness.
+    pub fn desaturate(self, amount: f32) -> PixelColor {
+        self.saturate(-amount)
+    }
+
+    // The hue, in degrees, and the greatest, and least, channels, in the `0.0..=1.0` range.
+    fn hue_max_min(self) -> (f32, f32, f32) {
+        let red = f32::from(self.red) / 255.0;
+        let green = f32::from(self.green) / 255.0;
+        let blue = f32::from(self.blue) / 255.0;
+        let max = red.max(green).max(blue);
+        let min = red.min(green).min(blue);
+        let delta = max - min;
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == red {
+            60.0 * ((green - blue) / delta)
+        } else if max == green {
+            60.0 * ((blue - red) / delta + 2.0)
+        } else {
+            60.0 * ((red - green) / delta + 4.0)
+        };
+        (wrap_hue(hue), max, min)
+    }
+}
+
+// The color with the given hue, chroma, and amount of gray added to every channel.
+fn from_hue_chroma(hue: f32, chroma: f32, gray: f32) -> PixelColor {
+    let sector = wrap_hue(hue) / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (red, green, blue) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let byte = |channel: f32| (unit(channel + gray) * 255.0).round() as u8;
+    PixelColor::new(byte(red), byte(green), byte(blue))
+}
+
+// Wraps a hue, in degrees, into the `0.0..360.0` range.
+fn wrap_hue(hue: f32) -> f32 {
+    let hue = hue.rem_euclid(360.0);
+    // `rem_euclid` may round up to the modulus for tiny negative hues.
+    if hue >= 360.0 {
+        0.0
+    } else {
+        hue
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use color::Rgb565;
+
+    const PRIMARIES: [(f32, PixelColor); 6] = [(0.0, PixelColor::RED),
+                                               (60.0, PixelColor::YELLOW),
+                                               (120.0, PixelColor::GREEN),
+                                               (180.0, PixelColor::CYAN),
+                                               (240.0, PixelColor::BLUE),
+                                               (300.0, PixelColor::MAGENTA)];
+
+    // The difference between two hues, in degrees, going the short way around.
+    pub(in color) fn hue_distance(a: f32, b: f32) -> f32 {
+        let distance = (a - b).abs() % 360.0;
+        distance.min(360.0 - distance)
+    }
+
+    #[test]
+    fn primaries_are_created_from_hsv_and_hsl() {
+        for &(hue, color) in &PRIMARIES {
+            assert_eq!(PixelColor::from_hsv(hue, 1.0, 1.0), color);
+            assert_eq!(PixelColor::from_hsl(hue, 1.0, 0.5), color);
+            assert_eq!(color.to_hsv(), (hue, 1.0, 1.0));
+            assert_eq!(color.to_hsl(), (hue, 1.0, 0.5));
+        }
+    }
+
+    #[test]
+    fn grays_have_no_hue_or_saturation() {
+        assert_eq!(PixelColor::BLACK.to_hsv(), (0.0, 0.0, 0.0));
+        assert_eq!(PixelColor::WHITE.to_hsv(), (0.0, 0.0, 1.0));
+        assert_eq!(PixelColor::WHITE.to_hsl(), (0.0, 0.0, 1.0));
+        assert_eq!(PixelColor::from_hsv(200.0, 0.0, 1.0), PixelColor::WHITE);
+        assert_eq!(PixelColor::from_hsl(200.0, 1.0, 0.0), PixelColor::BLACK);
+    }
+
+    #[test]
+    fn hues_wrap_around_and_values_are_clamped() {
+        assert_eq!(PixelColor::from_hsv(480.0, 1.0, 1.0), PixelColor::GREEN);
+        assert_eq!(PixelColor::from_hsv(-120.0, 1.0, 1.0), PixelColor::BLUE);
+        assert_eq!(PixelColor::from_hsv(-0.000_01, 1.0, 1.0), PixelColor::RED);
+        assert_eq!(PixelColor::from_hsv(0.0, 2.0, 3.0), PixelColor::RED);
+        assert_eq!(PixelColor::from_hsl(0.0, -1.0, 0.5), PixelColor::new(128, 128, 128));
+        assert_eq!(PixelColor::from_hsv(0.0, f32::NAN, 1.0), PixelColor::WHITE);
+        assert_eq!(PixelColor::from_hsl(0.0, 1.0, f32::NAN), PixelColor::BLACK);
+    }
+
+    #[test]
+    fn colors_round_trip_through_hsv_and_hsl() {
+        for red in (0..=255).step_by(15) {
+            for green in (0..=255).step_by(17) {
+                for blue in (0..=255).step_by(51) {
+                    let color = PixelColor::new(red, green, blue);
+                    let (h, s, v) = color.to_hsv();
+                    assert_eq!(PixelColor::from_hsv(h, s, v), color);
+                    let (h, s, l) = color.to_hsl();
+                    assert_eq!(PixelColor::from_hsl(h, s, l), color);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hsv_round_trips_through_rgb565_within_its_precision() {
+        // RGB565 drops the low 3 bits of red, and blue, and the low 2 bits of green, which
+        // `Rgb565::to_rgb` fills with the high bits, so the displayed color is darker, or
+        // brighter, by at most 7 levels, out of 255, on any channel.
+        for hue in (0..360).step_by(15) {
+            for &saturation in &[0.5, 0.75, 1.0] {
+                for &value in &[0.5, 0.75, 1.0] {
+                    let hue = hue as f32;
+                    let color = PixelColor::from_hsv(hue, saturation, value);
+                    let displayed = PixelColor::from(Rgb565::from(color));
+                    let (h, s, v) = displayed.to_hsv();
+                    assert!(hue_distance(h, hue) <= 8.0, "{} shifted to {}", hue, h);
+                    assert!((s - saturation).abs() <= 0.06, "{} became {}", saturation, s);
+                    assert!((v - value).abs() <= 8.0 / 255.0, "{} became {}", value, v);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hue_is_rotated() {
+        assert_eq!(PixelColor::RED.rotate_hue(120.0), PixelColor::GREEN);
+        assert_eq!(PixelColor::CYAN.rotate_hue(-180.0), PixelColor::RED);
+        let orange = PixelColor::new(255, 128, 0);
+        assert_eq!(orange.rotate_hue(360.0), orange);
+        assert_eq!(PixelColor::WHITE.rotate_hue(90.0), PixelColor::WHITE);
+    }
+
+    #[test]
+    fn saturation_is_adjusted() {
+        let muted = PixelColor::from_hsl(200.0, 0.25, 0.5);
+        assert_eq!(muted.saturate(0.5), PixelColor::from_hsl(200.0, 0.75, 0.5));
+        assert_eq!(muted.saturate(2.0), PixelColor::from_hsl(200.0, 1.0, 0.5));
+        assert_eq!(muted.desaturate(1.0), PixelColor::new(128, 128, 128));
+        assert_eq!(PixelColor::RED.desaturate(0.0), PixelColor::RED);
+    }
+}
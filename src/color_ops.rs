@@ -0,0 +1,264 @@
+//! Arithmetic, and interpolation, of `PixelColor`, and `Rgb565`.
+//!
+//! Every operation saturates, so that channels stay in their range instead of wrapping
+//! around. `Rgb565` is operated on directly in its 5-6-5 channels, without converting it to
+//! `PixelColor`, and back.
+use super::brightness::to_linear;
+use super::{expand, unit, PixelColor, Rgb565};
+use std::ops::{Add, Mul, Sub};
+
+impl PixelColor {
+    /// Returns the color `t` of the way from `start` to `end`, with `t` clamped to the
+    /// `0.0..=1.0` range. Interpolating between the pixels of two frames makes a fade, or a
+    /// cross-dissolve.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::PixelColor;
+    /// # fn main() {
+    ///     let orange = PixelColor::lerp(PixelColor::RED, PixelColor::YELLOW, 0.5);
+    ///     assert_eq!(orange, PixelColor::new(255, 128, 0));
+    ///     assert_eq!(PixelColor::RED + PixelColor::BLUE, PixelColor::MAGENTA);
+    ///     assert_eq!(PixelColor::WHITE - PixelColor::RED, PixelColor::CYAN);
+    ///     assert_eq!(PixelColor::new(100, 200, 50) * 1.5, PixelColor::new(150, 255, 75));
+    /// # }
+    /// ```
+    pub fn lerp(start: PixelColor, end: PixelColor, t: f32) -> PixelColor {
+        let t = unit(t);
+        let channel = |a: u8, b: u8| {
+            let (a, b) = (f32::from(a), f32::from(b));
+            (a + (b - a) * t).round() as u8
+        };
+        PixelColor::new(channel(start.red, end.red),
+                        channel(start.green, end.green),
+                        channel(start.blue, end.blue))
+    }
+
+    /// Returns the average of `colors`, rounded, or black if there are none.
+    pub fn average(colors: &[PixelColor]) -> PixelColor {
+        if colors.is_empty() {
+            return PixelColor::BLACK;
+        }
+        let sums = colors.iter().fold([0u64; 3], |sums, color| {
+                                    [sums[0] + u64::from(color.red),
+                                     sums[1] + u64::from(color.green),
+                                     sums[2] + u64::from(color.blue)]
+                                });
+        let count = colors.len() as u64;
+        let channel = |sum: u64| ((sum + count / 2) / count) as u8;
+        PixelColor::new(channel(sums[0]), channel(sums[1]), channel(sums[2]))
+    }
+
+    /// Returns the relative luminance of this color, in linear light, from `0.0` for black,
+    /// to `1.0` for white, with the Rec. 709 weights of sRGB.
+    pub fn luminance(self) -> f32 {
+        luminance(self.red, self.green, self.blue)
+    }
+}
+
+impl Add for PixelColor {
+    type Output = PixelColor;
+
+    fn add(self, other: PixelColor) -> PixelColor {
+        PixelColor::new(self.red.saturating_add(other.red),
+                        self.green.saturating_add(other.green),
+                        self.blue.saturating_add(other.blue))
+    }
+}
+
+impl Sub for PixelColor {
+    type Output = PixelColor;
+
+    fn sub(self, other: PixelColor) -> PixelColor {
+        PixelColor::new(self.red.saturating_sub(other.red),
+                        self.green.saturating_sub(other.green),
+                        self.blue.saturating_sub(other.blue))
+    }
+}
+
+/// Scales every channel, rounding, and saturating at `0xFF`. Negative factors give black.
+impl Mul<f32> for PixelColor {
+    type Output = PixelColor;
+
+    fn mul(self, factor: f32) -> PixelColor {
+        let channel = |value: u8| (f32::from(value) * factor).round().clamp(0.0, 255.0) as u8;
+        PixelColor::new(channel(self.red), channel(self.green), channel(self.blue))
+    }
+}
+
+// The greatest value of the red, green, and blue channels of `Rgb565`.
+const MAX_565: [u16; 3] = [0x1F, 0x3F, 0x1F];
+
+impl Rgb565 {
+    /// Returns the color `t` of the way from `start` to `end`, with `t` clamped to the
+    /// `0.0..=1.0` range, interpolating the 5-6-5 channels.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::color::Rgb565;
+    /// # fn main() {
+    ///     let red = Rgb565::from(0xF800);
+    ///     let blue = Rgb565::from(0x001F);
+    ///     assert_eq!(Rgb565::lerp(red, blue, 0.5), Rgb565::from(0x8010));
+    ///     assert_eq!(red + blue, Rgb565::from(0xF81F));
+    /// # }
+    /// ```
+    pub fn lerp(start: Rgb565, end: Rgb565, t: f32) -> Rgb565 {
+        let t = unit(t);
+        let (start, end) = (start.channels(), end.channels());
+        let channel = |index: usize| {
+            let (a, b) = (f32::from(start[index]), f32::from(end[index]));
+            (a + (b - a) * t).round() as u16
+        };
+        Rgb565::from_channels([channel(0), channel(1), channel(2)])
+    }
+
+    /// Returns the average of `colors`, rounded, or black if there are none.
+    pub fn average(colors: &[Rgb565]) -> Rgb565 {
+        if colors.is_empty() {
+            return Rgb565::default();
+        }
+        let mut sums = [0u64; 3];
+        for color in colors {
+            for (sum, &channel) in sums.iter_mut().zip(color.channels().iter()) {
+                *sum += u64::from(channel);
+            }
+        }
+        let count = colors.len() as u64;
+        Rgb565::from_channels(sums.map(|sum| ((sum + count / 2) / count) as u16))
+    }
+
+    /// Returns the relative luminance of this color, in linear light, from `0.0` for black,
+    /// to `1.0` for white.
+    pub fn luminance(self) -> f32 {
+        let [red, green, blue] = self.channels();
+        luminance(expand(red, 5), expand(green, 6), expand(blue, 5))
+    }
+
+    // The red, green, and blue channels, of 5, 6, and 5 bits.
+    fn channels(self) -> [u16; 3] {
+        [self.0 >> 11, (self.0 >> 5) & 0x3F, self.0 & 0x1F]
+    }
+
+    // Create `Rgb565` from channels, each clamped to its greatest value.
+    fn from_channels(channels: [u16; 3]) -> Rgb565 {
+        let [red, green, blue] = [0, 1, 2].map(|index| channels[index].min(MAX_565[index]));
+        Rgb565((red << 11) | (green << 5) | blue)
+    }
+}
+
+impl Add for Rgb565 {
+    type Output = Rgb565;
+
+    fn add(self, other: Rgb565) -> Rgb565 {
+        let (a, b) = (self.channels(), other.channels());
+        Rgb565::from_channels([0, 1, 2].map(|index| a[index] + b[index]))
+    }
+}
+
+impl Sub for Rgb565 {
+    type Output = Rgb565;
+
+    fn sub(self, other: Rgb565) -> Rgb565 {
+        let (a, b) = (self.channels(), other.channels());
+        Rgb565::from_channels([0, 1, 2].map(|index| a[index].saturating_sub(b[index])))
+    }
+}
+
+/// Scales every channel, rounding, and saturating at its greatest value. Negative factors give
+/// black.
+impl Mul<f32> for Rgb565 {
+    type Output = Rgb565;
+
+    fn mul(self, factor: f32) -> Rgb565 {
+        let channel = |value: u16| (f32::from(value) * factor).round().max(0.0) as u16;
+        Rgb565::from_channels(self.channels().map(channel))
+    }
+}
+
+// The relative luminance of the channels of a color, with the Rec. 709 weights of sRGB.
+fn luminance(red: u8, green: u8, blue: u8) -> f32 {
+    0.2126 * to_linear(red) + 0.7152 * to_linear(green) + 0.0722 * to_linear(blue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb565(red: u16, green: u16, blue: u16) -> Rgb565 {
+        Rgb565((red << 11) | (green << 5) | blue)
+    }
+
+    #[test]
+    fn colors_are_added_and_subtracted_with_saturation() {
+        let color = PixelColor::new(200, 100, 10);
+        assert_eq!(color + PixelColor::new(100, 100, 100), PixelColor::new(255, 200, 110));
+        assert_eq!(color - PixelColor::new(100, 200, 5), PixelColor::new(100, 0, 5));
+        assert_eq!(color + PixelColor::BLACK, color);
+        assert_eq!(color - color, PixelColor::BLACK);
+    }
+
+    #[test]
+    fn colors_are_scaled_with_rounding_and_saturation() {
+        let color = PixelColor::new(200, 101, 1);
+        assert_eq!(color * 0.5, PixelColor::new(100, 51, 1));
+        assert_eq!(color * 2.0, PixelColor::new(255, 202, 2));
+        assert_eq!(color * -1.0, PixelColor::BLACK);
+        assert_eq!(color * 1.0, color);
+    }
+
+    #[test]
+    fn colors_are_interpolated() {
+        let (start, end) = (PixelColor::new(0, 100, 255), PixelColor::new(255, 0, 0));
+        assert_eq!(PixelColor::lerp(start, end, 0.0), start);
+        assert_eq!(PixelColor::lerp(start, end, 0.25), PixelColor::new(64, 75, 191));
+        assert_eq!(PixelColor::lerp(start, end, 1.0), end);
+        assert_eq!(PixelColor::lerp(start, end, 7.0), end);
+        assert_eq!(PixelColor::lerp(start, end, f32::NAN), start);
+    }
+
+    #[test]
+    fn colors_are_averaged() {
+        let colors = [PixelColor::RED, PixelColor::GREEN, PixelColor::new(0, 1, 255)];
+        assert_eq!(PixelColor::average(&colors), PixelColor::new(85, 85, 85));
+        assert_eq!(PixelColor::average(&colors[..2]), PixelColor::new(128, 128, 0));
+        assert_eq!(PixelColor::average(&[]), PixelColor::BLACK);
+    }
+
+    #[test]
+    fn many_colors_are_averaged_without_overflow() {
+        // more than `u32::MAX / 255` colors.
+        let colors = vec![PixelColor::WHITE; (1 << 24) + (1 << 20)];
+        assert_eq!(PixelColor::average(&colors), PixelColor::WHITE);
+    }
+
+    #[test]
+    fn luminance_weighs_green_the_most() {
+        assert_eq!(PixelColor::BLACK.luminance(), 0.0);
+        assert!((PixelColor::WHITE.luminance() - 1.0).abs() < 1e-6);
+        assert!((PixelColor::GREEN.luminance() - 0.7152).abs() < 1e-6);
+        assert!(PixelColor::BLUE.luminance() < PixelColor::RED.luminance());
+        assert!(Rgb565::from(0x07E0).luminance() > Rgb565::from(0xF800).luminance());
+        let color = Rgb565::from(0x8A5C);
+        assert_eq!(color.luminance(), PixelColor::from(color).luminance());
+    }
+
+    #[test]
+    fn rgb565_operates_on_its_channels() {
+        let color = rgb565(16, 32, 1);
+        assert_eq!(color + color, rgb565(31, 63, 2));
+        assert_eq!(color - rgb565(1, 33, 0), rgb565(15, 0, 1));
+        assert_eq!(color * 0.5, rgb565(8, 16, 1));
+        assert_eq!(color * 4.0, rgb565(31, 63, 4));
+        assert_eq!(color * -1.0, Rgb565::default());
+    }
+
+    #[test]
+    fn rgb565_is_interpolated_and_averaged() {
+        let (black, white) = (Rgb565::from(0x0000), Rgb565::from(0xFFFF));
+        assert_eq!(Rgb565::lerp(black, white, 0.5), rgb565(16, 32, 16));
+        assert_eq!(Rgb565::lerp(black, white, 2.0), white);
+        assert_eq!(Rgb565::average(&[black, white, white]), rgb565(21, 42, 21));
+        assert_eq!(Rgb565::average(&[]), black);
+    }
+}
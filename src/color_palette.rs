@@ -0,0 +1,213 @@
+//! Palettes of up to 256 colors, with presets.
+//!
+//! A `Palette` gives the colors of the indices of an `IndexedFrame`. Changing, or cycling, the
+//! colors of a palette changes every pixel that uses them, without redrawing the frame.
+use super::PixelColor;
+use std::ops::{Index, Range};
+
+/// An ordered palette of up to 256 colors, each at a `u8` index.
+///
+/// ```
+/// # extern crate sensehat_screen;
+/// # use sensehat_screen::{Palette, PixelColor};
+/// # fn main() {
+///     let mut palette = Palette::new(&[PixelColor::BLACK, PixelColor::RED, PixelColor::BLUE]);
+///     assert_eq!(palette[1], PixelColor::RED);
+///     assert_eq!(palette.nearest(PixelColor::new(0, 0, 200)), 2);
+///
+///     palette.cycle(1..3, 1);
+///     assert_eq!(palette.colors(), &[PixelColor::BLACK, PixelColor::BLUE, PixelColor::RED]);
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Palette {
+    colors: Vec<PixelColor>,
+}
+
+impl Palette {
+    /// Index of the unlit color of `Palette::status`.
+    pub const STATUS_OFF: u8 = 0;
+    /// Index of the "ok" color of `Palette::status`.
+    pub const STATUS_OK: u8 = 1;
+    /// Index of the "warning" color of `Palette::status`.
+    pub const STATUS_WARNING: u8 = 2;
+    /// Index of the "error" color of `Palette::status`.
+    pub const STATUS_ERROR: u8 = 3;
+    /// Index of the "info" color of `Palette::status`.
+    pub const STATUS_INFO: u8 = 4;
+
+    /// Create a new palette with the given colors.
+    ///
+    /// # Panics
+    /// If there are more than 256 colors.
+    pub fn new(colors: &[PixelColor]) -> Self {
+        assert!(colors.len() <= 256,
+                "a palette has at most 256 colors, not {}",
+                colors.len());
+        Palette { colors: colors.to_vec() }
+    }
+
+    /// The 16 colors of the PICO-8 fantasy console.
+    pub fn pico8() -> Self {
+        Palette::from_hex(&[0x00_0000, 0x1D_2B53, 0x7E_2553, 0x00_8751, 0xAB_5236, 0x5F_574F,
+                            0xC2_C3C7, 0xFF_F1E8, 0xFF_004D, 0xFF_A300, 0xFF_EC27, 0x00_E436,
+                            0x29_ADFF, 0x83_769C, 0xFF_77A8, 0xFF_CCAA])
+    }
+
+    /// The 16 colors of the IBM CGA, in the order of their 4-bit codes.
+    pub fn cga() -> Self {
+        Palette::from_hex(&[0x00_0000, 0x00_00AA, 0x00_AA00, 0x00_AAAA, 0xAA_0000, 0xAA_00AA,
+                            0xAA_5500, 0xAA_AAAA, 0x55_5555, 0x55_55FF, 0x55_FF55, 0x55_FFFF,
+                            0xFF_5555, 0xFF_55FF, 0xFF_FF55, 0xFF_FFFF])
+    }
+
+    /// The 8 colors of Okabe, and Ito, that are told apart by people with any common form of
+    /// color blindness: black, orange, sky blue, bluish green, yellow, blue, vermillion, and
+    /// reddish purple.
+    pub fn okabe_ito() -> Self {
+        Palette::from_hex(&[0x00_0000, 0xE6_9F00, 0x56_B4E9, 0x00_9E73, 0xF0_E442, 0x00_72B2,
+                            0xD5_5E00, 0xCC_79A7])
+    }
+
+    /// Status colors, from the Okabe-Ito palette, that do not rely on telling red from green:
+    /// off, ok, warning, error, and info, at the `Palette::STATUS_*` indices.
+    pub fn status() -> Self {
+        Palette::from_hex(&[0x00_0000, 0x00_9E73, 0xF0_E442, 0xD5_5E00, 0x56_B4E9])
+    }
+
+    /// Returns the number of colors in this palette.
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// Returns `true` if this palette has no colors.
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    /// Returns the colors of this palette.
+    pub fn colors(&self) -> &[PixelColor] {
+        &self.colors
+    }
+
+    /// Returns the color at `index`, if there is one.
+    pub fn get(&self, index: u8) -> Option<PixelColor> {
+        self.colors.get(index as usize).cloned()
+    }
+
+    /// Sets the color at `index`.
+    ///
+    /// # Panics
+    /// If `index` is not in this palette.
+    pub fn set(&mut self, index: u8, color: PixelColor) {
+        self.colors[index as usize] = color;
+    }
+
+    /// Cycles the colors in `range` by `steps`, wrapping around, so that each color moves up
+    /// by `steps` indices, or down, if `steps` is negative. Cycling a palette animates every
+    /// pixel that uses its colors, such as flowing water, or a spinner.
+    ///
+    /// # Panics
+    /// If `range` is not in this palette.
+    pub fn cycle(&mut self, range: Range<usize>, steps: isize) {
+        let colors = &mut self.colors[range];
+        if colors.is_empty() {
+            return;
+        }
+        let steps = steps.rem_euclid(colors.len() as isize) as usize;
+        colors.rotate_right(steps);
+    }
+
+    /// Returns the index of the color nearest to `color`, by RGB distance, or `0` if this
+    /// palette is empty.
+    pub fn nearest(&self, color: PixelColor) -> u8 {
+        let distance = |other: &PixelColor| {
+            let channel = |a: u8, b: u8| (i32::from(a) - i32::from(b)).pow(2);
+            channel(color.red, other.red)
+            + channel(color.green, other.green)
+            + channel(color.blue, other.blue)
+        };
+        self.colors
+            .iter()
+            .enumerate()
+            .min_by_key(|&(_, other)| distance(other))
+            .map(|(index, _)| index as u8)
+            .unwrap_or(0)
+    }
+
+    fn from_hex(colors: &[u32]) -> Self {
+        let color = |rgb: u32| PixelColor::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8);
+        Palette { colors: colors.iter().map(|&rgb| color(rgb)).collect() }
+    }
+}
+
+impl Index<u8> for Palette {
+    type Output = PixelColor;
+
+    fn index(&self, index: u8) -> &PixelColor {
+        &self.colors[index as usize]
+    }
+}
+
+impl<'a> From<&'a [PixelColor]> for Palette {
+    fn from(colors: &'a [PixelColor]) -> Self {
+        Palette::new(colors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_have_their_colors() {
+        let pico8 = Palette::pico8();
+        assert_eq!(pico8.len(), 16);
+        assert_eq!(pico8[8], PixelColor::new(0xFF, 0x00, 0x4D));
+        let cga = Palette::cga();
+        assert_eq!(cga.len(), 16);
+        assert_eq!(cga[6], PixelColor::new(0xAA, 0x55, 0x00));
+        assert_eq!(cga[15], PixelColor::WHITE);
+        assert_eq!(Palette::okabe_ito().len(), 8);
+        let status = Palette::status();
+        assert_eq!(status[Palette::STATUS_OFF], PixelColor::BLACK);
+        assert_eq!(status[Palette::STATUS_ERROR], PixelColor::new(0xD5, 0x5E, 0x00));
+        assert_eq!(status.get(Palette::STATUS_INFO + 1), None);
+    }
+
+    #[test]
+    fn colors_are_cycled_in_a_range() {
+        let colors = Palette::cga().colors()[..6].to_vec();
+        let mut palette = Palette::new(&colors);
+        palette.cycle(1..4, 1);
+        assert_eq!(palette.colors(),
+                   &[colors[0], colors[3], colors[1], colors[2], colors[4], colors[5]]);
+        palette.cycle(1..4, -1);
+        assert_eq!(palette.colors(), &colors[..]);
+        palette.cycle(0..6, 12);
+        assert_eq!(palette.colors(), &colors[..]);
+        palette.cycle(2..2, 1);
+        assert_eq!(palette.colors(), &colors[..]);
+    }
+
+    #[test]
+    fn nearest_color_is_found() {
+        let palette = Palette::cga();
+        assert_eq!(palette.nearest(PixelColor::new(0xA0, 0x50, 0x10)), 6);
+        assert_eq!(palette.nearest(PixelColor::new(0xF0, 0xF0, 0xF0)), 15);
+        assert_eq!(Palette::default().nearest(PixelColor::RED), 0);
+    }
+
+    #[test]
+    fn colors_are_set() {
+        let mut palette = Palette::from(&[PixelColor::BLACK; 2][..]);
+        palette.set(1, PixelColor::CYAN);
+        assert_eq!(palette.get(1), Some(PixelColor::CYAN));
+    }
+
+    #[test]
+    #[should_panic]
+    fn palette_of_more_than_256_colors_panics() {
+        Palette::new(&[PixelColor::BLACK; 257]);
+    }
+}
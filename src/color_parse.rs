@@ -0,0 +1,364 @@
+//! Parsing of colors from strings, and their serde representation.
+//!
+//! Colors are written as `#RGB`, `#RRGGBB`, `rgb(r, g, b)`, or as one of the 148 named colors
+//! of CSS, which are mostly the same as the X11 color names. Names, and hex digits, are not
+//! case sensitive.
+use super::PixelColor;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// The error returned when a `PixelColor` could not be parsed from a string.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParseColorError {
+    reason: &'static str,
+    input: String,
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid color: {} {:?}", self.reason, self.input)
+    }
+}
+
+impl Error for ParseColorError {}
+
+/// Parses a color from its hex, `rgb()`, or CSS name, form.
+///
+/// ```
+/// # extern crate sensehat_screen;
+/// # use sensehat_screen::PixelColor;
+/// # fn main() {
+///     assert_eq!("#F80".parse::<PixelColor>().unwrap(), PixelColor::new(0xFF, 0x88, 0x00));
+///     assert_eq!("#1e90ff".parse::<PixelColor>().unwrap(), PixelColor::new(30, 144, 255));
+///     assert_eq!("rgb(0, 255, 0)".parse::<PixelColor>().unwrap(), PixelColor::GREEN);
+///     // the CSS `green` is only half as bright as `PixelColor::GREEN`, which is `lime`.
+///     assert_eq!("Green".parse::<PixelColor>().unwrap(), PixelColor::new(0, 0x80, 0));
+///     assert!("#12".parse::<PixelColor>().is_err());
+/// # }
+/// ```
+impl FromStr for PixelColor {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let lowercase = s.to_ascii_lowercase();
+        if let Some(hex) = s.strip_prefix('#') {
+            parse_hex(hex).ok_or_else(|| invalid("invalid hex color", s))
+        } else if let Some(channels) = lowercase.strip_prefix("rgb(")
+                                                .and_then(|rest| rest.strip_suffix(')'))
+        {
+            parse_channels(channels).ok_or_else(|| invalid("invalid rgb() color", s))
+        } else {
+            NAMES.binary_search_by(|&(name, _)| name.cmp(&lowercase))
+                 .map(|index| from_u32(NAMES[index].1))
+                 .map_err(|_| invalid("unknown color name", s))
+        }
+    }
+}
+
+// Parses the digits of a `#RGB`, or `#RRGGBB`, color.
+fn parse_hex(hex: &str) -> Option<PixelColor> {
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    match hex.len() {
+        // every digit is repeated, so that `F` is `FF`.
+        3 => Some(PixelColor::new(((value >> 8) & 0xF) as u8 * 0x11,
+                                  ((value >> 4) & 0xF) as u8 * 0x11,
+                                  (value & 0xF) as u8 * 0x11)),
+        6 => Some(from_u32(value)),
+        _ => None,
+    }
+}
+
+// Parses the three comma-separated channels of an `rgb(r, g, b)` color.
+fn parse_channels(channels: &str) -> Option<PixelColor> {
+    let channels = channels.split(',')
+                           .map(|channel| channel.trim().parse::<u8>().ok())
+                           .collect::<Option<Vec<u8>>>()?;
+    match channels[..] {
+        [red, green, blue] => Some(PixelColor::new(red, green, blue)),
+        _ => None,
+    }
+}
+
+fn from_u32(value: u32) -> PixelColor {
+    PixelColor::new((value >> 16) as u8, (value >> 8) as u8, value as u8)
+}
+
+fn invalid(reason: &'static str, s: &str) -> ParseColorError {
+    ParseColorError { reason,
+                      input: s.to_string(), }
+}
+
+#[cfg(feature = "serde-support")]
+mod serde_support {
+    use super::PixelColor;
+    use serde::de::{self, Visitor};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::fmt;
+
+    // Colors are serialized as `#RRGGBB` strings, and deserialized from any form that parses.
+    impl Serialize for PixelColor {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for PixelColor {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserializer.deserialize_str(ColorVisitor)
+        }
+    }
+
+    struct ColorVisitor;
+
+    impl<'de> Visitor<'de> for ColorVisitor {
+        type Value = PixelColor;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a color, as #RGB, #RRGGBB, rgb(r, g, b), or a CSS color name")
+        }
+
+        fn visit_str<E: de::Error>(self, s: &str) -> Result<PixelColor, E> {
+            s.parse().map_err(E::custom)
+        }
+    }
+}
+
+// The named colors of CSS, sorted by name.
+const NAMES: [(&str, u32); 148] = [("aliceblue", 0xF0F8FF),
+                                   ("antiquewhite", 0xFAEBD7),
+                                   ("aqua", 0x00FFFF),
+                                   ("aquamarine", 0x7FFFD4),
+                                   ("azure", 0xF0FFFF),
+                                   ("beige", 0xF5F5DC),
+                                   ("bisque", 0xFFE4C4),
+                                   ("black", 0x000000),
+                                   ("blanchedalmond", 0xFFEBCD),
+                                   ("blue", 0x0000FF),
+                                   ("blueviolet", 0x8A2BE2),
+                                   ("brown", 0xA52A2A),
+                                   ("burlywood", 0xDEB887),
+                                   ("cadetblue", 0x5F9EA0),
+                                   ("chartreuse", 0x7FFF00),
+                                   ("chocolate", 0xD2691E),
+                                   ("coral", 0xFF7F50),
+                                   ("cornflowerblue", 0x6495ED),
+                                   ("cornsilk", 0xFFF8DC),
+                                   ("crimson", 0xDC143C),
+                                   ("cyan", 0x00FFFF),
+                                   ("darkblue", 0x00008B),
+                                   ("darkcyan", 0x008B8B),
+                                   ("darkgoldenrod", 0xB8860B),
+                                   ("darkgray", 0xA9A9A9),
+                                   ("darkgreen", 0x006400),
+                                   ("darkgrey", 0xA9A9A9),
+                                   ("darkkhaki", 0xBDB76B),
+                                   ("darkmagenta", 0x8B008B),
+                                   ("darkolivegreen", 0x556B2F),
+                                   ("darkorange", 0xFF8C00),
+                                   ("darkorchid", 0x9932CC),
+                                   ("darkred", 0x8B0000),
+                                   ("darksalmon", 0xE9967A),
+                                   ("darkseagreen", 0x8FBC8F),
+                                   ("darkslateblue", 0x483D8B),
+                                   ("darkslategray", 0x2F4F4F),
+                                   ("darkslategrey", 0x2F4F4F),
+                                   ("darkturquoise", 0x00CED1),
+                                   ("darkviolet", 0x9400D3),
+                                   ("deeppink", 0xFF1493),
+                                   ("deepskyblue", 0x00BFFF),
+                                   ("dimgray", 0x696969),
+                                   ("dimgrey", 0x696969),
+                                   ("dodgerblue", 0x1E90FF),
+                                   ("firebrick", 0xB22222),
+                                   ("floralwhite", 0xFFFAF0),
+                                   ("forestgreen", 0x228B22),
+                                   ("fuchsia", 0xFF00FF),
+                                   ("gainsboro", 0xDCDCDC),
+                                   ("ghostwhite", 0xF8F8FF),
+                                   ("gold", 0xFFD700),
+                                   ("goldenrod", 0xDAA520),
+                                   ("gray", 0x808080),
+                                   ("green", 0x008000),
+                                   ("greenyellow", 0xADFF2F),
+                                   ("grey", 0x808080),
+                                   ("honeydew", 0xF0FFF0),
+                                   ("hotpink", 0xFF69B4),
+                                   ("indianred", 0xCD5C5C),
+                                   ("indigo", 0x4B0082),
+                                   ("ivory", 0xFFFFF0),
+                                   ("khaki", 0xF0E68C),
+                                   ("lavender", 0xE6E6FA),
+                                   ("lavenderblush", 0xFFF0F5),
+                                   ("lawngreen", 0x7CFC00),
+                                   ("lemonchiffon", 0xFFFACD),
+                                   ("lightblue", 0xADD8E6),
+                                   ("lightcoral", 0xF08080),
+                                   ("lightcyan", 0xE0FFFF),
+                                   ("lightgoldenrodyellow", 0xFAFAD2),
+                                   ("lightgray", 0xD3D3D3),
+                                   ("lightgreen", 0x90EE90),
+                                   ("lightgrey", 0xD3D3D3),
+                                   ("lightpink", 0xFFB6C1),
+                                   ("lightsalmon", 0xFFA07A),
+                                   ("lightseagreen", 0x20B2AA),
+                                   ("lightskyblue", 0x87CEFA),
+                                   ("lightslategray", 0x778899),
+                                   ("lightslategrey", 0x778899),
+                                   ("lightsteelblue", 0xB0C4DE),
+                                   ("lightyellow", 0xFFFFE0),
+                                   ("lime", 0x00FF00),
+                                   ("limegreen", 0x32CD32),
+                                   ("linen", 0xFAF0E6),
+                                   ("magenta", 0xFF00FF),
+                                   ("maroon", 0x800000),
+                                   ("mediumaquamarine", 0x66CDAA),
+                                   ("mediumblue", 0x0000CD),
+                                   ("mediumorchid", 0xBA55D3),
+                                   ("mediumpurple", 0x9370DB),
+                                   ("mediumseagreen", 0x3CB371),
+                                   ("mediumslateblue", 0x7B68EE),
+                                   ("mediumspringgreen", 0x00FA9A),
+                                   ("mediumturquoise", 0x48D1CC),
+                                   ("mediumvioletred", 0xC71585),
+                                   ("midnightblue", 0x191970),
+                                   ("mintcream", 0xF5FFFA),
+                                   ("mistyrose", 0xFFE4E1),
+                                   ("moccasin", 0xFFE4B5),
+                                   ("navajowhite", 0xFFDEAD),
+                                   ("navy", 0x000080),
+                                   ("oldlace", 0xFDF5E6),
+                                   ("olive", 0x808000),
+                                   ("olivedrab", 0x6B8E23),
+                                   ("orange", 0xFFA500),
+                                   ("orangered", 0xFF4500),
+                                   ("orchid", 0xDA70D6),
+                                   ("palegoldenrod", 0xEEE8AA),
+                                   ("palegreen", 0x98FB98),
+                                   ("paleturquoise", 0xAFEEEE),
+                                   ("palevioletred", 0xDB7093),
+                                   ("papayawhip", 0xFFEFD5),
+                                   ("peachpuff", 0xFFDAB9),
+                                   ("peru", 0xCD853F),
+                                   ("pink", 0xFFC0CB),
+                                   ("plum", 0xDDA0DD),
+                                   ("powderblue", 0xB0E0E6),
+                                   ("purple", 0x800080),
+                                   ("rebeccapurple", 0x663399),
+                                   ("red", 0xFF0000),
+                                   ("rosybrown", 0xBC8F8F),
+                                   ("royalblue", 0x4169E1),
+                                   ("saddlebrown", 0x8B4513),
+                                   ("salmon", 0xFA8072),
+                                   ("sandybrown", 0xF4A460),
+                                   ("seagreen", 0x2E8B57),
+                                   ("seashell", 0xFFF5EE),
+                                   ("sienna", 0xA0522D),
+                                   ("silver", 0xC0C0C0),
+                                   ("skyblue", 0x87CEEB),
+                                   ("slateblue", 0x6A5ACD),
+                                   ("slategray", 0x708090),
+                                   ("slategrey", 0x708090),
+                                   ("snow", 0xFFFAFA),
+                                   ("springgreen", 0x00FF7F),
+                                   ("steelblue", 0x4682B4),
+                                   ("tan", 0xD2B48C),
+                                   ("teal", 0x008080),
+                                   ("thistle", 0xD8BFD8),
+                                   ("tomato", 0xFF6347),
+                                   ("turquoise", 0x40E0D0),
+                                   ("violet", 0xEE82EE),
+                                   ("wheat", 0xF5DEB3),
+                                   ("white", 0xFFFFFF),
+                                   ("whitesmoke", 0xF5F5F5),
+                                   ("yellow", 0xFFFF00),
+                                   ("yellowgreen", 0x9ACD32)];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> PixelColor {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn hex_colors_are_parsed() {
+        assert_eq!(parse("#000"), PixelColor::BLACK);
+        assert_eq!(parse("#fFf"), PixelColor::WHITE);
+        assert_eq!(parse("#123"), PixelColor::new(0x11, 0x22, 0x33));
+        assert_eq!(parse("#FF8000"), PixelColor::new(0xFF, 0x80, 0x00));
+        assert_eq!(parse("  #00ff00\n"), PixelColor::GREEN);
+    }
+
+    #[test]
+    fn rgb_colors_are_parsed() {
+        assert_eq!(parse("rgb(255,0,255)"), PixelColor::MAGENTA);
+        assert_eq!(parse("RGB( 1 , 2 , 3 )"), PixelColor::new(1, 2, 3));
+    }
+
+    #[test]
+    fn named_colors_are_parsed() {
+        assert_eq!(parse("red"), PixelColor::RED);
+        assert_eq!(parse("Lime"), PixelColor::GREEN);
+        assert_eq!(parse("AQUA"), PixelColor::CYAN);
+        assert_eq!(parse("fuchsia"), PixelColor::MAGENTA);
+        assert_eq!(parse("aliceblue"), PixelColor::new(0xF0, 0xF8, 0xFF));
+        assert_eq!(parse("rebeccapurple"), PixelColor::new(0x66, 0x33, 0x99));
+        assert_eq!(parse("yellowgreen"), PixelColor::new(0x9A, 0xCD, 0x32));
+        assert_eq!(parse("grey"), parse("gray"));
+    }
+
+    #[test]
+    fn named_colors_are_sorted_for_binary_search() {
+        assert!(NAMES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        for &(name, value) in NAMES.iter() {
+            assert_eq!(parse(name), from_u32(value));
+        }
+    }
+
+    #[test]
+    fn invalid_colors_are_rejected() {
+        for s in &["",
+                   "#",
+                   "#12",
+                   "#1234",
+                   "#12345G",
+                   "#+12345",
+                   "rgb(1, 2)",
+                   "rgb(1, 2, 3, 4)",
+                   "rgb(1, 2, 256)",
+                   "rgb(1, 2, 3",
+                   "not a color"]
+        {
+            assert!(s.parse::<PixelColor>().is_err(), "{:?} parsed", s);
+        }
+        let err = "#12".parse::<PixelColor>().unwrap_err();
+        assert_eq!(err.to_string(), "invalid color: invalid hex color \"#12\"");
+    }
+
+    #[test]
+    fn colors_are_displayed_in_hex_and_parsed_back() {
+        let color = PixelColor::new(0x0A, 0xBC, 0xFF);
+        assert_eq!(color.to_string(), "#0ABCFF");
+        assert_eq!(parse(&color.to_string()), color);
+    }
+
+    #[cfg(feature = "serde-support")]
+    #[test]
+    fn colors_are_deserialized_from_strings() {
+        use serde::de::value::{Error, StrDeserializer};
+        use serde::de::IntoDeserializer;
+        use serde::Deserialize;
+
+        let deserializer: StrDeserializer<Error> = "salmon".into_deserializer();
+        assert_eq!(PixelColor::deserialize(deserializer).unwrap(),
+                   PixelColor::new(0xFA, 0x80, 0x72));
+        let deserializer: StrDeserializer<Error> = "#12".into_deserializer();
+        assert!(PixelColor::deserialize(deserializer).is_err());
+    }
+}
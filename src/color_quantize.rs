@@ -0,0 +1,97 @@
+//! Quantization of `PixelColor` to the colors that the LED Matrix can show.
+//!
+//! The screen shows 5 bits of red, 6 bits of green, and 5 bits of blue. `Rgb565::from_rgb`,
+//! used by `PixelFrame::frame_line`, drops the low bits of every channel, while
+//! `Rgb565::from_rgb_rounded`, used by `PixelFrame::frame_line_rounded`, keeps the nearest
+//! level, so that the color shown is the one closest to the color drawn.
+use super::{nearest_level, PixelColor, Rgb565};
+
+impl PixelColor {
+    /// Returns the color nearest to this one that the LED Matrix shows exactly, which is the
+    /// color read back after rendering it with `Rgb565::from_rgb_rounded`, as
+    /// `PixelFrame::frame_line_rounded` does.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::PixelColor;
+    /// # fn main() {
+    ///     assert_eq!(PixelColor::WHITE.quantized(), PixelColor::WHITE);
+    ///     let gray = PixelColor::new(0x80, 0x80, 0x80);
+    ///     assert_eq!(gray.quantized(), PixelColor::new(0x84, 0x82, 0x84));
+    ///     assert!(gray.approx_eq(gray.quantized(), 4));
+    /// # }
+    /// ```
+    pub fn quantized(self) -> PixelColor {
+        Rgb565::from_rgb_rounded(self.red, self.green, self.blue).into()
+    }
+
+    /// Returns `true` if no channel of this color differs from that of `other` by more than
+    /// `tolerance`. A tolerance of `4` covers the rounding of `Rgb565::from_rgb_rounded`, and
+    /// `7` the truncation of `Rgb565::from_rgb`.
+    pub fn approx_eq(self, other: PixelColor, tolerance: u8) -> bool {
+        let close = |a: u8, b: u8| (i16::from(a) - i16::from(b)).abs() <= i16::from(tolerance);
+        close(self.red, other.red) && close(self.green, other.green)
+        && close(self.blue, other.blue)
+    }
+}
+
+impl Rgb565 {
+    /// Create `Rgb565` instance from red, green, and blue `u8` values, rounding every channel
+    /// to the level that `Rgb565::to_rgb` expands nearest to it.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::color::Rgb565;
+    /// # fn main() {
+    ///     assert_eq!(Rgb565::from_rgb(0x07, 0x03, 0x07), Rgb565::from(0x0000));
+    ///     assert_eq!(Rgb565::from_rgb_rounded(0x07, 0x03, 0x07), Rgb565::from(0x0821));
+    /// # }
+    /// ```
+    pub fn from_rgb_rounded(red: u8, green: u8, blue: u8) -> Self {
+        let level = |value: u8, bits: u32| nearest_level(f32::from(value), bits);
+        Rgb565::from((level(red, 5) << 11) | (level(green, 6) << 5) | level(blue, 5))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantized_colors_round_trip_through_rgb565() {
+        for &color in &[PixelColor::WHITE,
+                        PixelColor::RED,
+                        PixelColor::GREEN,
+                        PixelColor::BLUE,
+                        PixelColor::YELLOW,
+                        PixelColor::CYAN,
+                        PixelColor::MAGENTA,
+                        PixelColor::BLACK]
+        {
+            assert_eq!(color.quantized(), color);
+            assert_eq!(PixelColor::from(Rgb565::from(color)), color);
+        }
+        let color = PixelColor::new(0x12, 0x9A, 0xFE);
+        let quantized = color.quantized();
+        assert_eq!(quantized, PixelColor::new(0x10, 0x9A, 0xFF));
+        assert_eq!(quantized.quantized(), quantized);
+        assert_eq!(PixelColor::from(Rgb565::from(quantized)), quantized);
+    }
+
+    #[test]
+    fn rounding_keeps_colors_closer_than_truncation() {
+        let color = PixelColor::new(0xF0, 0x7F, 0x06);
+        let truncated = PixelColor::from(Rgb565::from(color));
+        assert_eq!(truncated, PixelColor::new(0xF7, 0x7D, 0x00));
+        assert_eq!(color.quantized(), PixelColor::new(0xEF, 0x7D, 0x08));
+    }
+
+    #[test]
+    fn colors_are_compared_with_a_tolerance() {
+        let color = PixelColor::new(100, 150, 200);
+        assert!(color.approx_eq(color, 0));
+        assert!(color.approx_eq(PixelColor::new(104, 146, 200), 4));
+        assert!(!color.approx_eq(PixelColor::new(100, 150, 205), 4));
+        assert!(PixelColor::BLACK.approx_eq(PixelColor::WHITE, 255));
+    }
+}
@@ -0,0 +1,173 @@
+//! Displays that render frames for the LED Matrix.
+//!
+//! The `Display` trait decouples the code that draws frames from the device that shows them.
+//! `Screen` implements it for the LED Matrix, while `MemoryScreen` keeps every frame in memory,
+//! which is handy for testing animations on machines without a Sense HAT.
+use super::error::Result;
+use super::{FrameLine, PixelFrame};
+
+/// A display that renders frames of the 8x8 LED Matrix.
+pub trait Display {
+    /// Write the contents of a `FrameLine` onto the display.
+    fn write_frame(&mut self, frame: &FrameLine) -> Result<()>;
+
+    /// Write the contents of a `PixelFrame` onto the display.
+    fn write_pixel_frame(&mut self, frame: &PixelFrame) -> Result<()> {
+        self.write_frame(&frame.frame_line())
+    }
+
+    /// Turn off every pixel on the display.
+    fn clear(&mut self) -> Result<()> {
+        self.write_frame(&PixelFrame::BLACK.frame_line())
+    }
+
+    /// Read back the frame that is currently shown, for displays that support it.
+    ///
+    /// Returns `Ok(None)` by default.
+    fn read_frame(&self) -> Result<Option<FrameLine>> {
+        Ok(None)
+    }
+}
+
+impl<D: Display + ?Sized> Display for &mut D {
+    fn write_frame(&mut self, frame: &FrameLine) -> Result<()> {
+        (**self).write_frame(frame)
+    }
+
+    fn write_pixel_frame(&mut self, frame: &PixelFrame) -> Result<()> {
+        (**self).write_pixel_frame(frame)
+    }
+
+    fn clear(&mut self) -> Result<()> {
+        (**self).clear()
+    }
+
+    fn read_frame(&self) -> Result<Option<FrameLine>> {
+        (**self).read_frame()
+    }
+}
+
+/// An in-memory display, that records every frame written to it.
+///
+/// ```
+/// # extern crate sensehat_screen;
+/// # use sensehat_screen::{Display, MemoryScreen, PixelFrame};
+/// # fn main() {
+///     let mut screen = MemoryScreen::new();
+///     screen.write_pixel_frame(&PixelFrame::RED).unwrap();
+///     screen.clear().unwrap();
+///
+///     assert_eq!(screen.frames(),
+///                &[PixelFrame::RED.frame_line(), PixelFrame::BLACK.frame_line()]);
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemoryScreen {
+    frames: Vec<FrameLine>,
+}
+
+impl MemoryScreen {
+    /// Create a new `MemoryScreen`, with no frames recorded.
+    pub fn new() -> Self {
+        MemoryScreen::default()
+    }
+
+    /// Returns `&[FrameLine]` with every frame written, from first to last.
+    pub fn frames(&self) -> &[FrameLine] {
+        self.frames.as_slice()
+    }
+
+    /// Returns the last frame written, if any.
+    pub fn last_frame(&self) -> Option<&FrameLine> {
+        self.frames.last()
+    }
+
+    /// Remove, and return, every frame recorded so far.
+    pub fn take_frames(&mut self) -> Vec<FrameLine> {
+        ::std::mem::take(&mut self.frames)
+    }
+}
+
+impl Display for MemoryScreen {
+    fn write_frame(&mut self, frame: &FrameLine) -> Result<()> {
+        self.frames.push(*frame);
+        Ok(())
+    }
+
+    /// Returns the last frame written, or a blank frame if nothing has been written.
+    fn read_frame(&self) -> Result<Option<FrameLine>> {
+        Ok(Some(self.last_frame().cloned().unwrap_or_default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_screen_starts_without_frames() {
+        let screen = MemoryScreen::new();
+        assert!(screen.frames().is_empty());
+        assert_eq!(screen.last_frame(), None);
+    }
+
+    #[test]
+    fn memory_screen_records_every_frame_written() {
+        let mut screen = MemoryScreen::new();
+        screen.write_frame(&PixelFrame::BLUE.frame_line()).unwrap();
+        screen.write_pixel_frame(&PixelFrame::YELLOW).unwrap();
+        assert_eq!(screen.frames(),
+                   &[PixelFrame::BLUE.frame_line(), PixelFrame::YELLOW.frame_line()]);
+        assert_eq!(screen.last_frame(), Some(&PixelFrame::YELLOW.frame_line()));
+    }
+
+    #[test]
+    fn memory_screen_clears_with_a_black_frame() {
+        let mut screen = MemoryScreen::new();
+        screen.clear().unwrap();
+        assert_eq!(screen.frames(), &[PixelFrame::BLACK.frame_line()]);
+    }
+
+    #[test]
+    fn memory_screen_reads_back_the_last_frame() {
+        let mut screen = MemoryScreen::new();
+        assert_eq!(screen.read_frame().unwrap(), Some(FrameLine::default()));
+        screen.write_pixel_frame(&PixelFrame::GREEN).unwrap();
+        assert_eq!(screen.read_frame().unwrap(), Some(PixelFrame::GREEN.frame_line()));
+    }
+
+    #[test]
+    fn memory_screen_takes_recorded_frames() {
+        let mut screen = MemoryScreen::new();
+        screen.write_pixel_frame(&PixelFrame::RED).unwrap();
+        assert_eq!(screen.take_frames(), vec![PixelFrame::RED.frame_line()]);
+        assert!(screen.frames().is_empty());
+    }
+
+    #[test]
+    fn display_is_implemented_for_mutable_references() {
+        fn show<D: Display>(mut display: D) {
+            display.write_pixel_frame(&PixelFrame::CYAN).unwrap();
+        }
+        let mut screen = MemoryScreen::new();
+        show(&mut screen);
+        assert_eq!(screen.frames(), &[PixelFrame::CYAN.frame_line()]);
+    }
+
+    #[cfg(feature = "scroll")]
+    #[test]
+    fn memory_screen_records_a_scrolling_frame_sequence() {
+        use Scroll;
+
+        let scroll = Scroll::new(&[PixelFrame::RED, PixelFrame::BLUE]);
+        let mut screen = MemoryScreen::new();
+        for frame in scroll.right_to_left() {
+            screen.write_pixel_frame(&frame).unwrap();
+        }
+        let expected = scroll.right_to_left()
+                             .map(|frame| frame.frame_line())
+                             .collect::<Vec<FrameLine>>();
+        assert_eq!(screen.frames(), expected.as_slice());
+        assert_eq!(screen.last_frame(), Some(&PixelFrame::BLUE.frame_line()));
+    }
+}
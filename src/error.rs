@@ -0,0 +1,139 @@
+//! Errors for the LED Matrix screen.
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// A `Result` whose error type is `ScreenError`.
+pub type Result<T> = ::std::result::Result<T, ScreenError>;
+
+/// Errors that may happen while working with the LED Matrix screen.
+#[derive(Debug)]
+pub enum ScreenError {
+    /// The device, or image, file could not be opened.
+    Open { path: PathBuf, source: io::Error },
+    /// No framebuffer device with the given name was found.
+    DeviceNotFound { name: String },
+    /// The device file is not the expected framebuffer device.
+    WrongDevice { path: PathBuf, reason: String },
+    /// Fewer bytes than expected were written to the device.
+    ShortWrite { written: usize, expected: usize },
+    /// Fewer bytes than expected were read from the device.
+    ShortRead { read: usize, expected: usize },
+    /// An `ioctl` request on the device failed.
+    Ioctl { request: &'static str, source: io::Error },
+    /// The signal handler is already installed by another `Screen`.
+    SignalHandlerInUse,
+    /// A gamma table has a value greater than `0x1F` at the given index.
+    InvalidGamma { index: usize, value: u8 },
+    /// The device reports a geometry, or pixel depth, that is not supported.
+    UnsupportedGeometry { width: u32,
+                          height: u32,
+                          bits_per_pixel: u32, },
+    /// The device uses a pixel format that is not supported. Channels are given as their
+    /// `(offset, length)` in bits.
+    UnsupportedPixelFormat { bits_per_pixel: u32,
+                             red: (u32, u32),
+                             green: (u32, u32),
+                             blue: (u32, u32), },
+    /// An image could not be decoded, because it is corrupt, or uses a feature that is not
+    /// supported.
+    InvalidImage { reason: String },
+    /// A recording of frames could not be read, because it is corrupt.
+    InvalidRecording { reason: String },
+    /// Any other I/O error while reading from, or writing to, the device.
+    Io(io::Error),
+}
+
+impl fmt::Display for ScreenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ScreenError::Open { ref path, ref source } => {
+                write!(f, "could not open {}: {}", path.display(), source)
+            }
+            ScreenError::DeviceNotFound { ref name } => {
+                write!(f, "no framebuffer device named {:?} was found", name)
+            }
+            ScreenError::WrongDevice { ref path, ref reason } => {
+                write!(f, "{} is not the LED Matrix framebuffer: {}", path.display(), reason)
+            }
+            ScreenError::ShortWrite { written, expected } => {
+                write!(f, "short write: {} of {} bytes were written", written, expected)
+            }
+            ScreenError::ShortRead { read, expected } => {
+                write!(f, "short read: {} of {} bytes were read", read, expected)
+            }
+            ScreenError::Ioctl { request, ref source } => {
+                write!(f, "ioctl {} failed: {}", request, source)
+            }
+            ScreenError::SignalHandlerInUse => {
+                write!(f, "the signal handler is already installed by another screen")
+            }
+            ScreenError::InvalidGamma { index, value } => {
+                write!(f, "invalid gamma value {:#04X} at index {}", value, index)
+            }
+            ScreenError::UnsupportedGeometry { width,
+                                               height,
+                                               bits_per_pixel, } => {
+                write!(f,
+                       "unsupported geometry: {}x{} at {} bits per pixel",
+                       width, height, bits_per_pixel)
+            }
+            ScreenError::UnsupportedPixelFormat { bits_per_pixel,
+                                                  red,
+                                                  green,
+                                                  blue, } => {
+                write!(f,
+                       "unsupported pixel format: {} bits per pixel, with red at {:?}, \
+                        green at {:?}, and blue at {:?}",
+                       bits_per_pixel, red, green, blue)
+            }
+            ScreenError::InvalidImage { ref reason } => write!(f, "invalid image: {}", reason),
+            ScreenError::InvalidRecording { ref reason } => {
+                write!(f, "invalid recording: {}", reason)
+            }
+            ScreenError::Io(ref err) => write!(f, "I/O error: {}", err),
+        }
+    }
+}
+
+impl Error for ScreenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ScreenError::Open { ref source, .. } | ScreenError::Ioctl { ref source, .. } => {
+                Some(source)
+            }
+            ScreenError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ScreenError {
+    fn from(err: io::Error) -> Self {
+        ScreenError::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screen_error_displays_a_short_write() {
+        let err = ScreenError::ShortWrite { written: 64,
+                                            expected: 128, };
+        assert_eq!(format!("{}", err), "short write: 64 of 128 bytes were written");
+    }
+
+    #[test]
+    fn screen_error_exposes_the_io_error_as_its_source() {
+        let err = ScreenError::Open { path: PathBuf::from("/dev/fb1"),
+                                      source: io::Error::new(io::ErrorKind::NotFound, "gone"), };
+        assert!(err.source().is_some());
+        let err = ScreenError::UnsupportedGeometry { width: 640,
+                                                     height: 480,
+                                                     bits_per_pixel: 32, };
+        assert!(err.source().is_none());
+    }
+}
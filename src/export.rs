@@ -0,0 +1,205 @@
+//! Export frames, and animations, as images.
+//!
+//! Frames are upscaled by a whole factor, so that every LED becomes a square block of pixels,
+//! and written as PPM, or PNG, images. A `GifRecorder` collects frames, each with its own
+//! delay, and writes them as an animated GIF. The encoders are written in plain Rust, without
+//! any dependencies.
+//!
+//! ```no_run
+//! # extern crate sensehat_screen;
+//! # use sensehat_screen::export::write_png;
+//! # use sensehat_screen::{GifRecorder, PixelColor, PixelFrame};
+//! # use std::fs::File;
+//! # use std::time::Duration;
+//! # fn main() {
+//!     // a screenshot, with each LED as a block of 32x32 pixels.
+//!     let file = File::create("red.png").unwrap();
+//!     write_png(&PixelFrame::RED, 32, file).unwrap();
+//!
+//!     // an animation, at 8 frames per second.
+//!     let mut recorder = GifRecorder::new(32);
+//!     recorder.extend(vec![PixelFrame::RED, PixelFrame::BLUE], Duration::from_millis(125));
+//!     recorder.write(File::create("blink.gif").unwrap()).unwrap();
+//! # }
+//! ```
+#[path = "export_gif.rs"]
+mod gif;
+#[path = "export_png.rs"]
+mod png;
+
+use super::{PixelColor, PixelFrame};
+use std::io::{self, Write};
+use std::time::Duration;
+
+pub use self::png::write_png;
+
+/// Largest scale of an animated GIF, which is then 65528 pixels wide, as a GIF is at most 65535
+/// pixels wide.
+pub const MAX_SCALE: u32 = 0xFFFF / 8;
+
+/// Largest scale of a PPM, or PNG, image, which is then 4096 pixels wide, and holds 48 MiB of
+/// uncompressed pixels.
+pub const MAX_IMAGE_SCALE: u32 = 512;
+
+/// Write a frame as a binary PPM (`P6`) image, with each LED as a block of `scale` by `scale`
+/// pixels.
+///
+/// Fails with `io::ErrorKind::InvalidInput`, before writing anything, if the scale is zero, or
+/// more than `MAX_IMAGE_SCALE`.
+pub fn write_ppm<W: Write>(frame: &PixelFrame, scale: u32, mut writer: W) -> io::Result<()> {
+    let size = image_size(scale, MAX_IMAGE_SCALE)?;
+    write!(writer, "P6\n{} {}\n255\n", size, size)?;
+    let mut line = Vec::with_capacity(size as usize * 3);
+    for row in upscale(frame, scale) {
+        line.clear();
+        for color in row {
+            line.extend_from_slice(&[color.red, color.green, color.blue]);
+        }
+        writer.write_all(&line)?;
+    }
+    writer.flush()
+}
+
+/// Records frames, each with its own delay, to write them as an animated GIF.
+///
+/// GIF delays are measured in hundredths of a second, so delays are rounded to the nearest
+/// one. Every frame has its own color table, which keeps every color exact.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GifRecorder {
+    frames: Vec<(PixelFrame, Duration)>,
+    scale: u32,
+    repeat: Option<u16>,
+}
+
+impl GifRecorder {
+    /// Create a new `GifRecorder`, with each LED as a block of `scale` by `scale` pixels. The
+    /// animation loops forever, by default.
+    pub fn new(scale: u32) -> Self {
+        GifRecorder { frames: Vec::new(),
+                      scale,
+                      repeat: Some(0), }
+    }
+
+    /// Set how many times the animation is repeated after it is first played: `Some(0)` loops
+    /// forever, and `None` plays it once.
+    pub fn set_repeat(&mut self, repeat: Option<u16>) {
+        self.repeat = repeat;
+    }
+
+    /// Add a frame, shown for the given delay.
+    pub fn push(&mut self, frame: &PixelFrame, delay: Duration) {
+        self.frames.push((*frame, delay));
+    }
+
+    /// Add every frame of an iterator, such as a `FrameSequence`, each shown for the same
+    /// delay.
+    pub fn extend<I: IntoIterator<Item = PixelFrame>>(&mut self, frames: I, delay: Duration) {
+        self.frames
+            .extend(frames.into_iter().map(|frame| (frame, delay)));
+    }
+
+    /// Returns the frames recorded so far, with their delays.
+    pub fn frames(&self) -> &[(PixelFrame, Duration)] {
+        &self.frames
+    }
+
+    /// Write the recorded frames as an animated GIF.
+    ///
+    /// Fails with `io::ErrorKind::InvalidInput`, before writing anything, if the scale is zero,
+    /// or more than `MAX_SCALE`.
+    pub fn write<W: Write>(&self, writer: W) -> io::Result<()> {
+        gif::write_gif(&self.frames, self.scale, self.repeat, writer)
+    }
+}
+
+// Width, and height, of a frame upscaled by `scale`, which is between 1 and `max`.
+fn image_size(scale: u32, max: u32) -> io::Result<u32> {
+    scale.checked_mul(8)
+         .filter(|_| (1..=max).contains(&scale))
+         .ok_or_else(|| {
+                         io::Error::new(io::ErrorKind::InvalidInput,
+                                        format!("scale must be between 1 and {}, not {}",
+                                                max,
+                                                scale))
+                     })
+}
+
+// The rows of pixels of a frame, upscaled by a valid `scale`, built one at a time.
+fn upscale(frame: &PixelFrame, scale: u32) -> impl Iterator<Item = Vec<PixelColor>> {
+    let scale = scale as usize;
+    let size = 8 * scale;
+    let rows = frame.as_rows();
+    (0..size).map(move |y| (0..size).map(|x| rows[y / scale][x / scale]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_is_upscaled_by_whole_factors() {
+        let mut frame = PixelFrame::BLACK;
+        frame[9] = PixelColor::RED;
+        let rows = upscale(&frame, 2).collect::<Vec<_>>();
+        assert_eq!(rows.len(), 16);
+        assert_eq!(&rows[2][..6],
+                   &[PixelColor::BLACK,
+                     PixelColor::BLACK,
+                     PixelColor::RED,
+                     PixelColor::RED,
+                     PixelColor::BLACK,
+                     PixelColor::BLACK]);
+        assert_eq!(rows[3][3], PixelColor::RED);
+        assert_eq!(rows[4][3], PixelColor::BLACK);
+    }
+
+    #[test]
+    fn frame_is_written_as_ppm() {
+        let mut frame = PixelFrame::BLACK;
+        frame[0] = PixelColor::new(1, 2, 3);
+        let mut ppm = Vec::new();
+        write_ppm(&frame, 1, &mut ppm).unwrap();
+        let header = b"P6\n8 8\n255\n";
+        assert_eq!(&ppm[..header.len()], &header[..]);
+        assert_eq!(ppm.len(), header.len() + 8 * 8 * 3);
+        assert_eq!(&ppm[header.len()..header.len() + 6], &[1, 2, 3, 0, 0, 0]);
+    }
+
+    #[test]
+    fn frame_is_written_as_upscaled_ppm() {
+        let mut ppm = Vec::new();
+        write_ppm(&PixelFrame::GREEN, 4, &mut ppm).unwrap();
+        assert!(ppm.starts_with(b"P6\n32 32\n255\n"));
+        assert_eq!(ppm.len(), 13 + 32 * 32 * 3);
+    }
+
+    #[test]
+    fn frame_is_not_written_at_an_invalid_scale() {
+        for &scale in &[0, MAX_IMAGE_SCALE + 1, MAX_SCALE, 0x2000_0000, u32::MAX] {
+            let mut image = Vec::new();
+            let err = write_ppm(&PixelFrame::GREEN, scale, &mut image).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            let err = write_png(&PixelFrame::GREEN, scale, &mut image).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            assert!(image.is_empty());
+        }
+        for &scale in &[0, MAX_SCALE + 1, 0x2000_0000, u32::MAX] {
+            let mut image = Vec::new();
+            let err = GifRecorder::new(scale).write(&mut image).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            assert!(image.is_empty());
+        }
+    }
+
+    #[test]
+    fn gif_recorder_records_frames_with_delays() {
+        let mut recorder = GifRecorder::new(1);
+        recorder.push(&PixelFrame::RED, Duration::from_millis(500));
+        recorder.extend(vec![PixelFrame::GREEN, PixelFrame::BLUE],
+                        Duration::from_millis(100));
+        assert_eq!(recorder.frames(),
+                   &[(PixelFrame::RED, Duration::from_millis(500)),
+                     (PixelFrame::GREEN, Duration::from_millis(100)),
+                     (PixelFrame::BLUE, Duration::from_millis(100))]);
+    }
+}
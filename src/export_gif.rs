@@ -0,0 +1,278 @@
+//! Animated GIF encoding, with LZW compression.
+use super::{image_size, upscale, MAX_SCALE};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::time::Duration;
+use {PixelColor, PixelFrame};
+
+// Largest LZW code, GIF codes are at most 12 bits wide.
+const MAX_CODE: u16 = 0x0FFF;
+
+// Write the frames, and their delays, as an animated GIF. `repeat` is the number of times the
+// animation is repeated, zero for ever, or `None` to play it once. Fails, before writing
+// anything, if the scale is invalid.
+pub fn write_gif<W: Write>(frames: &[(PixelFrame, Duration)],
+                           scale: u32,
+                           repeat: Option<u16>,
+                           mut writer: W)
+                           -> io::Result<()> {
+    // every valid scale fits the 16-bit size of a GIF.
+    let size = image_size(scale, MAX_SCALE)? as u16;
+    writer.write_all(b"GIF89a")?;
+    // logical screen, without a global color table.
+    writer.write_all(&size.to_le_bytes())?;
+    writer.write_all(&size.to_le_bytes())?;
+    writer.write_all(&[0, 0, 0])?;
+
+    if let Some(repeat) = repeat {
+        writer.write_all(&[0x21, 0xFF, 0x0B])?;
+        writer.write_all(b"NETSCAPE2.0")?;
+        writer.write_all(&[0x03, 0x01])?;
+        writer.write_all(&repeat.to_le_bytes())?;
+        writer.write_all(&[0x00])?;
+    }
+
+    for &(ref frame, delay) in frames {
+        let (palette, indices) = index_colors(frame);
+        // the color table has a power-of-two size, of at least 2 colors.
+        let bits = (1..=8).find(|bits| palette.len() <= 1 << bits).unwrap();
+
+        let centis = (delay.as_millis() + 5) / 10;
+        let centis = centis.min(u128::from(u16::MAX)) as u16;
+        writer.write_all(&[0x21, 0xF9, 0x04, 0x00])?;
+        writer.write_all(&centis.to_le_bytes())?;
+        writer.write_all(&[0x00, 0x00])?;
+
+        writer.write_all(&[0x2C, 0, 0, 0, 0])?;
+        writer.write_all(&size.to_le_bytes())?;
+        writer.write_all(&size.to_le_bytes())?;
+        writer.write_all(&[0x80 | (bits - 1)])?;
+        for index in 0..1 << bits {
+            let color = palette.get(index).cloned().unwrap_or_default();
+            writer.write_all(&[color.red, color.green, color.blue])?;
+        }
+
+        let min_code_size = bits.max(2);
+        let pixels = upscale(frame, scale).flat_map(|row| {
+                                                        row.into_iter()
+                                                           .map(|color| indices[&color])
+                                                           .collect::<Vec<u8>>()
+                                                    });
+        writer.write_all(&[min_code_size])?;
+        for block in lzw_encode(pixels, min_code_size).chunks(255) {
+            writer.write_all(&[block.len() as u8])?;
+            writer.write_all(block)?;
+        }
+        writer.write_all(&[0x00])?;
+    }
+
+    writer.write_all(&[0x3B])?;
+    writer.flush()
+}
+
+// The distinct colors of the frame, in order of appearance, and the index of each color.
+fn index_colors(frame: &PixelFrame) -> (Vec<PixelColor>, HashMap<PixelColor, u8>) {
+    let mut palette = Vec::new();
+    let mut indices = HashMap::new();
+    for row in frame.as_rows().iter() {
+        for &color in row.iter() {
+            indices.entry(color).or_insert_with(|| {
+                                    palette.push(color);
+                                    (palette.len() - 1) as u8
+                                });
+        }
+    }
+    (palette, indices)
+}
+
+// Compress the color indices with the variable-length LZW codes of GIF images.
+fn lzw_encode<I: IntoIterator<Item = u8>>(indices: I, min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let mut writer = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut width = min_code_size + 1;
+
+    writer.write(clear, width);
+    let mut prefix: Option<u16> = None;
+    for index in indices {
+        let code = match prefix {
+            None => {
+                prefix = Some(u16::from(index));
+                continue;
+            }
+            Some(code) => code,
+        };
+        if let Some(&found) = table.get(&(code, index)) {
+            prefix = Some(found);
+            continue;
+        }
+        writer.write(code, width);
+        if next <= MAX_CODE {
+            table.insert((code, index), next);
+            next += 1;
+            if next > 1 << width && width < 12 {
+                width += 1;
+            }
+        } else {
+            writer.write(clear, width);
+            table.clear();
+            next = end + 1;
+            width = min_code_size + 1;
+        }
+        prefix = Some(u16::from(index));
+    }
+    if let Some(code) = prefix {
+        writer.write(code, width);
+    }
+    writer.write(end, width);
+    writer.finish()
+}
+
+// Packs codes into bytes, least significant bits first.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u8) {
+        self.buffer |= u32::from(code) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Decode variable-length LZW codes, as a GIF decoder does.
+    fn lzw_decode(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1u16 << min_code_size;
+        let end = clear + 1;
+        let (mut buffer, mut bits, mut bytes) = (0u32, 0u8, data.iter());
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut width = min_code_size + 1;
+        let mut previous: Option<Vec<u8>> = None;
+        let mut output = Vec::new();
+        loop {
+            while bits < width {
+                buffer |= u32::from(*bytes.next().unwrap()) << bits;
+                bits += 8;
+            }
+            let code = (buffer & ((1 << width) - 1)) as u16;
+            buffer >>= width;
+            bits -= width;
+            if code == clear {
+                table = (0..clear).map(|index| vec![index as u8]).collect();
+                table.push(Vec::new());
+                table.push(Vec::new());
+                width = min_code_size + 1;
+                previous = None;
+                continue;
+            }
+            if code == end {
+                return output;
+            }
+            let entry = match (table.get(code as usize), previous.as_ref()) {
+                (Some(entry), _) => entry.clone(),
+                (None, Some(previous)) => {
+                    let mut entry = previous.clone();
+                    entry.push(previous[0]);
+                    entry
+                }
+                (None, None) => panic!("invalid code {}", code),
+            };
+            output.extend_from_slice(&entry);
+            if let Some(mut previous) = previous {
+                previous.push(entry[0]);
+                table.push(previous);
+                if table.len() == 1 << width && width < 12 {
+                    width += 1;
+                }
+            }
+            previous = Some(entry);
+        }
+    }
+
+    #[test]
+    fn lzw_round_trips_color_indices() {
+        let indices = (0..4096u32).map(|n| ((n * 7 / 5) % 4) as u8).collect::<Vec<u8>>();
+        assert_eq!(lzw_decode(&lzw_encode(indices.iter().cloned(), 2), 2), indices);
+        let noisy = (0..20_000u32).map(|n| ((n * n + n / 3) % 64) as u8)
+                                  .collect::<Vec<u8>>();
+        assert_eq!(lzw_decode(&lzw_encode(noisy.iter().cloned(), 6), 6), noisy);
+        assert_eq!(lzw_decode(&lzw_encode(Vec::new(), 2), 2), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn colors_are_indexed_in_order_of_appearance() {
+        let mut frame = PixelFrame::BLUE;
+        frame[3] = PixelColor::RED;
+        let (palette, indices) = index_colors(&frame);
+        assert_eq!(palette, vec![PixelColor::BLUE, PixelColor::RED]);
+        assert_eq!(indices[&PixelColor::RED], 1);
+    }
+
+    #[test]
+    fn frames_are_written_as_an_animated_gif() {
+        let mut frame = PixelFrame::BLUE;
+        frame[0] = PixelColor::new(1, 2, 3);
+        let frames = [(frame, Duration::from_millis(125)),
+                      (PixelFrame::RED, Duration::from_secs(1))];
+        let mut gif = Vec::new();
+        write_gif(&frames, 2, Some(0), &mut gif).unwrap();
+
+        assert_eq!(&gif[..13], b"GIF89a\x10\x00\x10\x00\x00\x00\x00");
+        assert_eq!(&gif[13..32], b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+        // delay of 13 hundredths of a second.
+        assert_eq!(&gif[32..40], &[0x21, 0xF9, 0x04, 0x00, 13, 0, 0, 0]);
+        assert_eq!(&gif[40..50], &[0x2C, 0, 0, 0, 0, 16, 0, 16, 0, 0x80]);
+        assert_eq!(&gif[50..56], &[1, 2, 3, 0, 0, 0xFF]);
+
+        let min_code_size = gif[56];
+        assert_eq!(min_code_size, 2);
+        let len = gif[57] as usize;
+        let pixels = lzw_decode(&gif[58..58 + len], min_code_size);
+        assert_eq!(pixels.len(), 256);
+        assert_eq!(&pixels[..3], &[0, 0, 1]);
+        assert_eq!(&pixels[16..19], &[0, 0, 1]);
+        assert!(pixels[32..].iter().all(|&index| index == 1));
+        assert_eq!(gif[58 + len], 0);
+
+        // the second frame, one second long, and the trailer.
+        assert_eq!(&gif[59 + len..67 + len], &[0x21, 0xF9, 0x04, 0x00, 100, 0, 0, 0]);
+        assert_eq!(gif.last(), Some(&0x3B));
+    }
+
+    #[test]
+    fn gif_wider_than_its_16_bit_size_is_not_written() {
+        let mut gif = Vec::new();
+        let frames = [(PixelFrame::RED, Duration::from_millis(10))];
+        let err = write_gif(&frames, 8192, None, &mut gif).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(gif.is_empty());
+    }
+
+    #[test]
+    fn gif_without_repeat_is_played_once() {
+        let mut gif = Vec::new();
+        write_gif(&[(PixelFrame::RED, Duration::from_millis(10))], 1, None, &mut gif).unwrap();
+        assert_eq!(&gif[13..15], &[0x21, 0xF9]);
+    }
+}
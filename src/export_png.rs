@@ -0,0 +1,208 @@
+//! PNG encoding, with uncompressed deflate blocks.
+use super::{image_size, upscale, MAX_IMAGE_SCALE};
+use checksum::{adler32_update, crc32};
+use std::io::{self, Write};
+use PixelFrame;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+// Largest length of a stored deflate block.
+const MAX_STORED: usize = 0xFFFF;
+
+/// Write a frame as a PNG image, with each LED as a block of `scale` by `scale` pixels.
+///
+/// The image data is stored without compression, which keeps the encoder small.
+///
+/// Fails with `io::ErrorKind::InvalidInput`, before writing anything, if the scale is zero, or
+/// more than `MAX_IMAGE_SCALE`.
+pub fn write_png<W: Write>(frame: &PixelFrame, scale: u32, mut writer: W) -> io::Result<()> {
+    let size = image_size(scale, MAX_IMAGE_SCALE)?;
+    writer.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&size.to_be_bytes());
+    header.extend_from_slice(&size.to_be_bytes());
+    // 8 bits per channel, truecolor, deflate, adaptive filtering, no interlace.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(&mut writer, b"IHDR", &header)?;
+
+    let mut data = ImageData::new(&mut writer);
+    let mut line = Vec::with_capacity(size as usize * 3 + 1);
+    for row in upscale(frame, scale) {
+        line.clear();
+        // each line starts with its filter type, none.
+        line.push(0);
+        for color in row {
+            line.extend_from_slice(&[color.red, color.green, color.blue]);
+        }
+        data.write(&line)?;
+    }
+    data.finish()?;
+    write_chunk(&mut writer, b"IEND", &[])?;
+    writer.flush()
+}
+
+// Write a PNG chunk: its length, type, data, and the CRC of its type and data. The data is at
+// most a stored deflate block, with the zlib header, and checksum, so its length fits.
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data.iter()).cloned());
+    writer.write_all(&crc.to_be_bytes())
+}
+
+// Writes the image data as a zlib stream of stored, uncompressed, deflate blocks, with each
+// block in an `IDAT` chunk of its own.
+struct ImageData<W> {
+    writer: W,
+    block: Vec<u8>,
+    started: bool,
+    adler: u32,
+}
+
+impl<W: Write> ImageData<W> {
+    fn new(writer: W) -> Self {
+        ImageData { writer,
+                    block: Vec::with_capacity(MAX_STORED),
+                    started: false,
+                    adler: 1, }
+    }
+
+    // Add data to the stream, writing every block that is full.
+    fn write(&mut self, mut data: &[u8]) -> io::Result<()> {
+        self.adler = adler32_update(self.adler, data);
+        while !data.is_empty() {
+            let len = data.len().min(MAX_STORED - self.block.len());
+            self.block.extend_from_slice(&data[..len]);
+            data = &data[len..];
+            if self.block.len() == MAX_STORED {
+                self.write_block(false)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Write the last block, which may be empty, followed by the checksum of the stream.
+    fn finish(mut self) -> io::Result<()> {
+        self.write_block(true)
+    }
+
+    fn write_block(&mut self, last: bool) -> io::Result<()> {
+        let mut chunk = Vec::with_capacity(self.block.len() + 11);
+        if !self.started {
+            // deflate, with a 32K window, and no preset dictionary.
+            chunk.extend_from_slice(&[0x78, 0x01]);
+            self.started = true;
+        }
+        let len = self.block.len() as u16;
+        chunk.push(last as u8);
+        chunk.extend_from_slice(&len.to_le_bytes());
+        chunk.extend_from_slice(&(!len).to_le_bytes());
+        chunk.extend_from_slice(&self.block);
+        if last {
+            chunk.extend_from_slice(&self.adler.to_be_bytes());
+        }
+        self.block.clear();
+        write_chunk(&mut self.writer, b"IDAT", &chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use checksum::adler32;
+    use PixelColor;
+
+    // Split a PNG image into its chunks, checking their CRC.
+    fn chunks(png: &[u8]) -> Vec<(String, Vec<u8>)> {
+        assert_eq!(&png[..8], &SIGNATURE);
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+            let kind = &rest[4..8];
+            let data = &rest[8..8 + len];
+            let crc = &rest[8 + len..12 + len];
+            let expected = crc32(kind.iter().chain(data.iter()).cloned());
+            assert_eq!(crc, &expected.to_be_bytes());
+            chunks.push((String::from_utf8(kind.to_vec()).unwrap(), data.to_vec()));
+            rest = &rest[12 + len..];
+        }
+        chunks
+    }
+
+    // Read back the data of a zlib stream of stored blocks, checking its checksum.
+    fn unstore(zlib: &[u8]) -> Vec<u8> {
+        assert_eq!(&zlib[..2], &[0x78, 0x01]);
+        let mut data = Vec::new();
+        let mut rest = &zlib[2..];
+        loop {
+            let last = rest[0] == 1;
+            let len = u16::from_le_bytes([rest[1], rest[2]]);
+            assert_eq!(!len, u16::from_le_bytes([rest[3], rest[4]]));
+            data.extend_from_slice(&rest[5..5 + len as usize]);
+            rest = &rest[5 + len as usize..];
+            if last {
+                break;
+            }
+        }
+        assert_eq!(rest, &adler32(&data).to_be_bytes());
+        data
+    }
+
+    // The zlib stream of the image data, and the lengths of its chunks.
+    fn image_data(png: &[u8]) -> (Vec<u8>, Vec<usize>) {
+        let idat = chunks(png).into_iter()
+                              .filter(|chunk| chunk.0 == "IDAT")
+                              .map(|chunk| chunk.1)
+                              .collect::<Vec<Vec<u8>>>();
+        (idat.concat(), idat.iter().map(Vec::len).collect())
+    }
+
+    #[test]
+    fn image_data_is_split_in_stored_blocks() {
+        let data = (0..200_000u32).map(|n| n as u8).collect::<Vec<u8>>();
+        let mut png = SIGNATURE.to_vec();
+        let mut image = ImageData::new(&mut png);
+        image.write(&data[..100]).unwrap();
+        image.write(&data[100..]).unwrap();
+        image.finish().unwrap();
+        let (zlib, lens) = image_data(&png);
+        assert_eq!(lens, vec![2 + 5 + MAX_STORED, 5 + MAX_STORED, 5 + MAX_STORED, 5 + 3395 + 4]);
+        assert_eq!(unstore(&zlib), data);
+
+        let mut png = SIGNATURE.to_vec();
+        ImageData::new(&mut png).finish().unwrap();
+        assert_eq!(unstore(&image_data(&png).0), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn frame_is_written_as_png() {
+        let mut frame = PixelFrame::BLACK;
+        frame[1] = PixelColor::new(1, 2, 3);
+        let mut png = Vec::new();
+        write_png(&frame, 2, &mut png).unwrap();
+        let chunks = chunks(&png);
+        let kinds = chunks.iter().map(|chunk| chunk.0.as_str()).collect::<Vec<&str>>();
+        assert_eq!(kinds, vec!["IHDR", "IDAT", "IEND"]);
+        assert_eq!(chunks[0].1, vec![0, 0, 0, 16, 0, 0, 0, 16, 8, 2, 0, 0, 0]);
+
+        let raw = unstore(&image_data(&png).0);
+        assert_eq!(raw.len(), 16 * (16 * 3 + 1));
+        assert_eq!(&raw[..13], &[0, 0, 0, 0, 0, 0, 0, 1, 2, 3, 1, 2, 3]);
+        assert_eq!(&raw[49..62], &[0, 0, 0, 0, 0, 0, 0, 1, 2, 3, 1, 2, 3]);
+        assert!(raw[98..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn large_frame_is_written_in_bounded_chunks() {
+        let mut png = Vec::new();
+        write_png(&PixelFrame::BLUE, 64, &mut png).unwrap();
+        let (zlib, lens) = image_data(&png);
+        assert_eq!(lens.len(), 13);
+        assert!(lens.iter().all(|&len| len <= 2 + 5 + MAX_STORED + 4));
+        let raw = unstore(&zlib);
+        assert_eq!(raw.len(), 512 * (512 * 3 + 1));
+        assert_eq!(&raw[..4], &[0, 0, 0, 255]);
+    }
+}
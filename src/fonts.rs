@@ -28,11 +28,11 @@ lazy_static! {
 fn default_hashmap() -> HashMap<char, FontUnicode> {
     BASIC_FONTS.to_vec()
                .into_iter()
-               .chain(LATIN_FONTS.to_vec().into_iter())
-               .chain(BLOCK_FONTS.to_vec().into_iter())
-               .chain(BOX_FONTS.to_vec().into_iter())
-               .chain(GREEK_FONTS.to_vec().into_iter())
-               .chain(HIRAGANA_FONTS.to_vec().into_iter())
+               .chain(LATIN_FONTS.to_vec())
+               .chain(BLOCK_FONTS.to_vec())
+               .chain(BOX_FONTS.to_vec())
+               .chain(GREEK_FONTS.to_vec())
+               .chain(HIRAGANA_FONTS.to_vec())
                .collect()
 }
 
@@ -97,6 +97,7 @@ impl FontString {
     }
 
     /// Render the font string as a `String`.
+    #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         self.0.iter().map(|font| font.char()).collect::<String>()
     }
@@ -335,7 +336,7 @@ mod tests {
         let px_array = font_to_pixel_color_array_with_bg(&font.byte_array(),
                                                          PixelColor::BLUE,
                                                          PixelColor::YELLOW);
-        for (idx, px) in px_array.into_iter().enumerate() {
+        for (idx, px) in px_array.iter().enumerate() {
             assert_eq!(*px, BOX_FONT_BG[idx]);
         }
     }
@@ -345,7 +346,7 @@ mod tests {
         let font_set = FontCollection::new();
         let font = font_set.get('M').unwrap();
         let px_array = font_to_pixel_color_array(&font.byte_array(), PixelColor::BLUE);
-        for (idx, px) in px_array.into_iter().enumerate() {
+        for (idx, px) in px_array.iter().enumerate() {
             assert_eq!(*px, BASIC_FONT[idx]);
         }
     }
@@ -370,7 +371,7 @@ mod tests {
     fn font_frames_are_created_from_ut16_font_a_stroke_and_a_background_color() {
         let font_set = FontCollection::new();
         let letter_a = font_set.get('a').unwrap();
-        let font_frame = FontFrame::new(letter_a.clone(), PixelColor::WHITE, PixelColor::BLACK);
+        let font_frame = FontFrame::new(*letter_a, PixelColor::WHITE, PixelColor::BLACK);
         assert_eq!(font_frame,
                    FontFrame { font: *letter_a,
                                stroke: PixelColor::WHITE,
@@ -381,7 +382,7 @@ mod tests {
     fn font_frames_is_represented_as_a_pixel_frame() {
         let font_set = FontCollection::new();
         let hiragana_font = font_set.get('ち').unwrap();
-        let font_frame = FontFrame::new(hiragana_font.clone(), PixelColor::RED, PixelColor::BLACK);
+        let font_frame = FontFrame::new(*hiragana_font, PixelColor::RED, PixelColor::BLACK);
         let px_frame = font_frame.pixel_frame();
         assert_eq!(px_frame, PixelFrame::from(HIRAGANA_FONT));
     }
@@ -390,7 +391,7 @@ mod tests {
     fn pixel_frame_implements_from_font_frame_trait() {
         let font_set = FontCollection::new();
         let hiragana_font = font_set.get('ち').unwrap();
-        let font_frame = FontFrame::new(hiragana_font.clone(), PixelColor::RED, PixelColor::BLACK);
+        let font_frame = FontFrame::new(*hiragana_font, PixelColor::RED, PixelColor::BLACK);
         let px_frame = PixelFrame::from(font_frame);
         assert_eq!(px_frame, PixelFrame::from(HIRAGANA_FONT));
     }
@@ -399,7 +400,7 @@ mod tests {
     fn font_frame_sets_background_color() {
         let font_set = FontCollection::new();
         let letter_a = font_set.get('a').unwrap();
-        let mut font_frame = FontFrame::new(letter_a.clone(), PixelColor::WHITE, PixelColor::BLACK);
+        let mut font_frame = FontFrame::new(*letter_a, PixelColor::WHITE, PixelColor::BLACK);
         font_frame.set_background_color(PixelColor::RED);
         assert_eq!(font_frame,
                    FontFrame { font: *letter_a,
@@ -411,7 +412,7 @@ mod tests {
     fn font_frame_gets_background_color() {
         let font_set = FontCollection::new();
         let letter_a = font_set.get('a').unwrap();
-        let font_frame = FontFrame::new(letter_a.clone(), PixelColor::WHITE, PixelColor::GREEN);
+        let font_frame = FontFrame::new(*letter_a, PixelColor::WHITE, PixelColor::GREEN);
         assert_eq!(font_frame.get_background_color(), PixelColor::GREEN);
     }
 
@@ -419,7 +420,7 @@ mod tests {
     fn font_frame_sets_stroke_color() {
         let font_set = FontCollection::new();
         let letter_a = font_set.get('a').unwrap();
-        let mut font_frame = FontFrame::new(letter_a.clone(), PixelColor::WHITE, PixelColor::BLACK);
+        let mut font_frame = FontFrame::new(*letter_a, PixelColor::WHITE, PixelColor::BLACK);
         font_frame.set_stroke_color(PixelColor::YELLOW);
         assert_eq!(font_frame,
                    FontFrame { font: *letter_a,
@@ -431,7 +432,7 @@ mod tests {
     fn font_frame_gets_stroke_color() {
         let font_set = FontCollection::new();
         let letter_a = font_set.get('a').unwrap();
-        let font_frame = FontFrame::new(letter_a.clone(), PixelColor::BLUE, PixelColor::WHITE);
+        let font_frame = FontFrame::new(*letter_a, PixelColor::BLUE, PixelColor::WHITE);
         assert_eq!(font_frame.get_stroke_color(), PixelColor::BLUE);
     }
 }
@@ -1,4 +1,10 @@
 //! Frames for the LED Matrix screen
+#[path = "frame_blend.rs"]
+mod blend;
+#[path = "frame_gradient.rs"]
+pub mod gradient;
+#[path = "frame_indexed.rs"]
+pub mod indexed;
 #[cfg(feature = "clip")]
 #[path = "frame_clip.rs"]
 pub mod clip;
@@ -9,6 +15,7 @@ pub mod offset;
 #[path = "frame_rotate.rs"]
 pub mod rotate;
 
+use super::color::format::PixelFormat;
 use super::color::{PixelColor, Rgb565};
 use std::fmt::{self, Write};
 use std::ops::{Index, IndexMut};
@@ -49,6 +56,25 @@ impl FrameLine {
         FrameLine(colors)
     }
 
+    /// Create a new `FrameLine` instance, given a slice of `PixelColor`, rounding every color
+    /// with `Rgb565::from_rgb_rounded`, instead of truncating it.
+    pub fn from_pixels_rounded(pixels: &[PixelColor; 64]) -> Self {
+        let mut colors = [Rgb565::default(); 64];
+        for (color, pixel) in colors.iter_mut().zip(pixels.iter()) {
+            *color = Rgb565::from_rgb_rounded(pixel.red, pixel.green, pixel.blue);
+        }
+        FrameLine(colors)
+    }
+
+    /// Create a `PixelFrame` with the colors of this `FrameLine`.
+    pub fn pixel_frame(&self) -> PixelFrame {
+        let mut pixels = [PixelColor::default(); 64];
+        for (pixel, &color) in pixels.iter_mut().zip(self.0.iter()) {
+            *pixel = color.into();
+        }
+        PixelFrame(pixels)
+    }
+
     /// Returns the `FrameLine` as a slice of bytes.
     pub fn as_bytes(&self) -> [u8; 128] {
         self.0.iter()
@@ -66,6 +92,32 @@ impl FrameLine {
     }
 }
 
+impl FrameLine {
+    /// Encode the `FrameLine` with the given `PixelFormat`, as the bytes of a framebuffer
+    /// device.
+    pub fn encode(&self, format: PixelFormat) -> Vec<u8> {
+        let mut bytes = vec![0u8; format.frame_bytes()];
+        for (chunk, &color) in bytes.chunks_mut(format.bytes_per_pixel()).zip(self.0.iter()) {
+            format.encode(color, chunk);
+        }
+        bytes
+    }
+
+    /// Decode a `FrameLine` from the bytes of a framebuffer device, with the given
+    /// `PixelFormat`.
+    ///
+    /// # Panics
+    /// If there are fewer bytes than `PixelFormat::frame_bytes`.
+    pub fn decode(format: PixelFormat, bytes: &[u8]) -> Self {
+        assert!(bytes.len() >= format.frame_bytes());
+        let mut frame = FrameLine::new();
+        for (color, chunk) in frame.0.iter_mut().zip(bytes.chunks(format.bytes_per_pixel())) {
+            *color = format.decode(chunk);
+        }
+        frame
+    }
+}
+
 impl Default for FrameLine {
     fn default() -> Self {
         FrameLine::new()
@@ -151,6 +203,13 @@ impl PixelFrame {
         FrameLine::from_pixels(&colors)
     }
 
+    /// Create a `FrameLine` representing the current `PixelFrame`, with every color rounded to
+    /// the nearest one that the LED Matrix shows. The screen then shows `self.quantized()`,
+    /// instead of the truncated colors of `PixelFrame::frame_line`.
+    pub fn frame_line_rounded(&self) -> FrameLine {
+        FrameLine::from_pixels_rounded(&self.0)
+    }
+
     /// Transpose the LED Matrix. Rows become columns.
     pub fn transpose(&mut self) {
         for row in 0..8 {
@@ -180,6 +239,56 @@ impl PixelFrame {
         self.0.reverse();
     }
 
+    /// Set every pixel to a perceptual `brightness` of its current color, with
+    /// `PixelColor::with_brightness`.
+    pub fn set_brightness(&mut self, brightness: f32) {
+        for pixel in self.0.iter_mut() {
+            *pixel = pixel.with_brightness(brightness);
+        }
+    }
+
+    /// Create a new `PixelFrame` that is `t` of the way from this frame to `other`, pixel by
+    /// pixel, with `PixelColor::lerp`. Frames for increasing values of `t` make a
+    /// cross-dissolve, or, to or from black, a fade.
+    pub fn lerp(&self, other: &PixelFrame, t: f32) -> PixelFrame {
+        let mut frame = *self;
+        for (pixel, &end) in frame.0.iter_mut().zip(other.0.iter()) {
+            *pixel = PixelColor::lerp(*pixel, end, t);
+        }
+        frame
+    }
+
+    /// Create a new `PixelFrame` with every pixel set to the nearest color that the LED Matrix
+    /// shows exactly, with `PixelColor::quantized`. This is the frame shown after writing
+    /// `PixelFrame::frame_line_rounded`.
+    pub fn quantized(&self) -> PixelFrame {
+        let mut frame = *self;
+        for pixel in frame.0.iter_mut() {
+            *pixel = pixel.quantized();
+        }
+        frame
+    }
+
+    /// Returns `true` if every pixel of this frame is equal to that of `other`, within
+    /// `tolerance`, with `PixelColor::approx_eq`. Compares frames that went through the
+    /// RGB565 encoding of the screen.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::{PixelColor, PixelFrame};
+    /// # fn main() {
+    ///     let frame = PixelFrame::new(&[PixelColor::new(0x80, 0x80, 0x80); 64]);
+    ///     let shown = frame.frame_line().pixel_frame();
+    ///     assert_ne!(shown, frame);
+    ///     assert!(shown.approx_eq(&frame, 7));
+    /// # }
+    /// ```
+    pub fn approx_eq(&self, other: &PixelFrame, tolerance: u8) -> bool {
+        self.0.iter()
+              .zip(other.0.iter())
+              .all(|(&pixel, &other)| pixel.approx_eq(other, tolerance))
+    }
+
     /// Returns a `[[PixelColor; 8]; 8]`, organized by rows, from top to bottom.
     pub fn as_rows(&self) -> [[PixelColor; 8]; 8] {
         let pixels = self.0;
@@ -204,8 +313,8 @@ impl PixelFrame {
     /// Create a new `PixelFrame` from a `[[PixelColor; 8]; 8]`, of 8 rows with 8 `PixelColor`s.
     pub fn from_rows(rows: &[[PixelColor; 8]; 8]) -> Self {
         let mut pixels = [PixelColor::default(); 64];
-        for (row_idx, row) in rows.into_iter().enumerate() {
-            for (col_idx, &px) in row.into_iter().enumerate() {
+        for (row_idx, row) in rows.iter().enumerate() {
+            for (col_idx, &px) in row.iter().enumerate() {
                 pixels[row_idx * 8 + col_idx] = px;
             }
         }
@@ -215,8 +324,8 @@ impl PixelFrame {
     /// Create a new `PixelFrame` from a `[[PixelColor; 8]; 8]`, of 8 columns with 8 `PixelColor`s.
     pub fn from_columns(columns: &[[PixelColor; 8]; 8]) -> Self {
         let mut pixels = [PixelColor::default(); 64];
-        for (col_idx, col) in columns.into_iter().enumerate() {
-            for (row_idx, &px) in col.into_iter().enumerate() {
+        for (col_idx, col) in columns.iter().enumerate() {
+            for (row_idx, &px) in col.iter().enumerate() {
                 pixels[row_idx * 8 + col_idx] = px;
             }
         }
@@ -236,9 +345,15 @@ impl From<[PixelColor; 64]> for PixelFrame {
     }
 }
 
-impl Into<[PixelColor; 64]> for PixelFrame {
-    fn into(self) -> [PixelColor; 64] {
-        self.0
+impl From<FrameLine> for PixelFrame {
+    fn from(frame: FrameLine) -> Self {
+        frame.pixel_frame()
+    }
+}
+
+impl From<PixelFrame> for [PixelColor; 64] {
+    fn from(frame: PixelFrame) -> Self {
+        frame.0
     }
 }
 
@@ -410,8 +525,8 @@ mod tests {
         let color: [u8; 128] = [0xE0; 128];
         let frame_line = FrameLine::from_slice(&color);
         frame_line.as_bytes()
-                  .into_iter()
-                  .zip(color.into_iter())
+                  .iter()
+                  .zip(color.iter())
                   .for_each(|(a, b)| {
                                 assert_eq!(a, b);
                             });
@@ -437,13 +552,81 @@ mod tests {
                                                  });
     }
 
+    #[test]
+    fn frame_line_is_encoded_and_decoded_with_a_pixel_format() {
+        let frame_line = PixelFrame::new(&[PixelColor::new(0xF8, 0x80, 0x08); 64]).frame_line();
+        for &format in &[PixelFormat::Rgb565,
+                         PixelFormat::Bgr565,
+                         PixelFormat::Rgb888,
+                         PixelFormat::Xrgb8888]
+        {
+            let bytes = frame_line.encode(format);
+            assert_eq!(bytes.len(), format.frame_bytes());
+            assert_eq!(FrameLine::decode(format, &bytes), frame_line);
+        }
+    }
+
+    #[cfg(all(target_endian = "little", not(feature = "big-endian")))]
+    #[test]
+    fn frame_line_encodes_rgb565_as_its_little_endian_bytes() {
+        let frame_line = PixelFrame::YELLOW.frame_line();
+        assert_eq!(frame_line.encode(PixelFormat::Rgb565), frame_line.as_bytes().to_vec());
+    }
+
+    #[test]
+    #[should_panic]
+    fn frame_line_fails_to_decode_a_partial_frame() {
+        FrameLine::decode(PixelFormat::Xrgb8888, &[0u8; 128]);
+    }
+
+    #[test]
+    fn frames_are_quantized_and_compared_with_a_tolerance() {
+        let mut frame = PixelFrame::new(&[PixelColor::new(0xF0, 0x7F, 0x06); 64]);
+        frame[0] = PixelColor::WHITE;
+        let quantized = frame.quantized();
+        assert_eq!(quantized[0], PixelColor::WHITE);
+        assert_eq!(quantized[1], PixelColor::new(0xEF, 0x7D, 0x08));
+        assert!(quantized.approx_eq(&frame, 4));
+        assert!(!quantized.approx_eq(&frame, 1));
+        let shown = frame.frame_line().pixel_frame();
+        assert!(shown.approx_eq(&frame, 7));
+        assert!(!shown.approx_eq(&frame, 4));
+    }
+
+    #[test]
+    fn frame_line_is_rendered_as_a_pixel_frame() {
+        // colors that survive the RGB565 encoding, unchanged
+        let red = PixelColor::RED;
+        let white = PixelColor::WHITE;
+        let pixel_frame = PixelFrame::new(&[red, white, red, white, red, white, red, white, //
+                                            red, white, red, white, red, white, red, white, //
+                                            red, white, red, white, red, white, red, white, //
+                                            red, white, red, white, red, white, red, white, //
+                                            red, white, red, white, red, white, red, white, //
+                                            red, white, red, white, red, white, red, white, //
+                                            red, white, red, white, red, white, red, white, //
+                                            red, white, red, white, red, white, red, white]);
+        let frame_line = pixel_frame.frame_line();
+        assert_eq!(frame_line.pixel_frame(), pixel_frame);
+        assert_eq!(PixelFrame::from(frame_line), pixel_frame);
+    }
+
+    #[test]
+    fn rounded_frame_line_shows_the_quantized_frame() {
+        let mut pixel_frame = PixelFrame::new(&[PixelColor::new(0xF0, 0x7F, 0x06); 64]);
+        pixel_frame[9] = PixelColor::new(0x80, 0x80, 0x80);
+        let frame_line = pixel_frame.frame_line_rounded();
+        assert_eq!(frame_line.pixel_frame(), pixel_frame.quantized());
+        assert_ne!(pixel_frame.frame_line().pixel_frame(), pixel_frame.quantized());
+    }
+
     #[test]
     fn pixel_frame_is_created_from_a_slice_of_pixel_color() {
         let color_frame = [PixelColor::YELLOW; 64];
         let pixel_frame = PixelFrame::new(&color_frame);
         pixel_frame.0
-                   .into_iter()
-                   .zip(color_frame.into_iter())
+                   .iter()
+                   .zip(color_frame.iter())
                    .for_each(|(a, b)| {
                                  assert_eq!(a, b);
                              });
@@ -480,4 +663,25 @@ mod tests {
         let pixel_frame = PixelFrame::new(PIXEL_FRAME);
         assert_eq!(PixelFrame::from_columns(&test_columns()), pixel_frame);
     }
+
+    #[test]
+    fn pixel_frame_is_interpolated_pixel_by_pixel() {
+        let start = PixelFrame::new(PIXEL_FRAME);
+        assert_eq!(start.lerp(&PixelFrame::BLACK, 0.0), start);
+        assert_eq!(start.lerp(&PixelFrame::BLACK, 1.0), PixelFrame::BLACK);
+        let halfway = start.lerp(&PixelFrame::BLACK, 0.5);
+        assert_eq!(halfway[0], PixelColor::new(128, 0, 0));
+        assert_eq!(halfway[1], PixelColor::new(128, 128, 128));
+    }
+
+    #[test]
+    fn pixel_frame_sets_the_brightness_of_every_pixel() {
+        let mut pixel_frame = PixelFrame::new(PIXEL_FRAME);
+        pixel_frame.set_brightness(0.5);
+        let expected = PIXEL_FRAME.iter()
+                                  .map(|pixel| pixel.with_brightness(0.5))
+                                  .collect::<Vec<PixelColor>>();
+        assert_eq!(&pixel_frame.0[..], &expected[..]);
+        assert_ne!(pixel_frame, PixelFrame::new(PIXEL_FRAME));
+    }
 }
@@ -0,0 +1,66 @@
+//! Frame compositing for the LED Matrix screen
+use super::PixelFrame;
+use color::blend::{BlendMode, RgbaColor};
+
+impl PixelFrame {
+    /// Create a new `PixelFrame` with the opaque pixels of `other` blended over this frame,
+    /// pixel by pixel.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::{BlendMode, PixelColor, PixelFrame};
+    /// # fn main() {
+    ///     let lit = PixelFrame::RED.blend(&PixelFrame::BLUE, BlendMode::Add);
+    ///     assert_eq!(lit, PixelFrame::MAGENTA);
+    ///     let masked = PixelFrame::YELLOW.blend(&PixelFrame::GREEN, BlendMode::Multiply);
+    ///     assert_eq!(masked, PixelFrame::GREEN);
+    /// # }
+    /// ```
+    pub fn blend(&self, other: &PixelFrame, mode: BlendMode) -> PixelFrame {
+        let mut blended = *self;
+        for (pixel, source) in blended.0.iter_mut().zip(other.0.iter()) {
+            *pixel = mode.blend(*pixel, RgbaColor::from(*source));
+        }
+        blended
+    }
+
+    /// Create a new `PixelFrame` with a layer of translucent pixels blended over this frame.
+    /// Transparent pixels of the layer leave this frame showing through, such as around a
+    /// notification icon drawn over a clock.
+    pub fn blend_layer(&self, layer: &[RgbaColor; 64], mode: BlendMode) -> PixelFrame {
+        let mut blended = *self;
+        for (pixel, source) in blended.0.iter_mut().zip(layer.iter()) {
+            *pixel = mode.blend(*pixel, *source);
+        }
+        blended
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use color::PixelColor;
+
+    #[test]
+    fn frames_are_blended_pixel_by_pixel() {
+        let mut backdrop = PixelFrame::WHITE;
+        backdrop[9] = PixelColor::BLACK;
+        let blended = backdrop.blend(&PixelFrame::CYAN, BlendMode::Difference);
+        let mut expected = PixelFrame::RED;
+        expected[9] = PixelColor::CYAN;
+        assert_eq!(blended, expected);
+        assert_eq!(backdrop.blend(&PixelFrame::CYAN, BlendMode::Normal), PixelFrame::CYAN);
+    }
+
+    #[test]
+    fn transparent_pixels_of_a_layer_show_the_frame_through() {
+        let mut layer = [RgbaColor::TRANSPARENT; 64];
+        layer[0] = RgbaColor::from(PixelColor::RED);
+        layer[63] = RgbaColor::from_color(PixelColor::WHITE, 0x80);
+        let blended = PixelFrame::BLUE.blend_layer(&layer, BlendMode::Normal);
+        let mut expected = PixelFrame::BLUE;
+        expected[0] = PixelColor::RED;
+        expected[63] = PixelColor::new(0x80, 0x80, 0xFF);
+        assert_eq!(blended, expected);
+    }
+}
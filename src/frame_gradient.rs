@@ -0,0 +1,111 @@
+//! Gradient fills for the LED Matrix screen
+use super::PixelFrame;
+use color::gradient::Gradient;
+
+/// The direction that a `Gradient` is laid over a `PixelFrame`, from its start to its end.
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum GradientDirection {
+    /// From the left column to the right column.
+    Horizontal,
+    /// From the top row to the bottom row.
+    Vertical,
+    /// From the top-left corner to the bottom-right corner.
+    Diagonal,
+    /// From the center outwards, reaching the end of the gradient at the corners.
+    Radial,
+}
+
+impl GradientDirection {
+    // The position on the gradient of the pixel at the given row, and column.
+    fn position(self, row: usize, column: usize) -> f32 {
+        let (x, y) = (column as f32, row as f32);
+        match self {
+            GradientDirection::Horizontal => x / 7.0,
+            GradientDirection::Vertical => y / 7.0,
+            GradientDirection::Diagonal => (x + y) / 14.0,
+            GradientDirection::Radial => {
+                let (dx, dy) = (x - 3.5, y - 3.5);
+                (dx * dx + dy * dy).sqrt() / (2.0 * 3.5 * 3.5f32).sqrt()
+            }
+        }
+    }
+}
+
+impl PixelFrame {
+    /// Create a new `PixelFrame` filled with a gradient, laid in the given direction.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::{Gradient, GradientDirection, PixelColor, PixelFrame};
+    /// # fn main() {
+    ///     let gradient = Gradient::new(&[PixelColor::BLUE, PixelColor::RED]);
+    ///     let frame = PixelFrame::from_gradient(&gradient, GradientDirection::Vertical);
+    ///     assert_eq!(frame[0], PixelColor::BLUE);
+    ///     assert_eq!(frame[63], PixelColor::RED);
+    /// # }
+    /// ```
+    pub fn from_gradient(gradient: &Gradient, direction: GradientDirection) -> Self {
+        let mut frame = PixelFrame::default();
+        frame.fill_gradient(gradient, direction);
+        frame
+    }
+
+    /// Fill every pixel with a gradient, laid in the given direction.
+    pub fn fill_gradient(&mut self, gradient: &Gradient, direction: GradientDirection) {
+        for (index, pixel) in self.0.iter_mut().enumerate() {
+            *pixel = gradient.sample(direction.position(index / 8, index % 8));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use color::PixelColor;
+
+    fn black_to_white() -> Gradient {
+        Gradient::new(&[PixelColor::BLACK, PixelColor::WHITE])
+    }
+
+    #[test]
+    fn horizontal_and_vertical_gradients_are_transposed() {
+        let gradient = black_to_white();
+        let horizontal = PixelFrame::from_gradient(&gradient, GradientDirection::Horizontal);
+        let mut vertical = PixelFrame::from_gradient(&gradient, GradientDirection::Vertical);
+        for row in horizontal.as_rows().iter() {
+            assert_eq!(row[0], PixelColor::BLACK);
+            assert_eq!(row[3], PixelColor::new(109, 109, 109));
+            assert_eq!(row[7], PixelColor::WHITE);
+        }
+        vertical.transpose();
+        assert_eq!(vertical, horizontal);
+    }
+
+    #[test]
+    fn diagonal_gradient_goes_from_corner_to_corner() {
+        let frame = PixelFrame::from_gradient(&black_to_white(), GradientDirection::Diagonal);
+        assert_eq!(frame[0], PixelColor::BLACK);
+        assert_eq!(frame[63], PixelColor::WHITE);
+        // the other diagonal is halfway.
+        assert_eq!(frame[7], PixelColor::new(128, 128, 128));
+        assert_eq!(frame[56], frame[7]);
+    }
+
+    #[test]
+    fn radial_gradient_is_symmetric_and_ends_at_the_corners() {
+        let mut frame = PixelFrame::default();
+        frame.fill_gradient(&black_to_white(), GradientDirection::Radial);
+        for &corner in &[0, 7, 56, 63] {
+            assert_eq!(frame[corner], PixelColor::WHITE);
+        }
+        for &center in &[28, 35] {
+            assert_eq!(frame[center], frame[27]);
+        }
+        assert!(frame[27].red < frame[18].red);
+        let mut flipped = frame;
+        flipped.flip_h();
+        assert_eq!(flipped, frame);
+        flipped.transpose();
+        assert_eq!(flipped, frame);
+    }
+}
@@ -0,0 +1,174 @@
+//! Indexed-color frames for the LED Matrix screen
+use super::PixelFrame;
+use color::palette::Palette;
+use color::PixelColor;
+use std::fmt;
+use std::ops::{Index, IndexMut, Range};
+
+/// A frame of 64 palette indices, rendered into a `PixelFrame` with a `Palette`.
+///
+/// Rendering the same frame with a palette that is swapped, or cycled, animates it, without
+/// redrawing any pixel.
+///
+/// ```
+/// # extern crate sensehat_screen;
+/// # use sensehat_screen::{IndexedFrame, Palette, PixelColor, PixelFrame};
+/// # fn main() {
+///     let mut frame = IndexedFrame::default();
+///     frame[0] = Palette::STATUS_ERROR;
+///     let status = Palette::status();
+///     let rendered = frame.render(&status);
+///     assert_eq!(rendered[0], status[Palette::STATUS_ERROR]);
+///     assert_eq!(rendered[1], PixelColor::BLACK);
+///
+///     // a spinner, with every color of a 4-color ramp moving one step on each frame.
+///     let ramp = IndexedFrame::new(&[0, 1, 2, 3, 0, 1, 2, 3, //
+///                                    0, 1, 2, 3, 0, 1, 2, 3, //
+///                                    0, 1, 2, 3, 0, 1, 2, 3, //
+///                                    0, 1, 2, 3, 0, 1, 2, 3, //
+///                                    0, 1, 2, 3, 0, 1, 2, 3, //
+///                                    0, 1, 2, 3, 0, 1, 2, 3, //
+///                                    0, 1, 2, 3, 0, 1, 2, 3, //
+///                                    0, 1, 2, 3, 0, 1, 2, 3]);
+///     let frames: Vec<PixelFrame> = ramp.cycle_frames(&Palette::cga(), 0..4);
+///     assert_eq!(frames.len(), 4);
+///     assert_eq!(frames[1][1], Palette::cga()[0]);
+/// # }
+/// ```
+#[derive(Copy, Clone, Eq, Hash, PartialEq)]
+pub struct IndexedFrame([u8; 64]);
+
+impl fmt::Debug for IndexedFrame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "IndexedFrame:")?;
+        for row in self.0.chunks(8) {
+            writeln!(f, "{:?}", row)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for IndexedFrame {
+    fn default() -> Self {
+        IndexedFrame([0; 64])
+    }
+}
+
+impl IndexedFrame {
+    /// Create a new frame of palette indices.
+    pub fn new(indices: &[u8; 64]) -> Self {
+        IndexedFrame(*indices)
+    }
+
+    /// Create a new frame with the index of the nearest palette color of every pixel.
+    pub fn from_pixel_frame(frame: &PixelFrame, palette: &Palette) -> Self {
+        let mut indices = [0; 64];
+        for (index, pixel) in indices.iter_mut().zip(frame.0.iter()) {
+            *index = palette.nearest(*pixel);
+        }
+        IndexedFrame(indices)
+    }
+
+    /// Returns the palette indices of this frame.
+    pub fn indices(&self) -> &[u8; 64] {
+        &self.0
+    }
+
+    /// Render this frame with the colors of `palette`. Indices that are not in the palette
+    /// are rendered black.
+    pub fn render(&self, palette: &Palette) -> PixelFrame {
+        let mut frame = PixelFrame::default();
+        for (pixel, &index) in frame.0.iter_mut().zip(self.0.iter()) {
+            *pixel = palette.get(index).unwrap_or(PixelColor::BLACK);
+        }
+        frame
+    }
+
+    /// Render one frame for every step of cycling the colors of `palette` in `range`, one
+    /// index at a time, ready to be played by an `Animator`.
+    ///
+    /// # Panics
+    /// If `range` is not in the palette.
+    pub fn cycle_frames(&self, palette: &Palette, range: Range<usize>) -> Vec<PixelFrame> {
+        let mut palette = palette.clone();
+        let mut frames = Vec::with_capacity(range.len());
+        for _ in range.clone() {
+            frames.push(self.render(&palette));
+            palette.cycle(range.clone(), 1);
+        }
+        frames
+    }
+}
+
+impl Index<usize> for IndexedFrame {
+    type Output = u8;
+
+    fn index(&self, index: usize) -> &u8 {
+        &self.0[index]
+    }
+}
+
+impl IndexMut<usize> for IndexedFrame {
+    fn index_mut(&mut self, index: usize) -> &mut u8 {
+        &mut self.0[index]
+    }
+}
+
+impl From<[u8; 64]> for IndexedFrame {
+    fn from(indices: [u8; 64]) -> Self {
+        IndexedFrame(indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_are_rendered_with_a_palette() {
+        let palette = Palette::new(&[PixelColor::BLUE, PixelColor::YELLOW]);
+        let mut frame = IndexedFrame::default();
+        frame[63] = 1;
+        frame[62] = 2;
+        let mut expected = PixelFrame::BLUE;
+        expected[63] = PixelColor::YELLOW;
+        expected[62] = PixelColor::BLACK;
+        assert_eq!(frame.render(&palette), expected);
+    }
+
+    #[test]
+    fn swapping_palettes_recolors_the_frame() {
+        let mut frame = IndexedFrame::default();
+        frame[9] = 1;
+        let day = Palette::new(&[PixelColor::WHITE, PixelColor::BLUE]);
+        let night = Palette::new(&[PixelColor::BLACK, PixelColor::RED]);
+        assert_eq!(frame.render(&day)[9], PixelColor::BLUE);
+        assert_eq!(frame.render(&night)[9], PixelColor::RED);
+        assert_eq!(frame.render(&night)[0], PixelColor::BLACK);
+    }
+
+    #[test]
+    fn pixel_frames_are_indexed_by_their_nearest_color() {
+        let palette = Palette::cga();
+        let frame = IndexedFrame::from_pixel_frame(&PixelFrame::YELLOW, &palette);
+        assert_eq!(frame, IndexedFrame::from([14; 64]));
+        assert_eq!(frame.render(&palette)[0], palette[14]);
+    }
+
+    #[test]
+    fn cycling_frames_shift_colors_along_the_indices() {
+        let mut indices = [0; 64];
+        for (position, index) in indices.iter_mut().enumerate() {
+            *index = (position % 3) as u8;
+        }
+        let palette = Palette::new(&[PixelColor::RED, PixelColor::GREEN, PixelColor::BLUE]);
+        let frames = IndexedFrame::new(&indices).cycle_frames(&palette, 0..3);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(&frames[0].as_rows()[0][..3],
+                   &[PixelColor::RED, PixelColor::GREEN, PixelColor::BLUE]);
+        assert_eq!(&frames[1].as_rows()[0][..3],
+                   &[PixelColor::BLUE, PixelColor::RED, PixelColor::GREEN]);
+        assert_eq!(&frames[2].as_rows()[0][..3],
+                   &[PixelColor::GREEN, PixelColor::BLUE, PixelColor::RED]);
+    }
+}
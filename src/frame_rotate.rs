@@ -2,7 +2,7 @@
 use super::PixelFrame;
 
 /// A counter-clockwise angle, multiple of `90°`, used to create rotated `PixelFrame`s.
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Rotate {
     None,
     Ccw90,
@@ -1,24 +1,1117 @@
 //! Framebuffer support for the Sense HAT LED Matrix.
-use framebuffer::{Framebuffer, FramebufferError};
-use FrameLine;
+#[cfg(any(test, feature = "emulator"))]
+#[path = "framebuffer_emulator.rs"]
+pub mod emulator;
+#[path = "framebuffer_scaled.rs"]
+pub mod scaled;
+#[path = "framebuffer_signal.rs"]
+mod signal;
+
+use super::display::Display;
+use super::error::{Result, ScreenError};
+use framebuffer::{Bitfield, FixScreeninfo, VarScreeninfo};
+use libc;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use {FrameLine, PixelFormat, PixelFrame, Rotate};
+
+/// The name that the Sense HAT driver gives to its framebuffer.
+pub const SENSE_HAT_FB_NAME: &str = "RPi-Sense FB";
+
+const FBIOGET_VSCREENINFO: libc::c_ulong = 0x4600;
+const FBIOGET_FSCREENINFO: libc::c_ulong = 0x4602;
+const SENSEFB_FBIOGET_GAMMA: libc::c_ulong = 0xF100;
+const SENSEFB_FBIOSET_GAMMA: libc::c_ulong = 0xF101;
+const SENSEFB_FBIORESET_GAMMA: libc::c_ulong = 0xF102;
+
+/// Gamma table used by the Sense HAT driver by default.
+pub const GAMMA_DEFAULT: [u8; 32] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, //
+    0x02, 0x02, 0x03, 0x03, 0x04, 0x05, 0x06, 0x07, //
+    0x08, 0x09, 0x0A, 0x0B, 0x0C, 0x0E, 0x0F, 0x11, //
+    0x12, 0x14, 0x15, 0x17, 0x19, 0x1B, 0x1D, 0x1F, //
+];
+
+/// Gamma table used by the Sense HAT driver in low-light mode.
+pub const GAMMA_LOW_LIGHT: [u8; 32] = [
+    0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01, //
+    0x01, 0x01, 0x01, 0x01, 0x01, 0x02, 0x02, 0x02, //
+    0x03, 0x03, 0x03, 0x04, 0x04, 0x05, 0x05, 0x06, //
+    0x06, 0x07, 0x07, 0x08, 0x08, 0x09, 0x0A, 0x0A, //
+];
+
+// Largest value accepted in a gamma table, the LED driver uses 5-bit intensities.
+const GAMMA_MAX: u8 = 0x1F;
+
+// Width and height, in pixels, of the LED Matrix.
+const SCREEN_SIZE: u32 = 8;
+
+/// Gamma tables built into the Sense HAT driver.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GammaPreset {
+    /// The default gamma table, `GAMMA_DEFAULT`.
+    Default,
+    /// The low-light gamma table, `GAMMA_LOW_LIGHT`, for dimly lit rooms.
+    LowLight,
+}
+
+impl GammaPreset {
+    /// Returns the gamma table for this preset.
+    pub fn table(self) -> [u8; 32] {
+        match self {
+            GammaPreset::Default => GAMMA_DEFAULT,
+            GammaPreset::LowLight => GAMMA_LOW_LIGHT,
+        }
+    }
+
+    // Argument for the `SENSEFB_FBIORESET_GAMMA` ioctl.
+    fn ioctl_arg(self) -> libc::c_ulong {
+        match self {
+            GammaPreset::Default => 0,
+            GammaPreset::LowLight => 1,
+        }
+    }
+}
+
+/// What a `Screen` leaves on the LED Matrix when it is dropped, or when the process is
+/// interrupted after calling `Screen::install_signal_handler`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum DropAction {
+    /// Leave the last frame on the LED Matrix. This is the default.
+    #[default]
+    Keep,
+    /// Turn off every LED.
+    Clear,
+    /// Show the given frame, such as the one that was displayed before the screen was opened.
+    Restore(FrameLine),
+}
 
 /// This is the main type for interacting with the LED Matrix Screen.
+///
+/// The screen may be given an orientation, with `Screen::set_rotation`, `Screen::set_flip_h`,
+/// and `Screen::set_flip_v`, which is applied to every frame written, and reverted on every
+/// frame read back. This way, the application may always draw in upright coordinates, no
+/// matter how the Sense HAT is mounted.
+///
+/// The screen remembers the last frame written, and skips writing the same frame again. It
+/// also holds a back-buffer, a `PixelFrame` that can be drawn on with `Screen::draw`, and then
+/// shown with `Screen::present`.
+///
+/// ```no_run
+/// # extern crate sensehat_screen;
+/// # use sensehat_screen::{PixelColor, Screen};
+/// # fn main() {
+///     let mut screen = Screen::open_sense_hat().unwrap();
+///     screen.draw(|frame| {
+///         frame[0] = PixelColor::RED;
+///         frame[63] = PixelColor::BLUE;
+///     });
+///     screen.present().unwrap();
+/// # }
+/// ```
 #[derive(Debug)]
 pub struct Screen {
-    framebuffer: Framebuffer,
+    device: File,
+    format: PixelFormat,
+    rotation: Rotate,
+    flip_h: bool,
+    flip_v: bool,
+    last_frame: Option<FrameLine>,
+    back_buffer: PixelFrame,
+    drop_action: DropAction,
+    signal_device: Option<File>,
+    gamma: Box<dyn GammaBackend>,
 }
 
-#[cfg(feature = "linux-framebuffer")]
 impl Screen {
     /// Open the framebuffer to the screen at the given file-system path.
-    pub fn open(path: &str) -> Result<Self, FramebufferError> {
-        let framebuffer = Framebuffer::new(path)?;
-        Ok(Screen { framebuffer })
+    ///
+    /// The pixel format of the device is negotiated from its bits-per-pixel, and the layout
+    /// of its color channels. See `PixelFormat` for the supported formats.
+    ///
+    /// Fails if the device can't be opened, if it is not a framebuffer, if its geometry
+    /// is not that of the LED Matrix, or if its pixel format is not supported.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let (device, var_screen_info, _) = open_framebuffer(path.as_ref())?;
+        Screen::from_device(device, &var_screen_info, Box::new(GammaIoctls))
+    }
+
+    /// Open the framebuffer of the Sense HAT LED Matrix, wherever it is.
+    ///
+    /// The framebuffer number assigned to the Sense HAT changes depending on other displays
+    /// being attached (HDMI, DSI, etc.), so instead of hard-coding a path like `/dev/fb1`,
+    /// this looks up the device with `find_sense_hat_framebuffer("/sys")`.
+    pub fn open_sense_hat() -> Result<Self> {
+        let path = find_sense_hat_framebuffer("/sys")?;
+        Screen::open(path)
+    }
+
+    // Create a `Screen` from an open device, its variable screen information, and where its
+    // gamma table is kept.
+    fn from_device(device: File,
+                   var_screen_info: &VarScreeninfo,
+                   gamma: Box<dyn GammaBackend>)
+                   -> Result<Self> {
+        if var_screen_info.xres != SCREEN_SIZE || var_screen_info.yres != SCREEN_SIZE {
+            return Err(ScreenError::UnsupportedGeometry { width: var_screen_info.xres,
+                                                          height: var_screen_info.yres,
+                                                          bits_per_pixel:
+                                                              var_screen_info.bits_per_pixel, });
+        }
+        let format = pixel_format(var_screen_info)?;
+        Ok(Screen { device,
+                    format,
+                    rotation: Rotate::None,
+                    flip_h: false,
+                    flip_v: false,
+                    last_frame: None,
+                    back_buffer: PixelFrame::default(),
+                    drop_action: DropAction::default(),
+                    signal_device: None,
+                    gamma, })
     }
 
     /// Write the contents of a `FrameLine` into the framebuffer. This will
     /// render the frameline on the screen.
-    pub fn write_frame(&mut self, frame: &FrameLine) {
-        self.framebuffer.write_frame(&frame.as_bytes());
+    ///
+    /// If the frame is the same as the last frame written, nothing is written to the device.
+    ///
+    /// Fails if the device does not accept the whole frame.
+    pub fn write_frame(&mut self, frame: &FrameLine) -> Result<()> {
+        if self.last_frame.as_ref() == Some(frame) {
+            return Ok(());
+        }
+        self.force_write_frame(frame)
+    }
+
+    /// Write the contents of a `FrameLine` into the framebuffer, even if it is the same as
+    /// the last frame written.
+    ///
+    /// Fails if the device does not accept the whole frame.
+    pub fn force_write_frame(&mut self, frame: &FrameLine) -> Result<()> {
+        // a failed write leaves the device in an unknown state.
+        self.last_frame = None;
+        let bytes = self.orient(frame).encode(self.format);
+        let written = self.device.write_at(&bytes, 0)?;
+        if written != bytes.len() {
+            return Err(ScreenError::ShortWrite { written,
+                                                 expected: bytes.len(), });
+        }
+        self.last_frame = Some(*frame);
+        Ok(())
+    }
+
+    /// Returns the last frame written to the screen, if any is known.
+    pub fn last_frame(&self) -> Option<&FrameLine> {
+        self.last_frame.as_ref()
+    }
+
+    /// Forget the last frame written, so that the next frame is written to the device even
+    /// if it is the same. Useful when other processes may have written to the screen.
+    pub fn invalidate(&mut self) {
+        self.last_frame = None;
+    }
+
+    /// Returns the back-buffer, the `PixelFrame` that is shown by `Screen::present`.
+    pub fn back_buffer(&self) -> &PixelFrame {
+        &self.back_buffer
+    }
+
+    /// Draw on the back-buffer. Nothing is shown on the screen until `Screen::present`
+    /// is called.
+    pub fn draw<F: FnOnce(&mut PixelFrame)>(&mut self, draw: F) {
+        draw(&mut self.back_buffer);
+    }
+
+    /// Show the back-buffer on the screen. The back-buffer keeps its contents, so that the
+    /// next frame may be drawn on top of it.
+    ///
+    /// If the back-buffer has not changed since the last frame written, nothing is written
+    /// to the device.
+    pub fn present(&mut self) -> Result<()> {
+        let frame = self.back_buffer.frame_line();
+        self.write_frame(&frame)
+    }
+
+    /// Read the `FrameLine` that is currently displayed on the screen, as written by this,
+    /// or any other, process.
+    ///
+    /// Fails if the device does not return a whole frame.
+    pub fn read_frame(&self) -> Result<FrameLine> {
+        let mut bytes = vec![0u8; self.format.frame_bytes()];
+        let read = self.device.read_at(&mut bytes, 0)?;
+        if read != bytes.len() {
+            return Err(ScreenError::ShortRead { read,
+                                                expected: bytes.len(), });
+        }
+        Ok(self.unorient(&FrameLine::decode(self.format, &bytes)))
+    }
+
+    /// Returns the pixel format of the device, negotiated when the screen was opened.
+    pub fn pixel_format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// Read the `PixelFrame` that is currently displayed on the screen.
+    pub fn read_pixel_frame(&self) -> Result<PixelFrame> {
+        self.read_frame().map(|frame| frame.pixel_frame())
+    }
+
+    /// Returns the counter-clockwise rotation applied to every frame.
+    pub fn rotation(&self) -> Rotate {
+        self.rotation
+    }
+
+    /// Set the counter-clockwise rotation applied to every frame written to, or read from,
+    /// the screen.
+    pub fn set_rotation(&mut self, rotation: Rotate) {
+        self.rotation = rotation;
+        self.invalidate();
+        self.update_signal_frame();
+    }
+
+    /// Returns `true` if every frame is flipped horizontally.
+    pub fn flip_h(&self) -> bool {
+        self.flip_h
+    }
+
+    /// Set whether every frame written to, or read from, the screen is flipped horizontally.
+    pub fn set_flip_h(&mut self, flip_h: bool) {
+        self.flip_h = flip_h;
+        self.invalidate();
+        self.update_signal_frame();
+    }
+
+    /// Returns `true` if every frame is flipped vertically.
+    pub fn flip_v(&self) -> bool {
+        self.flip_v
+    }
+
+    /// Set whether every frame written to, or read from, the screen is flipped vertically.
+    pub fn set_flip_v(&mut self, flip_v: bool) {
+        self.flip_v = flip_v;
+        self.invalidate();
+        self.update_signal_frame();
+    }
+
+    // Turn a frame in upright coordinates into the frame shown by the device. Frames are
+    // flipped first, then rotated.
+    fn orient(&self, frame: &FrameLine) -> FrameLine {
+        if self.is_upright() {
+            return *frame;
+        }
+        let mut pixels = frame.pixel_frame();
+        self.flip(&mut pixels);
+        pixels.rotate(self.rotation).frame_line()
+    }
+
+    // Turn a frame shown by the device back into upright coordinates.
+    fn unorient(&self, frame: &FrameLine) -> FrameLine {
+        if self.is_upright() {
+            return *frame;
+        }
+        let inverse = match self.rotation {
+            Rotate::None => Rotate::None,
+            Rotate::Ccw90 => Rotate::Ccw270,
+            Rotate::Ccw180 => Rotate::Ccw180,
+            Rotate::Ccw270 => Rotate::Ccw90,
+        };
+        let mut pixels = frame.pixel_frame().rotate(inverse);
+        self.flip(&mut pixels);
+        pixels.frame_line()
+    }
+
+    // Flip the pixel frame, as configured.
+    fn flip(&self, pixels: &mut PixelFrame) {
+        if self.flip_h {
+            pixels.flip_h();
+        }
+        if self.flip_v {
+            pixels.flip_v();
+        }
+    }
+
+    // Returns `true` if frames are shown on the device as they are written.
+    fn is_upright(&self) -> bool {
+        self.rotation == Rotate::None && !self.flip_h && !self.flip_v
+    }
+
+    /// Returns what the screen leaves on the LED Matrix when it is dropped.
+    pub fn drop_action(&self) -> DropAction {
+        self.drop_action
+    }
+
+    /// Set what the screen leaves on the LED Matrix when it is dropped, or when the process
+    /// is interrupted after calling `Screen::install_signal_handler`.
+    ///
+    /// ```no_run
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::{DropAction, PixelFrame, Screen};
+    /// # fn main() {
+    ///     let mut screen = Screen::open_sense_hat().unwrap();
+    ///     screen.set_drop_action(DropAction::Clear);
+    ///     screen.install_signal_handler().unwrap();
+    ///     screen.write_frame(&PixelFrame::RED.frame_line()).unwrap();
+    ///     // the LED Matrix is turned off when `screen` is dropped, or on Ctrl-C.
+    /// # }
+    /// ```
+    pub fn set_drop_action(&mut self, drop_action: DropAction) {
+        self.drop_action = drop_action;
+        self.update_signal_frame();
+    }
+
+    /// Save the frame that is currently displayed, and restore it when the screen is dropped.
+    pub fn restore_on_drop(&mut self) -> Result<()> {
+        let frame = Screen::read_frame(self)?;
+        self.set_drop_action(DropAction::Restore(frame));
+        Ok(())
+    }
+
+    /// Install a handler for `SIGINT`, and `SIGTERM`, that performs the drop action of this
+    /// screen before the process terminates.
+    ///
+    /// The handler keeps a duplicate of the device file-descriptor open, and follows later
+    /// changes to the drop action and orientation of this screen. When the screen is dropped,
+    /// the handler no longer writes to the device, and the duplicate is closed.
+    ///
+    /// The process has a single handler, so only one screen at a time can own it. On any other
+    /// screen, this fails with `ScreenError::SignalHandlerInUse`, until the owner is dropped.
+    /// Actions that the signals had before, such as a handler of the application, are kept,
+    /// and run after the drop action. Without one, the signal terminates the process.
+    pub fn install_signal_handler(&mut self) -> Result<()> {
+        if self.signal_device.is_none() {
+            let device = self.device.try_clone()?;
+            if !signal::claim(device.as_raw_fd()) {
+                return Err(ScreenError::SignalHandlerInUse);
+            }
+            self.signal_device = Some(device);
+        }
+        self.update_signal_frame();
+        signal::install()?;
+        Ok(())
+    }
+
+    // Returns the frame that is written when the screen is dropped, if any.
+    fn drop_frame(&self) -> Option<FrameLine> {
+        match self.drop_action {
+            DropAction::Keep => None,
+            DropAction::Clear => Some(PixelFrame::BLACK.frame_line()),
+            DropAction::Restore(frame) => Some(frame),
+        }
+    }
+
+    // Prepare the frame written by the signal handler, if it was installed by this screen.
+    fn update_signal_frame(&self) {
+        if let Some(ref device) = self.signal_device {
+            let bytes = self.drop_frame()
+                            .map(|frame| self.orient(&frame).encode(self.format));
+            signal::set_frame(device.as_raw_fd(), bytes.as_deref());
+        }
+    }
+
+    /// Get the gamma table used by the LED Matrix driver.
+    ///
+    /// Each of the 32 entries maps a 5-bit color intensity to the 5-bit intensity that
+    /// drives the LEDs.
+    pub fn gamma(&self) -> Result<[u8; 32]> {
+        self.gamma.get(&self.device)
+    }
+
+    /// Set the gamma table used by the LED Matrix driver.
+    ///
+    /// Fails with `ScreenError::InvalidGamma` if any entry is greater than `0x1F`.
+    pub fn set_gamma(&mut self, gamma: [u8; 32]) -> Result<()> {
+        if let Some((index, &value)) = gamma.iter().enumerate().find(|&(_, &v)| v > GAMMA_MAX) {
+            return Err(ScreenError::InvalidGamma { index, value });
+        }
+        self.gamma.set(&self.device, &gamma)
+    }
+
+    /// Reset the gamma table used by the LED Matrix driver to one of its built-in presets.
+    pub fn reset_gamma(&mut self, preset: GammaPreset) -> Result<()> {
+        self.gamma.reset(&self.device, preset)
+    }
+
+    /// Returns `true` if the LED Matrix driver is using the low-light gamma table.
+    pub fn low_light(&self) -> Result<bool> {
+        self.gamma().map(|gamma| gamma == GAMMA_LOW_LIGHT)
+    }
+
+    /// Turn the low-light mode on, or off.
+    ///
+    /// Unlike `PixelColor::dim`, this lowers the brightness of the LEDs without losing
+    /// color precision in the frames.
+    pub fn set_low_light(&mut self, low_light: bool) -> Result<()> {
+        if low_light {
+            self.reset_gamma(GammaPreset::LowLight)
+        } else {
+            self.reset_gamma(GammaPreset::Default)
+        }
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        // the duplicate device is closed after the handler stops writing to it, so that the
+        // handler never writes to another file that reuses its descriptor.
+        if let Some(device) = self.signal_device.take() {
+            signal::release(device.as_raw_fd());
+            drop(device);
+        }
+        if let Some(frame) = self.drop_frame() {
+            let _ = self.force_write_frame(&frame);
+        }
+    }
+}
+
+impl Display for Screen {
+    fn write_frame(&mut self, frame: &FrameLine) -> Result<()> {
+        Screen::write_frame(self, frame)
+    }
+
+    fn read_frame(&self) -> Result<Option<FrameLine>> {
+        Screen::read_frame(self).map(Some)
+    }
+}
+
+/// Find the path to the Sense HAT framebuffer device, `/dev/fbN`, by scanning the
+/// `class/graphics/fb*/name` files under `sysfs_root` (usually `/sys`) for the name
+/// of the Sense HAT driver, `SENSE_HAT_FB_NAME`.
+///
+/// Fails with `ScreenError::DeviceNotFound` if no framebuffer has that name.
+pub fn find_sense_hat_framebuffer<P: AsRef<Path>>(sysfs_root: P) -> Result<PathBuf> {
+    let graphics = sysfs_root.as_ref().join("class").join("graphics");
+    let not_found = || ScreenError::DeviceNotFound { name: SENSE_HAT_FB_NAME.to_string() };
+    let entries = match fs::read_dir(&graphics) {
+        Ok(entries) => entries,
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Err(not_found()),
+        Err(err) => return Err(err.into()),
+    };
+    let mut framebuffers = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if let Some(number) = entry.file_name().to_str().and_then(framebuffer_number) {
+            framebuffers.push((number, entry.path()));
+        }
+    }
+    framebuffers.sort();
+    for (number, path) in framebuffers {
+        match fs::read_to_string(path.join("name")) {
+            Ok(ref name) if name.trim() == SENSE_HAT_FB_NAME => {
+                return Ok(PathBuf::from(format!("/dev/fb{}", number)));
+            }
+            Ok(_) => continue,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Err(not_found())
+}
+
+// Open a framebuffer device, and get its variable, and fixed, screen information.
+fn open_framebuffer(path: &Path) -> Result<(File, VarScreeninfo, FixScreeninfo)> {
+    let device = OpenOptions::new().read(true)
+                                   .write(true)
+                                   .open(path)
+                                   .map_err(|source| {
+                                                ScreenError::Open { path: path.to_path_buf(),
+                                                                    source, }
+                                            })?;
+    let fix_screen_info = get_fix_screeninfo(&device).map_err(|err| match err {
+        ScreenError::Ioctl { ref source, .. } if source.raw_os_error() == Some(libc::ENOTTY) => {
+            ScreenError::WrongDevice { path: path.to_path_buf(),
+                                       reason: "not a framebuffer device".to_string(), }
+        }
+        err => err,
+    })?;
+    let var_screen_info = get_var_screeninfo(&device)?;
+    Ok((device, var_screen_info, fix_screen_info))
+}
+
+// Parse the number in a framebuffer name, such as `fb1`.
+fn framebuffer_number(name: &str) -> Option<u32> {
+    name.strip_prefix("fb").and_then(|number| number.parse().ok())
+}
+
+// Negotiate the pixel format from the variable screen information of the device.
+fn pixel_format(info: &VarScreeninfo) -> Result<PixelFormat> {
+    let (red, green, blue) = (bitfield(&info.red), bitfield(&info.green), bitfield(&info.blue));
+    PixelFormat::negotiate(info.bits_per_pixel, red, green, blue).ok_or(
+        ScreenError::UnsupportedPixelFormat { bits_per_pixel: info.bits_per_pixel,
+                                              red,
+                                              green,
+                                              blue, },
+    )
+}
+
+// The `(offset, length)` of a color channel.
+fn bitfield(field: &Bitfield) -> (u32, u32) {
+    (field.offset, field.length)
+}
+
+// Get the variable screen information of the framebuffer device.
+fn get_var_screeninfo(device: &File) -> Result<VarScreeninfo> {
+    let mut info = VarScreeninfo::default();
+    unsafe {
+        ioctl(device, FBIOGET_VSCREENINFO, "FBIOGET_VSCREENINFO", &mut info as *mut _)?;
+    }
+    Ok(info)
+}
+
+// Get the fixed screen information of the framebuffer device.
+fn get_fix_screeninfo(device: &File) -> Result<FixScreeninfo> {
+    let mut info = FixScreeninfo::default();
+    unsafe {
+        ioctl(device, FBIOGET_FSCREENINFO, "FBIOGET_FSCREENINFO", &mut info as *mut _)?;
+    }
+    Ok(info)
+}
+
+// Where a `Screen` gets, and sets, the gamma table of the LED Matrix driver, chosen when the
+// `Screen` is created.
+trait GammaBackend: fmt::Debug + Send + Sync {
+    fn get(&self, device: &File) -> Result<[u8; 32]>;
+    fn set(&self, device: &File, gamma: &[u8; 32]) -> Result<()>;
+    fn reset(&self, device: &File, preset: GammaPreset) -> Result<()>;
+}
+
+// The gamma ioctls of the Sense HAT driver.
+#[derive(Debug)]
+struct GammaIoctls;
+
+impl GammaBackend for GammaIoctls {
+    fn get(&self, device: &File) -> Result<[u8; 32]> {
+        get_gamma_lut(device)
+    }
+
+    fn set(&self, device: &File, gamma: &[u8; 32]) -> Result<()> {
+        set_gamma_lut(device, gamma)
+    }
+
+    fn reset(&self, device: &File, preset: GammaPreset) -> Result<()> {
+        reset_gamma_lut(device, preset)
+    }
+}
+
+// Get the gamma table of the LED Matrix driver.
+fn get_gamma_lut(device: &File) -> Result<[u8; 32]> {
+    let mut gamma = [0u8; 32];
+    unsafe {
+        ioctl(device, SENSEFB_FBIOGET_GAMMA, "SENSEFB_FBIOGET_GAMMA", gamma.as_mut_ptr())?;
+    }
+    Ok(gamma)
+}
+
+// Set the gamma table of the LED Matrix driver.
+fn set_gamma_lut(device: &File, gamma: &[u8; 32]) -> Result<()> {
+    unsafe {
+        ioctl(device, SENSEFB_FBIOSET_GAMMA, "SENSEFB_FBIOSET_GAMMA", gamma.as_ptr())?;
+    }
+    Ok(())
+}
+
+// Reset the gamma table of the LED Matrix driver to one of its presets.
+fn reset_gamma_lut(device: &File, preset: GammaPreset) -> Result<()> {
+    unsafe {
+        ioctl(device,
+              SENSEFB_FBIORESET_GAMMA,
+              "SENSEFB_FBIORESET_GAMMA",
+              preset.ioctl_arg())?;
+    }
+    Ok(())
+}
+
+// Issue an `ioctl` request on the device. Only called by the wrappers above, which fix the
+// type of `arg` for each request.
+//
+// # Safety
+// `arg` must be what `request` expects: a pointer to memory of the type, and size, that the
+// driver reads, or writes, or an integer for requests that take a value.
+unsafe fn ioctl<T>(device: &File,
+                   request: libc::c_ulong,
+                   name: &'static str,
+                   arg: T)
+                   -> Result<libc::c_int> {
+    match libc::ioctl(device.as_raw_fd(), request as _, arg) {
+        -1 => Err(ScreenError::Ioctl { request: name,
+                                       source: io::Error::last_os_error(), }),
+        ret => Ok(ret),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+    use PixelColor;
+
+    // Path to a scratch file, unique to this test process.
+    fn scratch_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("sensehat-screen-{}-{}", process::id(), name))
+    }
+
+    // Screen information of the Sense HAT framebuffer.
+    fn sense_hat_var_screeninfo() -> VarScreeninfo {
+        VarScreeninfo { xres: 8,
+                        yres: 8,
+                        bits_per_pixel: 16,
+                        red: channel(11, 5),
+                        green: channel(5, 6),
+                        blue: channel(0, 5),
+                        ..Default::default() }
+    }
+
+    // Screen information of an 8x8 framebuffer with 32-bit XRGB pixels.
+    fn xrgb8888_var_screeninfo() -> VarScreeninfo {
+        VarScreeninfo { xres: 8,
+                        yres: 8,
+                        bits_per_pixel: 32,
+                        red: channel(16, 8),
+                        green: channel(8, 8),
+                        blue: channel(0, 8),
+                        ..Default::default() }
+    }
+
+    fn channel(offset: u32, length: u32) -> Bitfield {
+        Bitfield { offset,
+                   length,
+                   msb_right: 0, }
+    }
+
+    // Create a fake sysfs tree, with a framebuffer entry for each of the given names.
+    fn fake_sysfs(name: &str, framebuffers: &[(&str, &str)]) -> PathBuf {
+        let root = scratch_path(name);
+        for &(entry, fb_name) in framebuffers {
+            let dir = root.join("class").join("graphics").join(entry);
+            fs::create_dir_all(&dir).unwrap();
+            if !fb_name.is_empty() {
+                fs::write(dir.join("name"), format!("{}\n", fb_name)).unwrap();
+            }
+        }
+        root
+    }
+
+    // Open a `Screen` on top of a regular 128-byte file.
+    fn file_screen(path: &Path) -> Screen {
+        file_screen_with_info(path, &sense_hat_var_screeninfo()).unwrap()
+    }
+
+    // Open a `Screen` on top of a regular file, sized for the given screen information.
+    fn file_screen_with_info(path: &Path, info: &VarScreeninfo) -> Result<Screen> {
+        let size = (info.xres * info.yres * info.bits_per_pixel / 8) as usize;
+        fs::write(path, vec![0u8; size]).unwrap();
+        let device = OpenOptions::new().read(true).write(true).open(path).unwrap();
+        Screen::from_device(device, info, Box::new(GammaIoctls))
+    }
+
+    #[test]
+    fn screen_fails_to_open_a_missing_device() {
+        let path = scratch_path("missing");
+        match Screen::open(&path) {
+            Err(ScreenError::Open { path: err_path, .. }) => assert_eq!(err_path, path),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn screen_fails_to_open_a_device_that_is_not_a_framebuffer() {
+        let path = scratch_path("not-a-framebuffer");
+        fs::write(&path, &[0u8; 128][..]).unwrap();
+        let result = Screen::open(&path);
+        fs::remove_file(&path).unwrap();
+        match result {
+            Err(ScreenError::WrongDevice { .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn screen_reads_back_the_frame_on_the_device() {
+        let path = scratch_path("read-frame");
+        let screen = file_screen(&path);
+        let frame_line = PixelFrame::MAGENTA.frame_line();
+        fs::write(&path, frame_line.encode(PixelFormat::Rgb565)).unwrap();
+        let read = screen.read_frame();
+        let read_pixels = screen.read_pixel_frame();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap(), frame_line);
+        assert_eq!(read_pixels.unwrap(), frame_line.pixel_frame());
+    }
+
+    #[test]
+    fn screen_fails_to_read_back_a_partial_frame() {
+        let path = scratch_path("read-short-frame");
+        let screen = file_screen(&path);
+        fs::write(&path, &[0u8; 64][..]).unwrap();
+        let read = screen.read_frame();
+        fs::remove_file(&path).unwrap();
+        match read {
+            Err(ScreenError::ShortRead { read: 64,
+                                         expected: 128, }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn screen_restores_a_previously_read_frame() {
+        let path = scratch_path("restore-frame");
+        let mut screen = file_screen(&path);
+        screen.write_frame(&PixelFrame::GREEN.frame_line()).unwrap();
+        let saved = screen.read_frame().unwrap();
+        screen.write_frame(&PixelFrame::RED.frame_line()).unwrap();
+        screen.write_frame(&saved).unwrap();
+        let restored = screen.read_frame();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(restored.unwrap(), PixelFrame::GREEN.frame_line());
+    }
+
+    // A frame with a single, lit, pixel at the top-left corner.
+    fn corner_frame() -> PixelFrame {
+        let mut frame = PixelFrame::BLACK;
+        frame[0] = PixelColor::WHITE;
+        frame
+    }
+
+    #[test]
+    fn screen_starts_upright() {
+        let path = scratch_path("upright");
+        let screen = file_screen(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(screen.rotation(), Rotate::None);
+        assert!(!screen.flip_h());
+        assert!(!screen.flip_v());
+    }
+
+    #[test]
+    fn screen_rotates_every_frame_written() {
+        let path = scratch_path("rotate-write");
+        let mut screen = file_screen(&path);
+        screen.set_rotation(Rotate::Ccw90);
+        screen.write_frame(&corner_frame().frame_line()).unwrap();
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let expected = corner_frame().rotate(Rotate::Ccw90).frame_line();
+        assert_eq!(written, expected.encode(PixelFormat::Rgb565));
+    }
+
+    #[test]
+    fn screen_flips_every_frame_written() {
+        let path = scratch_path("flip-write");
+        let mut screen = file_screen(&path);
+        screen.set_flip_h(true);
+        screen.set_flip_v(true);
+        screen.write_frame(&corner_frame().frame_line()).unwrap();
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let expected = corner_frame().rotate(Rotate::Ccw180).frame_line();
+        assert_eq!(written, expected.encode(PixelFormat::Rgb565));
+    }
+
+    #[test]
+    fn screen_reads_back_frames_in_upright_coordinates() {
+        let path = scratch_path("orientation-read");
+        let mut screen = file_screen(&path);
+        screen.set_rotation(Rotate::Ccw270);
+        screen.set_flip_h(true);
+        let frame_line = corner_frame().frame_line();
+        screen.write_frame(&frame_line).unwrap();
+        let read = screen.read_frame();
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_ne!(written, frame_line.encode(PixelFormat::Rgb565));
+        assert_eq!(read.unwrap(), frame_line);
+    }
+
+    #[test]
+    fn screen_skips_writing_the_same_frame_twice() {
+        let path = scratch_path("skip-write");
+        let mut screen = file_screen(&path);
+        let frame_line = PixelFrame::BLUE.frame_line();
+        screen.write_frame(&frame_line).unwrap();
+        // another process writes to the screen
+        fs::write(&path, &[0u8; 128][..]).unwrap();
+        screen.write_frame(&frame_line).unwrap();
+        let skipped = fs::read(&path).unwrap();
+        screen.force_write_frame(&frame_line).unwrap();
+        let forced = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(&skipped[..], &[0u8; 128][..]);
+        assert_eq!(forced, frame_line.encode(PixelFormat::Rgb565));
+    }
+
+    #[test]
+    fn screen_writes_the_same_frame_after_being_invalidated() {
+        let path = scratch_path("invalidate");
+        let mut screen = file_screen(&path);
+        let frame_line = PixelFrame::BLUE.frame_line();
+        screen.write_frame(&frame_line).unwrap();
+        assert_eq!(screen.last_frame(), Some(&frame_line));
+        fs::write(&path, &[0u8; 128][..]).unwrap();
+        screen.invalidate();
+        assert_eq!(screen.last_frame(), None);
+        screen.write_frame(&frame_line).unwrap();
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, frame_line.encode(PixelFormat::Rgb565));
+    }
+
+    #[test]
+    fn screen_writes_the_same_frame_after_changing_orientation() {
+        let path = scratch_path("orientation-invalidate");
+        let mut screen = file_screen(&path);
+        let frame_line = corner_frame().frame_line();
+        screen.write_frame(&frame_line).unwrap();
+        screen.set_flip_h(true);
+        screen.write_frame(&frame_line).unwrap();
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let mut expected = corner_frame();
+        expected.flip_h();
+        assert_eq!(written, expected.frame_line().encode(PixelFormat::Rgb565));
+    }
+
+    #[test]
+    fn screen_presents_the_back_buffer() {
+        let path = scratch_path("present");
+        let mut screen = file_screen(&path);
+        screen.draw(|frame| frame[0] = PixelColor::WHITE);
+        let before = fs::read(&path).unwrap();
+        screen.present().unwrap();
+        let after = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(&before[..], &[0u8; 128][..]);
+        assert_eq!(after, corner_frame().frame_line().encode(PixelFormat::Rgb565));
+        assert_eq!(screen.back_buffer(), &corner_frame());
+    }
+
+    #[test]
+    fn screen_keeps_the_last_frame_when_dropped_by_default() {
+        let path = scratch_path("drop-keep");
+        let mut screen = file_screen(&path);
+        assert_eq!(screen.drop_action(), DropAction::Keep);
+        screen.write_frame(&PixelFrame::RED.frame_line()).unwrap();
+        drop(screen);
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, PixelFrame::RED.frame_line().encode(PixelFormat::Rgb565));
+    }
+
+    #[test]
+    fn screen_clears_the_device_when_dropped() {
+        let path = scratch_path("drop-clear");
+        let mut screen = file_screen(&path);
+        screen.set_drop_action(DropAction::Clear);
+        screen.write_frame(&PixelFrame::RED.frame_line()).unwrap();
+        drop(screen);
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, PixelFrame::BLACK.frame_line().encode(PixelFormat::Rgb565));
+    }
+
+    #[test]
+    fn screen_restores_the_saved_frame_when_dropped() {
+        let path = scratch_path("drop-restore");
+        let mut screen = file_screen(&path);
+        fs::write(&path, PixelFrame::GREEN.frame_line().encode(PixelFormat::Rgb565)).unwrap();
+        screen.restore_on_drop().unwrap();
+        screen.write_frame(&PixelFrame::RED.frame_line()).unwrap();
+        drop(screen);
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, PixelFrame::GREEN.frame_line().encode(PixelFormat::Rgb565));
+    }
+
+    #[test]
+    fn signal_handler_writes_the_drop_frame_of_the_screen() {
+        let path = scratch_path("signal-handler");
+        let mut screen = file_screen(&path);
+        screen.set_rotation(Rotate::Ccw90);
+        screen.set_drop_action(DropAction::Restore(corner_frame().frame_line()));
+        screen.install_signal_handler().unwrap();
+        screen.write_frame(&PixelFrame::RED.frame_line()).unwrap();
+        // what the handler does, without terminating the test process
+        assert!(signal::write_frame());
+        let written = fs::read(&path).unwrap();
+        // every frame prepared is published whole, in either of the two slots.
+        screen.set_drop_action(DropAction::Restore(PixelFrame::BLUE.frame_line()));
+        screen.set_drop_action(DropAction::Restore(PixelFrame::GREEN.frame_line()));
+        assert!(signal::write_frame());
+        let rewritten = fs::read(&path).unwrap();
+        screen.set_drop_action(DropAction::Keep);
+        assert!(!signal::write_frame());
+        // the handler has a single owner, until it is dropped.
+        let other_path = scratch_path("signal-handler-other");
+        let mut other = file_screen(&other_path);
+        let in_use = other.install_signal_handler();
+        drop(screen);
+        let installed = other.install_signal_handler();
+        drop(other);
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&other_path).unwrap();
+        let expected = corner_frame().rotate(Rotate::Ccw90).frame_line();
+        assert_eq!(written, expected.encode(PixelFormat::Rgb565));
+        assert_eq!(rewritten, PixelFrame::GREEN.frame_line().encode(PixelFormat::Rgb565));
+        match in_use {
+            Err(ScreenError::SignalHandlerInUse) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(installed.is_ok());
+    }
+
+    #[test]
+    fn gamma_presets_have_driver_tables() {
+        assert_eq!(GammaPreset::Default.table(), GAMMA_DEFAULT);
+        assert_eq!(GammaPreset::LowLight.table(), GAMMA_LOW_LIGHT);
+        assert!(GAMMA_DEFAULT.iter().chain(GAMMA_LOW_LIGHT.iter()).all(|&v| v <= GAMMA_MAX));
+    }
+
+    #[test]
+    fn screen_rejects_gamma_tables_with_out_of_range_values() {
+        let path = scratch_path("invalid-gamma");
+        let mut screen = file_screen(&path);
+        let mut gamma = GAMMA_DEFAULT;
+        gamma[7] = 0x20;
+        let result = screen.set_gamma(gamma);
+        fs::remove_file(&path).unwrap();
+        match result {
+            Err(ScreenError::InvalidGamma { index: 7,
+                                            value: 0x20, }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn screen_reports_failed_gamma_ioctls() {
+        let path = scratch_path("gamma-ioctl");
+        let mut screen = file_screen(&path);
+        let get = screen.gamma();
+        let reset = screen.set_low_light(true);
+        fs::remove_file(&path).unwrap();
+        match get {
+            Err(ScreenError::Ioctl { request: "SENSEFB_FBIOGET_GAMMA",
+                                     .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match reset {
+            Err(ScreenError::Ioctl { request: "SENSEFB_FBIORESET_GAMMA",
+                                     .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sense_hat_framebuffer_is_found_by_its_driver_name() {
+        let root = fake_sysfs("sysfs-found",
+                              &[("fb0", "BCM2708 FB"), ("fbcon", ""), ("fb2", "RPi-Sense FB")]);
+        let found = find_sense_hat_framebuffer(&root);
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(found.unwrap(), PathBuf::from("/dev/fb2"));
+    }
+
+    #[test]
+    fn sense_hat_framebuffer_is_not_found_without_a_matching_driver_name() {
+        let root = fake_sysfs("sysfs-not-found", &[("fb0", "BCM2708 FB")]);
+        let found = find_sense_hat_framebuffer(&root);
+        fs::remove_dir_all(&root).unwrap();
+        match found {
+            Err(ScreenError::DeviceNotFound { name }) => assert_eq!(name, SENSE_HAT_FB_NAME),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn sense_hat_framebuffer_is_not_found_without_graphics_class() {
+        let root = scratch_path("sysfs-empty");
+        match find_sense_hat_framebuffer(&root) {
+            Err(ScreenError::DeviceNotFound { .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn screen_rejects_devices_with_unsupported_geometry() {
+        let path = scratch_path("geometry");
+        fs::write(&path, &[0u8; 128][..]).unwrap();
+        let device = File::open(&path).unwrap();
+        let mut info = sense_hat_var_screeninfo();
+        info.xres = 640;
+        info.yres = 480;
+        let result = Screen::from_device(device, &info, Box::new(GammaIoctls));
+        fs::remove_file(&path).unwrap();
+        match result {
+            Err(ScreenError::UnsupportedGeometry { width: 640,
+                                                   height: 480,
+                                                   bits_per_pixel: 16, }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn screen_negotiates_rgb565_for_the_sense_hat() {
+        let path = scratch_path("format-rgb565");
+        let screen = file_screen(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(screen.pixel_format(), PixelFormat::Rgb565);
+    }
+
+    #[test]
+    fn screen_negotiates_rgb565_for_16_bit_devices_without_channels() {
+        let path = scratch_path("format-no-channels");
+        let info = VarScreeninfo { xres: 8,
+                                   yres: 8,
+                                   bits_per_pixel: 16,
+                                   ..Default::default() };
+        let screen = file_screen_with_info(&path, &info);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(screen.unwrap().pixel_format(), PixelFormat::Rgb565);
+    }
+
+    #[test]
+    fn screen_writes_and_reads_frames_in_the_negotiated_format() {
+        let path = scratch_path("format-xrgb8888");
+        let mut screen = file_screen_with_info(&path, &xrgb8888_var_screeninfo()).unwrap();
+        let frame_line = corner_frame().frame_line();
+        screen.write_frame(&frame_line).unwrap();
+        let written = fs::read(&path).unwrap();
+        let read = screen.read_frame();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(screen.pixel_format(), PixelFormat::Xrgb8888);
+        assert_eq!(written, frame_line.encode(PixelFormat::Xrgb8888));
+        assert_eq!(read.unwrap(), frame_line);
+    }
+
+    #[test]
+    fn screen_rejects_devices_with_unsupported_pixel_format() {
+        let path = scratch_path("format-unsupported");
+        let mut info = xrgb8888_var_screeninfo();
+        info.red = channel(0, 8);
+        info.blue = channel(16, 8);
+        let result = file_screen_with_info(&path, &info);
+        fs::remove_file(&path).unwrap();
+        match result {
+            Err(ScreenError::UnsupportedPixelFormat { bits_per_pixel: 32,
+                                                      red: (0, 8),
+                                                      green: (8, 8),
+                                                      blue: (16, 8), }) => {}
+            other => panic!("unexpected result: {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn screen_writes_frame_line_bytes_to_the_device() {
+        let path = scratch_path("write-frame");
+        let mut screen = file_screen(&path);
+        let frame_line = PixelFrame::BLUE.frame_line();
+        screen.write_frame(&frame_line).unwrap();
+        let written = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(written, frame_line.encode(PixelFormat::Rgb565));
     }
 }
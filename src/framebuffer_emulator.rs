@@ -0,0 +1,315 @@
+//! A file-backed framebuffer, to test code that uses a `Screen` without the Sense HAT.
+//!
+//! An `EmulatedFramebuffer` is a regular file, as large as the frames of its `PixelFormat`,
+//! with the screen information that a real framebuffer device reports through its ioctls.
+//! A `Screen` opened on it writes frames to the file, so that tests, in CI, can assert on the
+//! exact bytes written by `Screen::write_frame`, by the orientation of the screen, or by its
+//! drop action. The gamma table, which the driver keeps in the kernel, is kept in memory,
+//! and shared by every `Screen` opened on the emulator.
+//!
+//! ```
+//! # extern crate sensehat_screen;
+//! # use sensehat_screen::{EmulatedFramebuffer, PixelFrame, Rotate};
+//! # fn main() {
+//!     let emulator = EmulatedFramebuffer::temporary().unwrap();
+//!     let mut screen = emulator.open_screen().unwrap();
+//!     screen.set_rotation(Rotate::Ccw180);
+//!     screen.write_frame(&PixelFrame::RED.frame_line()).unwrap();
+//!
+//!     let red = PixelFrame::RED.frame_line();
+//!     assert_eq!(emulator.bytes().unwrap(), red.encode(emulator.format()));
+//! # }
+//! ```
+use super::{GammaBackend, GammaPreset, Screen, GAMMA_DEFAULT};
+use error::{Result, ScreenError};
+use framebuffer::{Bitfield, VarScreeninfo};
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use {FrameLine, PixelFormat, PixelFrame};
+
+// Number of temporary emulators created by this process, to give each a unique file.
+static TEMPORARY_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// A regular file, with fake screen information, that a `Screen` can be opened on.
+#[derive(Debug)]
+pub struct EmulatedFramebuffer {
+    path: PathBuf,
+    format: PixelFormat,
+    gamma: Arc<Mutex<[u8; 32]>>,
+    temporary: bool,
+}
+
+impl EmulatedFramebuffer {
+    /// Create an emulator of the Sense HAT framebuffer, with a file of 128 zero bytes at
+    /// `path`, truncating any existing one.
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        EmulatedFramebuffer::with_format(path, PixelFormat::Rgb565)
+    }
+
+    /// Create an emulator of an 8x8 framebuffer with the given pixel format, with a file of
+    /// zero bytes at `path`, truncating any existing one.
+    pub fn with_format<P: AsRef<Path>>(path: P, format: PixelFormat) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Err(source) = fs::write(&path, vec![0u8; format.frame_bytes()]) {
+            return Err(ScreenError::Open { path, source });
+        }
+        Ok(EmulatedFramebuffer { path,
+                                 format,
+                                 gamma: Arc::new(Mutex::new(GAMMA_DEFAULT)),
+                                 temporary: false, })
+    }
+
+    /// Create an emulator of the Sense HAT framebuffer in a new file, in the temporary
+    /// directory, that is removed when the emulator is dropped.
+    pub fn temporary() -> Result<Self> {
+        let count = TEMPORARY_COUNT.fetch_add(1, Ordering::SeqCst);
+        let name = format!("sensehat-screen-emulator-{}-{}", process::id(), count);
+        let mut emulator = EmulatedFramebuffer::create(env::temp_dir().join(name))?;
+        emulator.temporary = true;
+        Ok(emulator)
+    }
+
+    /// Returns the path to the file of the emulator.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the pixel format of the emulated framebuffer.
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// Returns the variable screen information that the emulated framebuffer reports, as a
+    /// real device does through the `FBIOGET_VSCREENINFO` ioctl.
+    pub fn var_screeninfo(&self) -> VarScreeninfo {
+        let (red, green, blue) = match self.format {
+            PixelFormat::Rgb565 => ((11, 5), (5, 6), (0, 5)),
+            PixelFormat::Bgr565 => ((0, 5), (5, 6), (11, 5)),
+            PixelFormat::Rgb888 | PixelFormat::Xrgb8888 => ((16, 8), (8, 8), (0, 8)),
+        };
+        VarScreeninfo { xres: 8,
+                        yres: 8,
+                        xres_virtual: 8,
+                        yres_virtual: 8,
+                        bits_per_pixel: self.format.bits_per_pixel(),
+                        red: channel(red),
+                        green: channel(green),
+                        blue: channel(blue),
+                        ..Default::default() }
+    }
+
+    /// Open a `Screen` on the emulated framebuffer. Any number of screens may be open at the
+    /// same time, as with a real device.
+    pub fn open_screen(&self) -> Result<Screen> {
+        let device = OpenOptions::new().read(true)
+                                       .write(true)
+                                       .open(&self.path)
+                                       .map_err(|source| {
+                                                    ScreenError::Open { path: self.path.clone(),
+                                                                        source, }
+                                                })?;
+        Screen::from_device(device,
+                            &self.var_screeninfo(),
+                            Box::new(EmulatedGamma(self.gamma.clone())))
+    }
+
+    /// Returns the bytes of the emulated framebuffer, as last written by a `Screen`.
+    pub fn bytes(&self) -> Result<Vec<u8>> {
+        Ok(fs::read(&self.path)?)
+    }
+
+    /// Overwrite the bytes of the emulated framebuffer, as another process would.
+    pub fn set_bytes(&self, bytes: &[u8]) -> Result<()> {
+        Ok(fs::write(&self.path, bytes)?)
+    }
+
+    /// Returns the frame shown on the emulated framebuffer, as the device stores it, without
+    /// undoing the orientation of any `Screen`.
+    ///
+    /// Fails with `ScreenError::ShortRead` if the file is shorter than a frame.
+    pub fn frame(&self) -> Result<FrameLine> {
+        let bytes = self.bytes()?;
+        let expected = self.format.frame_bytes();
+        if bytes.len() < expected {
+            return Err(ScreenError::ShortRead { read: bytes.len(),
+                                                expected, });
+        }
+        Ok(FrameLine::decode(self.format, &bytes))
+    }
+
+    /// Returns the frame shown on the emulated framebuffer, as a `PixelFrame`.
+    pub fn pixel_frame(&self) -> Result<PixelFrame> {
+        self.frame().map(|frame| frame.pixel_frame())
+    }
+
+    /// Show a frame on the emulated framebuffer, as another process would.
+    pub fn set_frame(&self, frame: &FrameLine) -> Result<()> {
+        self.set_bytes(&frame.encode(self.format))
+    }
+
+    /// Returns the gamma table of the emulated driver.
+    pub fn gamma(&self) -> [u8; 32] {
+        *self.gamma.lock().unwrap()
+    }
+}
+
+impl Drop for EmulatedFramebuffer {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+// The gamma table of an emulator, which has no gamma ioctls, shared by its screens.
+#[derive(Debug)]
+struct EmulatedGamma(Arc<Mutex<[u8; 32]>>);
+
+impl GammaBackend for EmulatedGamma {
+    fn get(&self, _device: &File) -> Result<[u8; 32]> {
+        Ok(*self.0.lock().unwrap())
+    }
+
+    fn set(&self, _device: &File, gamma: &[u8; 32]) -> Result<()> {
+        *self.0.lock().unwrap() = *gamma;
+        Ok(())
+    }
+
+    fn reset(&self, _device: &File, preset: GammaPreset) -> Result<()> {
+        *self.0.lock().unwrap() = preset.table();
+        Ok(())
+    }
+}
+
+fn channel((offset, length): (u32, u32)) -> Bitfield {
+    Bitfield { offset,
+               length,
+               msb_right: 0, }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use screen::{DropAction, GammaPreset, GAMMA_LOW_LIGHT};
+    use {PixelColor, Rotate};
+
+    fn corner_frame() -> PixelFrame {
+        let mut frame = PixelFrame::BLACK;
+        frame[0] = PixelColor::RED;
+        frame
+    }
+
+    #[test]
+    fn emulator_starts_with_a_blank_sense_hat_frame() {
+        let emulator = EmulatedFramebuffer::temporary().unwrap();
+        assert_eq!(emulator.format(), PixelFormat::Rgb565);
+        assert_eq!(emulator.bytes().unwrap(), vec![0u8; 128]);
+        assert_eq!(emulator.pixel_frame().unwrap(), PixelFrame::BLACK);
+        assert_eq!(emulator.gamma(), GAMMA_DEFAULT);
+        let info = emulator.var_screeninfo();
+        assert_eq!((info.xres, info.yres, info.bits_per_pixel), (8, 8, 16));
+    }
+
+    #[test]
+    fn temporary_emulators_have_their_own_files_and_remove_them() {
+        let first = EmulatedFramebuffer::temporary().unwrap();
+        let second = EmulatedFramebuffer::temporary().unwrap();
+        assert_ne!(first.path(), second.path());
+        let path = first.path().to_path_buf();
+        drop(first);
+        assert!(!path.exists());
+        assert!(second.path().exists());
+    }
+
+    #[test]
+    fn emulator_at_a_path_is_kept() {
+        let path = env::temp_dir().join(format!("sensehat-screen-emulator-{}-kept",
+                                                process::id()));
+        drop(EmulatedFramebuffer::create(&path).unwrap());
+        assert_eq!(fs::read(&path).unwrap(), vec![0u8; 128]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn screen_writes_frames_to_the_emulator() {
+        let emulator = EmulatedFramebuffer::temporary().unwrap();
+        let mut screen = emulator.open_screen().unwrap();
+        screen.write_frame(&corner_frame().frame_line()).unwrap();
+        let mut expected = vec![0u8; 128];
+        expected[..2].copy_from_slice(&0xF800u16.to_ne_bytes());
+        assert_eq!(emulator.bytes().unwrap(), expected);
+    }
+
+    #[test]
+    fn screen_orientation_is_applied_to_the_emulator() {
+        let emulator = EmulatedFramebuffer::temporary().unwrap();
+        let mut screen = emulator.open_screen().unwrap();
+        screen.set_rotation(Rotate::Ccw180);
+        screen.write_frame(&corner_frame().frame_line()).unwrap();
+        assert_eq!(emulator.pixel_frame().unwrap()[63], PixelColor::RED);
+        assert_eq!(screen.read_pixel_frame().unwrap()[0], PixelColor::RED);
+    }
+
+    #[test]
+    fn screen_reads_frames_set_on_the_emulator() {
+        let emulator = EmulatedFramebuffer::temporary().unwrap();
+        emulator.set_frame(&PixelFrame::BLUE.frame_line()).unwrap();
+        let screen = emulator.open_screen().unwrap();
+        assert_eq!(screen.read_frame().unwrap(), PixelFrame::BLUE.frame_line());
+        emulator.set_bytes(&[0; 10]).unwrap();
+        assert!(emulator.frame().is_err());
+        assert!(screen.read_frame().is_err());
+    }
+
+    #[test]
+    fn screen_gamma_is_kept_by_the_emulator() {
+        let emulator = EmulatedFramebuffer::temporary().unwrap();
+        let mut screen = emulator.open_screen().unwrap();
+        assert!(!screen.low_light().unwrap());
+        screen.set_low_light(true).unwrap();
+        assert_eq!(emulator.gamma(), GAMMA_LOW_LIGHT);
+        // every screen shares the gamma table of the driver.
+        assert!(emulator.open_screen().unwrap().low_light().unwrap());
+
+        let mut table = [0x1F; 32];
+        table[0] = 0;
+        screen.set_gamma(table).unwrap();
+        assert_eq!(screen.gamma().unwrap(), table);
+        assert!(screen.set_gamma([0x20; 32]).is_err());
+        screen.reset_gamma(GammaPreset::Default).unwrap();
+        assert_eq!(emulator.gamma(), GAMMA_DEFAULT);
+    }
+
+    #[test]
+    fn screen_drop_action_is_applied_to_the_emulator() {
+        let emulator = EmulatedFramebuffer::temporary().unwrap();
+        emulator.set_frame(&PixelFrame::GREEN.frame_line()).unwrap();
+        let mut screen = emulator.open_screen().unwrap();
+        screen.restore_on_drop().unwrap();
+        screen.write_frame(&PixelFrame::RED.frame_line()).unwrap();
+        drop(screen);
+        assert_eq!(emulator.frame().unwrap(), PixelFrame::GREEN.frame_line());
+
+        let mut screen = emulator.open_screen().unwrap();
+        screen.set_drop_action(DropAction::Clear);
+        drop(screen);
+        assert_eq!(emulator.bytes().unwrap(), vec![0u8; 128]);
+    }
+
+    #[test]
+    fn emulator_negotiates_other_pixel_formats() {
+        let path = env::temp_dir().join(format!("sensehat-screen-emulator-{}-xrgb",
+                                                process::id()));
+        let emulator = EmulatedFramebuffer::with_format(&path, PixelFormat::Xrgb8888).unwrap();
+        let mut screen = emulator.open_screen().unwrap();
+        assert_eq!(screen.pixel_format(), PixelFormat::Xrgb8888);
+        screen.write_frame(&PixelFrame::BLUE.frame_line()).unwrap();
+        assert_eq!(emulator.bytes().unwrap().len(), 256);
+        assert_eq!(emulator.frame().unwrap(), PixelFrame::BLUE.frame_line());
+        fs::remove_file(&path).unwrap();
+    }
+}
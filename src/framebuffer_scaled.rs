@@ -0,0 +1,370 @@
+//! Preview frames on other framebuffers, such as an HDMI display, or a small SPI LCD.
+//!
+//! A `ScaledScreen` draws every `PixelFrame` as an 8x8 grid of blocks, as large as the device
+//! allows, and centered on it. Blocks may be separated by gaps, and drawn as round "LED" dots.
+//!
+//! ```no_run
+//! # extern crate sensehat_screen;
+//! # use sensehat_screen::{LedShape, PixelFrame, ScaledScreen};
+//! # fn main() {
+//!     let mut preview = ScaledScreen::open("/dev/fb0").unwrap();
+//!     preview.set_shape(LedShape::Round);
+//!     preview.set_gap(4);
+//!     preview.write_pixel_frame(&PixelFrame::RED).unwrap();
+//! # }
+//! ```
+use super::super::display::Display;
+use super::super::error::{Result, ScreenError};
+use super::{open_framebuffer, pixel_format};
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+use {FrameLine, PixelColor, PixelFormat, PixelFrame};
+
+/// The resolution, and memory layout, of a framebuffer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Geometry {
+    /// Visible width, in pixels.
+    pub width: u32,
+    /// Visible height, in pixels.
+    pub height: u32,
+    /// Number of bytes between the start of two consecutive lines.
+    pub line_length: u32,
+    /// Layout of each pixel.
+    pub format: PixelFormat,
+}
+
+impl Geometry {
+    /// Create the `Geometry` of a framebuffer without padding at the end of each line.
+    pub fn new(width: u32, height: u32, format: PixelFormat) -> Self {
+        Geometry { width,
+                   height,
+                   line_length: width * format.bytes_per_pixel() as u32,
+                   format, }
+    }
+
+    /// Returns the number of bytes used by a whole screen.
+    pub fn frame_bytes(&self) -> usize {
+        self.line_length as usize * self.height as usize
+    }
+}
+
+/// The shape of each LED drawn by a `ScaledScreen`.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum LedShape {
+    /// Each LED fills its square block. This is the default.
+    #[default]
+    Square,
+    /// Each LED is a round dot, inscribed in its square block.
+    Round,
+}
+
+/// A `Display` that scales frames up to fit any framebuffer.
+#[derive(Debug)]
+pub struct ScaledScreen {
+    device: File,
+    geometry: Geometry,
+    shape: LedShape,
+    gap: u32,
+    background: PixelColor,
+}
+
+impl ScaledScreen {
+    /// Open the framebuffer at the given file-system path, and read its resolution, and
+    /// pixel format.
+    ///
+    /// Fails if the device can't be opened, if it is not a framebuffer, if it is smaller than
+    /// 8x8 pixels, or if its pixel format is not supported.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let (device, var_screen_info, fix_screen_info) = open_framebuffer(path.as_ref())?;
+        let geometry = Geometry { width: var_screen_info.xres,
+                                  height: var_screen_info.yres,
+                                  line_length: fix_screen_info.line_length,
+                                  format: pixel_format(&var_screen_info)?, };
+        ScaledScreen::from_device(device, geometry)
+    }
+
+    /// Open a file that is laid out like a framebuffer with the given geometry, such as a
+    /// regular file, or a framebuffer whose screen information is already known.
+    ///
+    /// Fails if the file can't be opened, or if the geometry is smaller than 8x8 pixels.
+    pub fn open_with_geometry<P: AsRef<Path>>(path: P, geometry: Geometry) -> Result<Self> {
+        let path = path.as_ref();
+        let device = OpenOptions::new().read(true)
+                                       .write(true)
+                                       .open(path)
+                                       .map_err(|source| {
+                                                    ScreenError::Open { path: path.to_path_buf(),
+                                                                        source, }
+                                                })?;
+        ScaledScreen::from_device(device, geometry)
+    }
+
+    fn from_device(device: File, geometry: Geometry) -> Result<Self> {
+        let min_line_length = geometry.width * geometry.format.bytes_per_pixel() as u32;
+        if geometry.width < 8 || geometry.height < 8 || geometry.line_length < min_line_length {
+            return Err(ScreenError::UnsupportedGeometry { width: geometry.width,
+                                                          height: geometry.height,
+                                                          bits_per_pixel:
+                                                              geometry.format.bits_per_pixel(), });
+        }
+        Ok(ScaledScreen { device,
+                          geometry,
+                          shape: LedShape::default(),
+                          gap: 0,
+                          background: PixelColor::BLACK, })
+    }
+
+    /// Returns the geometry of the framebuffer.
+    pub fn geometry(&self) -> Geometry {
+        self.geometry
+    }
+
+    /// Returns the shape of each LED.
+    pub fn shape(&self) -> LedShape {
+        self.shape
+    }
+
+    /// Set the shape of each LED.
+    pub fn set_shape(&mut self, shape: LedShape) {
+        self.shape = shape;
+    }
+
+    /// Returns the gap between LEDs, in pixels.
+    pub fn gap(&self) -> u32 {
+        self.gap
+    }
+
+    /// Set the gap between LEDs, in pixels. Each LED is at least one pixel wide, however
+    /// large the gap.
+    pub fn set_gap(&mut self, gap: u32) {
+        self.gap = gap;
+    }
+
+    /// Returns the color drawn in the gaps, and around the grid.
+    pub fn background(&self) -> PixelColor {
+        self.background
+    }
+
+    /// Set the color drawn in the gaps, and around the grid.
+    pub fn set_background(&mut self, background: PixelColor) {
+        self.background = background;
+    }
+
+    /// Draw a `PixelFrame` on the framebuffer, keeping every bit of its colors on devices
+    /// with 8 bits per channel.
+    ///
+    /// Fails if the device does not accept the whole screen.
+    pub fn write_pixel_frame(&mut self, frame: &PixelFrame) -> Result<()> {
+        let bytes = self.render(frame);
+        let written = self.device.write_at(&bytes, 0)?;
+        if written != bytes.len() {
+            return Err(ScreenError::ShortWrite { written,
+                                                 expected: bytes.len(), });
+        }
+        Ok(())
+    }
+
+    // Render the frame as the bytes of the whole screen.
+    fn render(&self, frame: &PixelFrame) -> Vec<u8> {
+        let Geometry { width,
+                       height,
+                       line_length,
+                       format, } = self.geometry;
+        let size = format.bytes_per_pixel();
+        let block = width.min(height) / 8;
+        let led = block.saturating_sub(self.gap).max(1);
+        let left = (width - block * 8) / 2 + (block - led) / 2;
+        let top = (height - block * 8) / 2 + (block - led) / 2;
+        let pixels = frame.as_rows();
+
+        let mut background = vec![0u8; size];
+        format.encode_color(self.background, &mut background);
+        let mut bytes = vec![0u8; self.geometry.frame_bytes()];
+        for (y, line) in bytes.chunks_mut(line_length as usize).enumerate() {
+            let y = y as u32;
+            for (x, pixel) in line.chunks_mut(size).take(width as usize).enumerate() {
+                let x = x as u32;
+                let lit = if x < left || y < top {
+                    None
+                } else {
+                    let (col, row) = ((x - left) / block, (y - top) / block);
+                    let (dx, dy) = ((x - left) % block, (y - top) % block);
+                    if col < 8 && row < 8 && dx < led && dy < led && self.is_lit(dx, dy, led) {
+                        Some(pixels[row as usize][col as usize])
+                    } else {
+                        None
+                    }
+                };
+                match lit {
+                    Some(color) => format.encode_color(color, pixel),
+                    None => pixel.copy_from_slice(&background),
+                }
+            }
+        }
+        bytes
+    }
+
+    // Whether the pixel at `(dx, dy)`, inside an LED of `led` pixels, is part of its shape.
+    fn is_lit(&self, dx: u32, dy: u32, led: u32) -> bool {
+        match self.shape {
+            LedShape::Square => true,
+            LedShape::Round => {
+                // distances from the center, doubled to keep them whole.
+                let (dx, dy) = (i64::from(2 * dx + 1) - i64::from(led),
+                                i64::from(2 * dy + 1) - i64::from(led));
+                dx * dx + dy * dy <= i64::from(led) * i64::from(led)
+            }
+        }
+    }
+}
+
+impl Display for ScaledScreen {
+    fn write_frame(&mut self, frame: &FrameLine) -> Result<()> {
+        ScaledScreen::write_pixel_frame(self, &frame.pixel_frame())
+    }
+
+    fn write_pixel_frame(&mut self, frame: &PixelFrame) -> Result<()> {
+        ScaledScreen::write_pixel_frame(self, frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    const ON: PixelColor = PixelColor { red: 0x12,
+                                        green: 0x34,
+                                        blue: 0x56, };
+
+    // Path to a scratch file, unique to this test process.
+    fn scratch_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("sensehat-screen-scaled-{}-{}", process::id(), name))
+    }
+
+    // Draw a frame on a regular file, and return the colors of each pixel, as rows.
+    fn draw(name: &str, geometry: Geometry, setup: &dyn Fn(&mut ScaledScreen)) -> Vec<Vec<u32>> {
+        let path = scratch_path(name);
+        fs::write(&path, vec![0xFFu8; geometry.frame_bytes()]).unwrap();
+        let mut screen = ScaledScreen::open_with_geometry(&path, geometry).unwrap();
+        setup(&mut screen);
+        let mut frame = PixelFrame::default();
+        frame[0] = ON;
+        frame[63] = ON;
+        screen.write_pixel_frame(&frame).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        bytes.chunks(geometry.line_length as usize)
+             .map(|line| {
+                      line.chunks(4)
+                          .take(geometry.width as usize)
+                          .map(|pixel| {
+                                   let mut value = [0u8; 4];
+                                   value.copy_from_slice(pixel);
+                                   u32::from_ne_bytes(value)
+                               })
+                          .collect()
+                  })
+             .collect()
+    }
+
+    fn xrgb(width: u32, height: u32) -> Geometry {
+        Geometry::new(width, height, PixelFormat::Xrgb8888)
+    }
+
+    #[test]
+    fn geometry_without_padding() {
+        let geometry = Geometry::new(320, 240, PixelFormat::Rgb565);
+        assert_eq!(geometry.line_length, 640);
+        assert_eq!(geometry.frame_bytes(), 153_600);
+    }
+
+    #[test]
+    fn scaled_screen_rejects_framebuffers_smaller_than_the_led_matrix() {
+        let path = scratch_path("too-small");
+        fs::write(&path, vec![0u8; 64]).unwrap();
+        let geometry = Geometry::new(4, 4, PixelFormat::Rgb565);
+        let result = ScaledScreen::open_with_geometry(&path, geometry);
+        fs::remove_file(&path).unwrap();
+        match result {
+            Err(ScreenError::UnsupportedGeometry { width: 4,
+                                                   height: 4,
+                                                   bits_per_pixel: 16, }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn scaled_screen_fails_to_open_a_device_that_is_not_a_framebuffer() {
+        let path = scratch_path("not-a-framebuffer");
+        fs::write(&path, vec![0u8; 64]).unwrap();
+        let result = ScaledScreen::open(&path);
+        fs::remove_file(&path).unwrap();
+        match result {
+            Err(ScreenError::WrongDevice { .. }) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn scaled_screen_draws_square_blocks() {
+        let rows = draw("square", xrgb(16, 16), &|_| {});
+        assert_eq!(&rows[0][..4], &[0x12_3456, 0x12_3456, 0, 0]);
+        assert_eq!(&rows[1][..4], &[0x12_3456, 0x12_3456, 0, 0]);
+        assert_eq!(&rows[2][..4], &[0, 0, 0, 0]);
+        assert_eq!(&rows[15][12..], &[0, 0, 0x12_3456, 0x12_3456]);
+    }
+
+    #[test]
+    fn scaled_screen_centers_the_grid() {
+        let rows = draw("centered", xrgb(20, 16), &|screen| {
+                            screen.set_background(PixelColor::BLUE);
+                        });
+        assert_eq!(&rows[0][..4], &[0xFF, 0xFF, 0x12_3456, 0x12_3456]);
+        assert_eq!(&rows[15][16..], &[0x12_3456, 0x12_3456, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    fn scaled_screen_separates_leds_with_gaps() {
+        let rows = draw("gaps", xrgb(32, 32), &|screen| screen.set_gap(2));
+        assert_eq!(&rows[0][..4], &[0, 0, 0, 0]);
+        assert_eq!(&rows[1][..4], &[0, 0x12_3456, 0x12_3456, 0]);
+        assert_eq!(&rows[2][..4], &[0, 0x12_3456, 0x12_3456, 0]);
+        assert_eq!(&rows[3][..4], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn scaled_screen_draws_round_leds() {
+        let rows = draw("round", xrgb(64, 64), &|screen| screen.set_shape(LedShape::Round));
+        let on = |row: &[u32]| row[..8].iter().map(|&c| c != 0).collect::<Vec<bool>>();
+        assert_eq!(on(&rows[0]), vec![false, false, true, true, true, true, false, false]);
+        assert_eq!(on(&rows[3]), vec![true; 8]);
+        assert_eq!(on(&rows[7]), vec![false, false, true, true, true, true, false, false]);
+    }
+
+    #[test]
+    fn scaled_screen_skips_padding_at_the_end_of_lines() {
+        let geometry = Geometry { line_length: 40,
+                                  ..xrgb(8, 8) };
+        let rows = draw("padding", geometry, &|_| {});
+        assert_eq!(rows[0][0], 0x12_3456);
+        assert_eq!(rows[7][7], 0x12_3456);
+        assert_eq!(rows[7][6], 0);
+    }
+
+    #[test]
+    fn scaled_screen_encodes_the_format_of_the_device() {
+        let path = scratch_path("rgb565");
+        let geometry = Geometry::new(8, 8, PixelFormat::Rgb565);
+        fs::write(&path, vec![0u8; geometry.frame_bytes()]).unwrap();
+        let mut screen = ScaledScreen::open_with_geometry(&path, geometry).unwrap();
+        screen.write_frame(&PixelFrame::RED.frame_line()).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(bytes, PixelFrame::RED.frame_line().encode(PixelFormat::Rgb565));
+    }
+}
@@ -0,0 +1,199 @@
+//! Signal handling, to leave the LED Matrix in a known state when the process is interrupted.
+//!
+//! The handler only calls async-signal-safe functions: it writes a prepared frame to the
+//! device with `pwrite`, and then runs the action that the signal had before the handler was
+//! installed. A handler installed before, by the application, is called, and stays behind
+//! this one, so that every later signal also writes the frame. Without one, the signal
+//! terminates the process, as it does by default.
+//!
+//! There is a single handler for the process, so it is owned by one file-descriptor at a time.
+use libc;
+use std::io;
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+
+// The signals that the handler is installed for.
+const SIGNALS: [libc::c_int; 2] = [libc::SIGINT, libc::SIGTERM];
+
+// File-descriptor that owns the signal handler, or `-1` if it is not owned.
+static OWNER: AtomicI32 = AtomicI32::new(-1);
+
+// File-descriptor that the signal handler writes to, or `-1` if it should not write.
+static SIGNAL_FD: AtomicI32 = AtomicI32::new(-1);
+
+// Number of handlers that may be writing to `SIGNAL_FD`, which is not closed until they end.
+static WRITING: AtomicUsize = AtomicUsize::new(0);
+
+// Largest frame that the signal handler writes, 64 pixels of 32 bits.
+const MAX_FRAME_BYTES: usize = 256;
+
+// Two frames that the signal handler writes, as 8-byte words, and their lengths. A new frame
+// is prepared in the one that the handler does not read, and then published, so that the
+// handler never writes a frame that is only partly updated.
+static SIGNAL_FRAMES: [[AtomicU64; MAX_FRAME_BYTES / 8]; 2] =
+    [const { [const { AtomicU64::new(0) }; MAX_FRAME_BYTES / 8] }; 2];
+static SIGNAL_LENS: [AtomicUsize; 2] = [const { AtomicUsize::new(0) }; 2];
+
+// Generation of the published frame, kept in `SIGNAL_FRAMES[generation % 2]`.
+static PUBLISHED: AtomicUsize = AtomicUsize::new(0);
+
+// Generation of the last frame that `set_frame` started to prepare.
+static PREPARING: AtomicUsize = AtomicUsize::new(0);
+
+// Serializes `set_frame`, which the handler never waits for.
+static PREPARE: Mutex<()> = Mutex::new(());
+
+// Set while the handler is installed for `SIGINT`, and `SIGTERM`.
+static INSTALLED: AtomicBool = AtomicBool::new(false);
+
+// The actions of `SIGNALS` before the handler was first installed, run after it.
+static PREVIOUS: OnceLock<[libc::sigaction; 2]> = OnceLock::new();
+
+/// Install the handler for `SIGINT`, and `SIGTERM`, if it is not installed yet, saving the
+/// actions that the signals had.
+pub fn install() -> io::Result<()> {
+    if INSTALLED.swap(true, Ordering::SeqCst) {
+        return Ok(());
+    }
+    if PREVIOUS.get().is_none() {
+        let mut previous: [libc::sigaction; 2] = unsafe { mem::zeroed() };
+        for (&signal, action) in SIGNALS.iter().zip(previous.iter_mut()) {
+            if unsafe { libc::sigaction(signal, ptr::null(), action) } == -1 {
+                INSTALLED.store(false, Ordering::SeqCst);
+                return Err(io::Error::last_os_error());
+            }
+        }
+        let _ = PREVIOUS.set(previous);
+    }
+    for &signal in &SIGNALS {
+        let result = unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = handle_signal as *const () as libc::sighandler_t;
+            action.sa_flags = libc::SA_SIGINFO;
+            libc::sigemptyset(&mut action.sa_mask);
+            libc::sigaction(signal, &action, ptr::null_mut())
+        };
+        if result == -1 {
+            INSTALLED.store(false, Ordering::SeqCst);
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Make `fd` the owner of the handler, if it has no other owner. Returns `true` if `fd` owns
+/// the handler.
+pub fn claim(fd: libc::c_int) -> bool {
+    match OWNER.compare_exchange(-1, fd, Ordering::SeqCst, Ordering::SeqCst) {
+        Ok(_) => true,
+        Err(owner) => owner == fd,
+    }
+}
+
+/// Stop writing to `fd` when a signal arrives, and give up the handler, if `fd` owns it.
+/// Returns once no handler is writing to `fd`, so that it can be closed.
+pub fn release(fd: libc::c_int) {
+    let _ = SIGNAL_FD.compare_exchange(fd, -1, Ordering::SeqCst, Ordering::SeqCst);
+    let _ = OWNER.compare_exchange(fd, -1, Ordering::SeqCst, Ordering::SeqCst);
+    // a handler running on another thread may have read `fd` before it was cleared.
+    while WRITING.load(Ordering::SeqCst) != 0 {
+        thread::yield_now();
+    }
+}
+
+/// Set the frame, already in device coordinates, that the handler writes to `fd`. With
+/// `None`, the handler leaves the device untouched. Does nothing unless `fd` owns the handler.
+///
+/// # Panics
+/// If the frame is longer than 256 bytes.
+pub fn set_frame(fd: libc::c_int, frame: Option<&[u8]>) {
+    if OWNER.load(Ordering::SeqCst) != fd {
+        return;
+    }
+    match frame {
+        Some(bytes) => {
+            assert!(bytes.len() <= MAX_FRAME_BYTES);
+            let _prepare = PREPARE.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let generation = PUBLISHED.load(Ordering::SeqCst).wrapping_add(1);
+            PREPARING.store(generation, Ordering::SeqCst);
+            let slot = generation % 2;
+            for (word, chunk) in SIGNAL_FRAMES[slot].iter().zip(bytes.chunks(8)) {
+                let mut value = [0u8; 8];
+                value[..chunk.len()].copy_from_slice(chunk);
+                word.store(u64::from_ne_bytes(value), Ordering::SeqCst);
+            }
+            SIGNAL_LENS[slot].store(bytes.len(), Ordering::SeqCst);
+            PUBLISHED.store(generation, Ordering::SeqCst);
+            SIGNAL_FD.store(fd, Ordering::SeqCst);
+        }
+        None => SIGNAL_FD.store(-1, Ordering::SeqCst),
+    }
+}
+
+/// Write the prepared frame to the device, if there is any. Returns `true` if it was written.
+pub fn write_frame() -> bool {
+    WRITING.fetch_add(1, Ordering::SeqCst);
+    let fd = SIGNAL_FD.load(Ordering::SeqCst);
+    let written = fd >= 0 && {
+        let (bytes, len) = published_frame();
+        let written = unsafe { libc::pwrite(fd, bytes.as_ptr() as *const libc::c_void, len, 0) };
+        written == len as isize
+    };
+    WRITING.fetch_sub(1, Ordering::SeqCst);
+    written
+}
+
+// Copy the published frame, and its length. The copy is taken again if `set_frame` started to
+// prepare a frame in the same slot, on another thread, while it was read.
+fn published_frame() -> ([u8; MAX_FRAME_BYTES], usize) {
+    let mut bytes = [0u8; MAX_FRAME_BYTES];
+    loop {
+        let generation = PUBLISHED.load(Ordering::SeqCst);
+        let slot = generation % 2;
+        for (chunk, word) in bytes.chunks_mut(8).zip(SIGNAL_FRAMES[slot].iter()) {
+            chunk.copy_from_slice(&word.load(Ordering::SeqCst).to_ne_bytes());
+        }
+        let len = SIGNAL_LENS[slot].load(Ordering::SeqCst);
+        if PREPARING.load(Ordering::SeqCst).wrapping_sub(generation) < 2 {
+            return (bytes, len);
+        }
+    }
+}
+
+extern "C" fn handle_signal(signal: libc::c_int,
+                            info: *mut libc::siginfo_t,
+                            context: *mut libc::c_void) {
+    write_frame();
+    let previous = PREVIOUS.get()
+                           .and_then(|previous| {
+                                         SIGNALS.iter()
+                                                .position(|&s| s == signal)
+                                                .map(|index| &previous[index])
+                                     });
+    let action = previous.map_or(libc::SIG_DFL, |previous| previous.sa_sigaction);
+    unsafe {
+        match action {
+            libc::SIG_IGN => {}
+            // the signal is blocked until the handler returns, so it is raised again with the
+            // default action, which terminates the process.
+            libc::SIG_DFL => {
+                libc::signal(signal, libc::SIG_DFL);
+                libc::raise(signal);
+            }
+            // the handler of the application is called, instead of reinstalled, so that this
+            // handler still runs for later signals.
+            _ if previous.is_some_and(|previous| previous.sa_flags & libc::SA_SIGINFO != 0) => {
+                let handler: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) =
+                    mem::transmute(action);
+                handler(signal, info, context);
+            }
+            _ => {
+                let handler: extern "C" fn(libc::c_int) = mem::transmute(action);
+                handler(signal);
+            }
+        }
+    }
+}
@@ -0,0 +1,443 @@
+//! Import images, and sprite sheets, as frames.
+//!
+//! PPM (`P3`, and `P6`), BMP (uncompressed), and PNG images are decoded into an `Image`, in
+//! plain Rust, without any dependencies. Images larger, or smaller, than the LED Matrix are
+//! resampled to 8x8, averaging the area of the image that each LED covers.
+//!
+//! The LED Matrix shows colors as `Rgb565`, with 5 bits of red, 6 of green, and 5 of blue,
+//! so smooth gradients turn into bands. A `Dither` spreads the error of the 5-6-5 colors to
+//! neighbouring LEDs, which keeps the average color of every area of the image.
+//!
+//! ```no_run
+//! # extern crate sensehat_screen;
+//! # use sensehat_screen::{Dither, Image, PixelFrame};
+//! # fn main() {
+//!     let logo = PixelFrame::from_image_file("logo.png").unwrap();
+//!
+//!     // a sprite sheet, with a row of 8x8 sprites.
+//!     let sheet = Image::open("walk.bmp").unwrap();
+//!     let sprites = sheet.sprites(8, 8, Dither::FloydSteinberg);
+//! # }
+//! ```
+#[path = "import_bmp.rs"]
+mod bmp;
+#[path = "import_inflate.rs"]
+mod inflate;
+#[path = "import_png.rs"]
+mod png;
+#[path = "import_ppm.rs"]
+mod ppm;
+
+use super::error::{Result, ScreenError};
+use super::color::{expand, nearest_level};
+use super::{PixelColor, PixelFrame};
+use std::fs;
+use std::path::Path;
+
+// The 8x8 Bayer matrix, for ordered dithering.
+const BAYER: [[u8; 8]; 8] = [[0, 32, 8, 40, 2, 34, 10, 42],
+                             [48, 16, 56, 24, 50, 18, 58, 26],
+                             [12, 44, 4, 36, 14, 46, 6, 38],
+                             [60, 28, 52, 20, 62, 30, 54, 22],
+                             [3, 35, 11, 43, 1, 33, 9, 41],
+                             [51, 19, 59, 27, 49, 17, 57, 25],
+                             [15, 47, 7, 39, 13, 45, 5, 37],
+                             [63, 31, 55, 23, 61, 29, 53, 21]];
+// Bits of red, green, and blue shown by the LED Matrix.
+const CHANNEL_BITS: [u32; 3] = [5, 6, 5];
+
+/// How colors are reduced to the 5-6-5 precision of `Rgb565`, when an image is resampled.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum Dither {
+    /// Colors are kept as they are, and truncated when they are displayed. This is the
+    /// default.
+    #[default]
+    None,
+    /// Floyd–Steinberg error diffusion, which spreads the error of every LED to the LEDs to
+    /// its right, and below it.
+    FloydSteinberg,
+    /// Ordered dithering, with an 8x8 Bayer matrix. The pattern does not change between
+    /// frames, which suits animations.
+    Ordered,
+}
+
+/// An image of any size, decoded from a PPM, BMP, or PNG file.
+///
+/// Transparent pixels are blended over black, as unlit LEDs are.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<PixelColor>,
+}
+
+impl Image {
+    /// Create an `Image` from its pixels, row by row.
+    ///
+    /// # Panics
+    /// If the number of pixels is not `width * height`.
+    pub fn new(width: u32, height: u32, pixels: Vec<PixelColor>) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+        Image { width,
+                height,
+                pixels, }
+    }
+
+    /// Open, and decode, an image file. The format is detected from the contents of the file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|source| {
+                                               ScreenError::Open { path: path.to_path_buf(),
+                                                                   source, }
+                                           })?;
+        Image::decode(&bytes)
+    }
+
+    /// Decode a PPM, BMP, or PNG image, detected from its first bytes.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        if bytes.starts_with(b"\x89PNG") {
+            png::decode_png(bytes)
+        } else if bytes.starts_with(b"BM") {
+            bmp::decode_bmp(bytes)
+        } else if bytes.starts_with(b"P3") || bytes.starts_with(b"P6") {
+            ppm::decode_ppm(bytes)
+        } else {
+            Err(invalid("unknown image format"))
+        }
+    }
+
+    /// Returns the width of the image, in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Returns the height of the image, in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the color of the pixel at column `x`, and row `y`.
+    ///
+    /// # Panics
+    /// If the pixel is outside of the image.
+    pub fn pixel(&self, x: u32, y: u32) -> PixelColor {
+        assert!(x < self.width && y < self.height);
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Returns the part of the image of the given size, with its top-left corner at column
+    /// `x`, and row `y`.
+    ///
+    /// # Panics
+    /// If the part is not entirely inside the image.
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Image {
+        assert!(x + width <= self.width && y + height <= self.height);
+        let pixels = (y..y + height).flat_map(|row| (x..x + width).map(move |col| (col, row)))
+                                    .map(|(col, row)| self.pixel(col, row))
+                                    .collect();
+        Image::new(width, height, pixels)
+    }
+
+    /// Resample the image to an 8x8 `PixelFrame`. Every LED shows the average color of the
+    /// area of the image it covers, reduced to 5-6-5 colors with the given `Dither`.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::{Dither, Image, PixelColor, PixelFrame};
+    /// # fn main() {
+    ///     let image = Image::new(16, 16, vec![PixelColor::BLUE; 256]);
+    ///     assert_eq!(image.resample(Dither::None), PixelFrame::BLUE);
+    /// # }
+    /// ```
+    pub fn resample(&self, dither: Dither) -> PixelFrame {
+        let (columns, rows) = (area_weights(self.width), area_weights(self.height));
+        let mut values = [[0f32; 3]; 64];
+        for (y, row_weights) in rows.iter().enumerate() {
+            for (x, column_weights) in columns.iter().enumerate() {
+                let (mut sum, mut total) = ([0f32; 3], 0f32);
+                for &(row, row_weight) in row_weights {
+                    for &(column, column_weight) in column_weights {
+                        let weight = row_weight * column_weight;
+                        let color = self.pixels[row * self.width as usize + column];
+                        sum[0] += weight * f32::from(color.red);
+                        sum[1] += weight * f32::from(color.green);
+                        sum[2] += weight * f32::from(color.blue);
+                        total += weight;
+                    }
+                }
+                for (value, sum) in values[y * 8 + x].iter_mut().zip(sum.iter()) {
+                    *value = sum / total;
+                }
+            }
+        }
+        dither_values(values, dither)
+    }
+
+    /// Split the image into tiles of `tile_width` by `tile_height` pixels, row by row, and
+    /// resample each tile to a `PixelFrame`. Tiles that do not fit entirely in the image are
+    /// skipped.
+    ///
+    /// # Panics
+    /// If either tile dimension is zero.
+    pub fn sprites(&self, tile_width: u32, tile_height: u32, dither: Dither) -> Vec<PixelFrame> {
+        assert!(tile_width > 0 && tile_height > 0);
+        let (columns, rows) = (self.width / tile_width, self.height / tile_height);
+        (0..rows).flat_map(|row| (0..columns).map(move |column| (column, row)))
+                 .map(|(column, row)| {
+                          self.crop(column * tile_width, row * tile_height, tile_width, tile_height)
+                              .resample(dither)
+                      })
+                 .collect()
+    }
+}
+
+impl PixelFrame {
+    /// Open a PPM, BMP, or PNG image, and resample it to an 8x8 `PixelFrame`, without
+    /// dithering.
+    pub fn from_image_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        PixelFrame::from_image_file_dithered(path, Dither::None)
+    }
+
+    /// Open a PPM, BMP, or PNG image, and resample it to an 8x8 `PixelFrame`, with the given
+    /// `Dither`.
+    pub fn from_image_file_dithered<P: AsRef<Path>>(path: P, dither: Dither) -> Result<Self> {
+        Ok(Image::open(path)?.resample(dither))
+    }
+
+    /// Open a sprite sheet, and resample each of its tiles to a `PixelFrame`. See
+    /// `Image::sprites`.
+    pub fn sprites_from_image_file<P: AsRef<Path>>(path: P,
+                                                   tile_width: u32,
+                                                   tile_height: u32,
+                                                   dither: Dither)
+                                                   -> Result<Vec<Self>> {
+        Ok(Image::open(path)?.sprites(tile_width, tile_height, dither))
+    }
+}
+
+// The error of an image that could not be decoded.
+fn invalid(reason: &str) -> ScreenError {
+    ScreenError::InvalidImage { reason: reason.to_string() }
+}
+
+// `value` divided by `divisor`, rounded up, without overflowing.
+fn div_ceil(value: usize, divisor: usize) -> usize {
+    value / divisor + usize::from(value % divisor != 0)
+}
+
+// For each of the 8 LEDs along an axis of `len` pixels, the pixels it covers, and how much of
+// each pixel it covers.
+fn area_weights(len: u32) -> Vec<Vec<(usize, f32)>> {
+    let len = len as f32;
+    let mut weights = Vec::with_capacity(8);
+    for led in 0..8 {
+        let (start, end) = (led as f32 * len / 8.0, (led + 1) as f32 * len / 8.0);
+        let mut covered = Vec::new();
+        for pixel in start.floor() as usize..end.ceil() as usize {
+            let weight = end.min(pixel as f32 + 1.0) - start.max(pixel as f32);
+            if weight > 0.0 {
+                covered.push((pixel, weight));
+            }
+        }
+        weights.push(covered);
+    }
+    weights
+}
+
+// Reduce the averaged colors of the LEDs, row by row, to a `PixelFrame`.
+fn dither_values(mut values: [[f32; 3]; 64], dither: Dither) -> PixelFrame {
+    let mut pixels = [PixelColor::BLACK; 64];
+    for index in 0..64 {
+        let (x, y) = (index % 8, index / 8);
+        let mut channels = [0u8; 3];
+        for channel in 0..3 {
+            let bits = CHANNEL_BITS[channel];
+            let value = values[index][channel];
+            channels[channel] = match dither {
+                Dither::None => value.round().clamp(0.0, 255.0) as u8,
+                Dither::FloydSteinberg => {
+                    let quantized = quantize(value, bits);
+                    let error = value.clamp(0.0, 255.0) - f32::from(quantized);
+                    let mut spread = |dx: isize, dy: usize, weight: f32| {
+                        let (nx, ny) = (x as isize + dx, y + dy);
+                        if (0..8).contains(&nx) && ny < 8 {
+                            values[ny * 8 + nx as usize][channel] += error * weight / 16.0;
+                        }
+                    };
+                    spread(1, 0, 7.0);
+                    spread(-1, 1, 3.0);
+                    spread(0, 1, 5.0);
+                    spread(1, 1, 1.0);
+                    quantized
+                }
+                Dither::Ordered => {
+                    let step = f32::from(1u16 << (8 - bits));
+                    let threshold = (f32::from(BAYER[y][x]) + 0.5) / 64.0 - 0.5;
+                    quantize(value + threshold * step, bits)
+                }
+            };
+        }
+        pixels[index] = PixelColor::new(channels[0], channels[1], channels[2]);
+    }
+    PixelFrame::new(&pixels)
+}
+
+// The displayed intensity, of a channel with the given bits, closest to `value`, as
+// `Rgb565::to_rgb` expands it.
+fn quantize(value: f32, bits: u32) -> u8 {
+    expand(nearest_level(value, bits), bits)
+}
+
+// Scale a sample, at most `max`, to a byte.
+fn scale_sample(sample: u32, max: u32) -> u8 {
+    let (sample, max) = (u64::from(sample), u64::from(max));
+    ((sample * 255 + max / 2) / max) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use color::Rgb565;
+
+    fn gradient(width: u32, height: u32) -> Image {
+        let pixels = (0..height).flat_map(|_| 0..width)
+                                .map(|x| {
+                                         let value = (x * 255 / (width - 1)) as u8;
+                                         PixelColor::new(value, value, value)
+                                     })
+                                .collect();
+        Image::new(width, height, pixels)
+    }
+
+    fn displayed(color: PixelColor) -> PixelColor {
+        Rgb565::from(color).into()
+    }
+
+    #[test]
+    fn images_are_detected_from_their_first_bytes() {
+        assert!(Image::decode(b"P6\n1 1\n255\n\x01\x02\x03").is_ok());
+        match Image::decode(b"GIF89a") {
+            Err(ScreenError::InvalidImage { ref reason }) => {
+                assert_eq!(reason, "unknown image format")
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_image_file_is_an_open_error() {
+        match Image::open("/this/image/does/not/exist.png") {
+            Err(ScreenError::Open { .. }) => {}
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn images_are_cropped() {
+        let image = gradient(16, 2);
+        let cropped = image.crop(4, 1, 3, 1);
+        assert_eq!(cropped.width(), 3);
+        assert_eq!(cropped.height(), 1);
+        assert_eq!(cropped.pixel(0, 0), image.pixel(4, 1));
+        assert_eq!(cropped.pixel(2, 0), image.pixel(6, 1));
+    }
+
+    #[test]
+    fn larger_images_are_averaged_by_area() {
+        // black, and white, pixels, alternating in a checkerboard.
+        let pixels = (0..16).flat_map(|y| (0..16).map(move |x| (x, y)))
+                            .map(|(x, y)| {
+                                     if (x + y) % 2 == 0 {
+                                         PixelColor::WHITE
+                                     } else {
+                                         PixelColor::BLACK
+                                     }
+                                 })
+                            .collect();
+        let frame = Image::new(16, 16, pixels).resample(Dither::None);
+        assert_eq!(frame, PixelFrame::new(&[PixelColor::new(128, 128, 128); 64]));
+
+        // 12 pixels over 8 LEDs, each LED covers one pixel and a half.
+        let mut pixels = vec![PixelColor::BLACK; 12];
+        pixels[1] = PixelColor::new(90, 90, 90);
+        let frame = Image::new(12, 1, pixels).resample(Dither::None);
+        assert_eq!(frame[0], PixelColor::new(30, 30, 30));
+        assert_eq!(frame[1], PixelColor::new(30, 30, 30));
+        assert_eq!(frame[2], PixelColor::BLACK);
+    }
+
+    #[test]
+    fn smaller_images_are_scaled_up() {
+        let image = Image::new(2, 1, vec![PixelColor::RED, PixelColor::BLUE]);
+        let rows = image.resample(Dither::None).as_rows();
+        for row in rows.iter() {
+            assert_eq!(&row[..4], &[PixelColor::RED; 4]);
+            assert_eq!(&row[4..], &[PixelColor::BLUE; 4]);
+        }
+    }
+
+    #[test]
+    fn colors_are_quantized_to_displayed_levels() {
+        assert_eq!(quantize(0.0, 5), 0);
+        assert_eq!(quantize(11.0, 5), 8);
+        assert_eq!(quantize(13.0, 5), 16);
+        assert_eq!(quantize(255.0, 5), 255);
+        assert_eq!(quantize(300.0, 6), 255);
+        assert_eq!(quantize(-4.0, 6), 0);
+        assert_eq!(quantize(7.0, 6), 8);
+        assert_eq!(quantize(130.0, 6), 130);
+    }
+
+    #[test]
+    fn dithered_colors_are_shown_exactly() {
+        let image = gradient(64, 8);
+        for &dither in &[Dither::FloydSteinberg, Dither::Ordered] {
+            let frame = image.resample(dither);
+            for index in 0..64 {
+                assert_eq!(displayed(frame[index]), frame[index]);
+            }
+        }
+    }
+
+    #[test]
+    fn dithering_keeps_the_average_color() {
+        // a flat color between two red levels, 8 and 16.
+        let color = PixelColor::new(12, 0, 0);
+        let image = Image::new(8, 8, vec![color; 64]);
+        let average = |frame: PixelFrame| {
+            (0..64).map(|index| u32::from(displayed(frame[index]).red)).sum::<u32>() as f32
+            / 64.0
+        };
+        assert_eq!(average(image.resample(Dither::None)), 8.0);
+        assert!((average(image.resample(Dither::FloydSteinberg)) - 12.0).abs() < 0.5);
+        assert!((average(image.resample(Dither::Ordered)) - 12.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn sprite_sheets_are_split_row_by_row() {
+        let colors = [PixelColor::RED, PixelColor::GREEN, PixelColor::BLUE, PixelColor::WHITE];
+        // a 2x2 sheet of 8x8 sprites, with a partial column on the right.
+        let pixels = (0..16).flat_map(|y| (0..20).map(move |x| (x, y)))
+                            .map(|(x, y)| colors[((y / 8) * 2 + (x / 8).min(1)) as usize])
+                            .collect();
+        let sprites = Image::new(20, 16, pixels).sprites(8, 8, Dither::None);
+        assert_eq!(sprites,
+                   vec![PixelFrame::RED,
+                        PixelFrame::GREEN,
+                        PixelFrame::BLUE,
+                        PixelFrame::WHITE]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn sprites_are_not_split_into_empty_tiles() {
+        gradient(8, 8).sprites(0, 8, Dither::None);
+    }
+
+    #[test]
+    fn samples_are_scaled_to_bytes() {
+        assert_eq!(scale_sample(1, 1), 255);
+        assert_eq!(scale_sample(2, 3), 170);
+        assert_eq!(scale_sample(0x8000, 0xFFFF), 128);
+    }
+}
@@ -0,0 +1,212 @@
+//! BMP decoding, of uncompressed images with a palette, or 16, 24, or 32 bits per pixel.
+use super::{div_ceil, invalid, scale_sample, Image};
+use error::Result;
+use PixelColor;
+
+// Compression of images with no compression, or with channel masks.
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
+
+// Decode a BMP image, with a `BITMAPINFOHEADER`, or any later header.
+pub fn decode_bmp(bytes: &[u8]) -> Result<Image> {
+    let u16_at = |offset: usize| bytes.get(offset..offset + 2)
+                                      .map(|b| u16::from_le_bytes([b[0], b[1]]));
+    let u32_at = |offset: usize| bytes.get(offset..offset + 4)
+                                      .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    let header = |offset| u32_at(offset).ok_or_else(|| invalid("BMP header is too short"));
+
+    let data_offset = header(10)? as usize;
+    let header_size = header(14)? as usize;
+    if header_size < 40 {
+        return Err(invalid("BMP core headers are not supported"));
+    }
+    let width = header(18)? as i32;
+    let height = header(22)? as i32;
+    let bits = u16_at(28).unwrap_or(0);
+    let compression = header(30)?;
+    let colors_used = header(46)?;
+    if width <= 0 || height == 0 {
+        return Err(invalid("invalid BMP dimensions"));
+    }
+    let (width, top_down) = (width as u32, height < 0);
+    let height = height.unsigned_abs();
+
+    let masks = match (compression, bits) {
+        (BI_RGB, 16) => Some([0x7C00, 0x03E0, 0x001F]),
+        (BI_RGB, 32) => Some([0x00FF_0000, 0x0000_FF00, 0x0000_00FF]),
+        (BI_RGB, 1) | (BI_RGB, 4) | (BI_RGB, 8) | (BI_RGB, 24) => None,
+        (BI_BITFIELDS, 16) | (BI_BITFIELDS, 32) => {
+            // the masks follow a `BITMAPINFOHEADER`, and are part of any later header.
+            Some([header(54)?, header(58)?, header(62)?])
+        }
+        _ => return Err(invalid("compressed BMP images are not supported")),
+    };
+
+    let palette = if bits <= 8 {
+        let count = if colors_used == 0 { 1 << bits } else { colors_used as usize };
+        let start = header_size.checked_add(14)
+                               .ok_or_else(|| invalid("BMP header is too large"))?;
+        let table = count.checked_mul(4)
+                         .and_then(|len| start.checked_add(len))
+                         .and_then(|end| bytes.get(start..end))
+                         .ok_or_else(|| invalid("BMP palette is too short"))?;
+        table.chunks(4)
+             .map(|bgr| PixelColor::new(bgr[2], bgr[1], bgr[0]))
+             .collect::<Vec<PixelColor>>()
+    } else {
+        Vec::new()
+    };
+
+    // every row is padded to a multiple of 4 bytes.
+    let stride = (width as usize).checked_mul(bits as usize)
+                                 .map(|row_bits| div_ceil(row_bits, 32) * 4)
+                                 .ok_or_else(|| invalid("BMP image is too large"))?;
+    let data = stride.checked_mul(height as usize)
+                     .and_then(|len| data_offset.checked_add(len))
+                     .and_then(|end| bytes.get(data_offset..end))
+                     .ok_or_else(|| invalid("BMP image ended unexpectedly"))?;
+
+    let mut pixels = Vec::with_capacity(width as usize * height as usize);
+    for y in 0..height as usize {
+        let row = if top_down { y } else { height as usize - 1 - y };
+        let row = &data[row * stride..(row + 1) * stride];
+        for x in 0..width as usize {
+            let color = match (bits, masks) {
+                (24, _) => PixelColor::new(row[x * 3 + 2], row[x * 3 + 1], row[x * 3]),
+                (16, Some(masks)) => {
+                    let value = u32::from(u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]));
+                    masked_color(value, masks)
+                }
+                (32, Some(masks)) => {
+                    let value = u32::from_le_bytes([row[x * 4],
+                                                    row[x * 4 + 1],
+                                                    row[x * 4 + 2],
+                                                    row[x * 4 + 3]]);
+                    masked_color(value, masks)
+                }
+                _ => {
+                    let per_byte = 8 / bits as usize;
+                    let shift = 8 - bits as usize * (x % per_byte + 1);
+                    let index = (row[x / per_byte] >> shift) & ((1u16 << bits) - 1) as u8;
+                    *palette.get(index as usize)
+                            .ok_or_else(|| invalid("BMP palette index is out of range"))?
+                }
+            };
+            pixels.push(color);
+        }
+    }
+    Ok(Image::new(width, height, pixels))
+}
+
+// The color of a pixel, with each channel given by a contiguous mask.
+fn masked_color(value: u32, masks: [u32; 3]) -> PixelColor {
+    let channel = |mask: u32| {
+        if mask == 0 {
+            return 0;
+        }
+        let max = mask >> mask.trailing_zeros();
+        scale_sample((value & mask) >> mask.trailing_zeros(), max)
+    };
+    PixelColor::new(channel(masks[0]), channel(masks[1]), channel(masks[2]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A BMP image with a `BITMAPINFOHEADER`, followed by the palette, or masks, and the data.
+    fn bmp(width: i32, height: i32, bits: u16, compression: u32, extra: &[u8], data: &[u8])
+           -> Vec<u8> {
+        let data_offset = 14 + 40 + extra.len() as u32;
+        let mut bmp = b"BM".to_vec();
+        bmp.extend_from_slice(&(data_offset + data.len() as u32).to_le_bytes());
+        bmp.extend_from_slice(&[0; 4]);
+        bmp.extend_from_slice(&data_offset.to_le_bytes());
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&width.to_le_bytes());
+        bmp.extend_from_slice(&height.to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&bits.to_le_bytes());
+        bmp.extend_from_slice(&compression.to_le_bytes());
+        bmp.extend_from_slice(&[0; 20]);
+        bmp.extend_from_slice(extra);
+        bmp.extend_from_slice(data);
+        bmp
+    }
+
+    #[test]
+    fn bottom_up_24_bit_bmp_is_decoded() {
+        // rows are stored bottom-up, in BGR order, and padded to 4 bytes.
+        let data = [0, 0, 0xFF, 0, 0xFF, 0, 0, 0, 0xFF, 0, 0, 1, 2, 3, 0, 0];
+        let image = decode_bmp(&bmp(2, 2, 24, BI_RGB, &[], &data)).unwrap();
+        assert_eq!(image,
+                   Image::new(2,
+                              2,
+                              vec![PixelColor::BLUE,
+                                   PixelColor::new(3, 2, 1),
+                                   PixelColor::RED,
+                                   PixelColor::GREEN]));
+    }
+
+    #[test]
+    fn top_down_32_bit_bmp_is_decoded() {
+        let data = [3, 2, 1, 0, 0xFF, 0xFF, 0xFF, 0];
+        let image = decode_bmp(&bmp(1, -2, 32, BI_RGB, &[], &data)).unwrap();
+        assert_eq!(image,
+                   Image::new(1, 2, vec![PixelColor::new(1, 2, 3), PixelColor::WHITE]));
+    }
+
+    #[test]
+    fn bmp_with_bitfields_is_decoded() {
+        // RGB565 pixels, with their masks following the header.
+        let mut masks = Vec::new();
+        for mask in &[0xF800u32, 0x07E0, 0x001F] {
+            masks.extend_from_slice(&mask.to_le_bytes());
+        }
+        let data = [0x00, 0xF8, 0xE0, 0x07];
+        let image = decode_bmp(&bmp(2, 1, 16, BI_BITFIELDS, &masks, &data)).unwrap();
+        assert_eq!(image, Image::new(2, 1, vec![PixelColor::RED, PixelColor::GREEN]));
+    }
+
+    #[test]
+    fn bmp_with_32_bit_wide_masks_is_decoded() {
+        let mut masks = Vec::new();
+        for mask in &[0xFFFF_FFFFu32, 0, 0] {
+            masks.extend_from_slice(&mask.to_le_bytes());
+        }
+        let data = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F];
+        let image = decode_bmp(&bmp(2, 1, 32, BI_BITFIELDS, &masks, &data)).unwrap();
+        assert_eq!(image,
+                   Image::new(2, 1, vec![PixelColor::RED, PixelColor::new(0x7F, 0, 0)]));
+    }
+
+    #[test]
+    fn bmp_with_a_palette_is_decoded() {
+        let palette = [0, 0, 0, 0, 0xFF, 0, 0, 0];
+        let data = [0b1010_0000, 0, 0, 0];
+        let image = decode_bmp(&bmp(3, 1, 1, BI_RGB, &palette, &data)).unwrap();
+        assert_eq!(image,
+                   Image::new(3, 1, vec![PixelColor::BLUE, PixelColor::BLACK, PixelColor::BLUE]));
+    }
+
+    #[test]
+    fn invalid_bmp_is_rejected() {
+        assert!(decode_bmp(&bmp(2, 2, 24, BI_RGB, &[], &[0; 8])).is_err());
+        assert!(decode_bmp(&bmp(1, 1, 8, 1, &[], &[0; 4])).is_err());
+        assert!(decode_bmp(&bmp(0, 1, 24, BI_RGB, &[], &[0; 4])).is_err());
+        assert!(decode_bmp(b"BM").is_err());
+    }
+
+    #[test]
+    fn bmp_with_maximal_header_fields_is_rejected() {
+        let mut image = bmp(i32::MAX, i32::MIN + 1, 32, BI_RGB, &[], &[0; 8]);
+        image[10..14].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode_bmp(&image).is_err());
+
+        // a palette with the most colors, after the largest header.
+        let mut image = bmp(i32::MAX, 1, 8, BI_RGB, &[], &[0; 8]);
+        image[14..18].copy_from_slice(&u32::MAX.to_le_bytes());
+        image[46..50].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode_bmp(&image).is_err());
+    }
+}
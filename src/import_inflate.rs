@@ -0,0 +1,344 @@
+//! Decompression of zlib streams, as found in PNG images.
+use super::invalid;
+use checksum::adler32;
+use error::Result;
+
+// Base lengths, and extra bits, of the length codes 257 to 285.
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43,
+                                51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4,
+                                4, 4, 5, 5, 5, 5, 0];
+// Base distances, and extra bits, of the distance codes 0 to 29.
+const DIST_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257,
+                              385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289,
+                              16385, 24577];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9,
+                              10, 10, 11, 11, 12, 12, 13, 13];
+// Order in which the code lengths of the code length alphabet are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2,
+                                        14, 1, 15];
+
+/// Decompress a zlib stream, of at most `limit` bytes, and check its Adler-32 checksum. Fails as
+/// soon as the output is longer than `limit`.
+pub fn zlib_decompress(data: &[u8], limit: usize) -> Result<Vec<u8>> {
+    if data.len() < 6 {
+        return Err(invalid("zlib stream is too short"));
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0F != 8 || (u16::from(cmf) << 8 | u16::from(flg)) % 31 != 0 {
+        return Err(invalid("invalid zlib header"));
+    }
+    if flg & 0x20 != 0 {
+        return Err(invalid("zlib preset dictionaries are not supported"));
+    }
+    let mut reader = BitReader::new(&data[2..]);
+    let output = inflate(&mut reader, limit)?;
+    let checksum = reader.aligned_bytes(4)?;
+    let expected = u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]);
+    if adler32(&output) != expected {
+        return Err(invalid("zlib checksum mismatch"));
+    }
+    Ok(output)
+}
+
+// Decompress raw deflate blocks, of at most `limit` bytes, until the final one.
+fn inflate(reader: &mut BitReader, limit: usize) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                let header = reader.aligned_bytes(4)?;
+                let len = u16::from_le_bytes([header[0], header[1]]);
+                let nlen = u16::from_le_bytes([header[2], header[3]]);
+                if len != !nlen {
+                    return Err(invalid("invalid stored block length"));
+                }
+                check_limit(&output, len as usize, limit)?;
+                output.extend_from_slice(reader.aligned_bytes(len as usize)?);
+            }
+            1 => {
+                let (lengths, distances) = fixed_codes();
+                inflate_block(reader, &mut output, &lengths, &distances, limit)?;
+            }
+            2 => {
+                let (lengths, distances) = dynamic_codes(reader)?;
+                inflate_block(reader, &mut output, &lengths, &distances, limit)?;
+            }
+            _ => return Err(invalid("invalid deflate block type")),
+        }
+        if last {
+            return Ok(output);
+        }
+    }
+}
+
+// Decompress the symbols of a block, coded with the given Huffman codes, into an output of at
+// most `limit` bytes.
+fn inflate_block(reader: &mut BitReader,
+                 output: &mut Vec<u8>,
+                 lengths: &Huffman,
+                 distances: &Huffman,
+                 limit: usize)
+                 -> Result<()> {
+    loop {
+        let symbol = lengths.decode(reader)?;
+        match symbol {
+            0..=255 => {
+                check_limit(output, 1, limit)?;
+                output.push(symbol as u8);
+            }
+            256 => return Ok(()),
+            257..=285 => {
+                let index = (symbol - 257) as usize;
+                let length = LENGTH_BASE[index] as usize
+                             + reader.bits(LENGTH_EXTRA[index])? as usize;
+                let index = distances.decode(reader)? as usize;
+                if index >= DIST_BASE.len() {
+                    return Err(invalid("invalid deflate distance"));
+                }
+                let distance = DIST_BASE[index] as usize
+                               + reader.bits(DIST_EXTRA[index])? as usize;
+                if distance > output.len() {
+                    return Err(invalid("deflate distance is too far back"));
+                }
+                check_limit(output, length, limit)?;
+                let start = output.len() - distance;
+                for offset in 0..length {
+                    let byte = output[start + offset];
+                    output.push(byte);
+                }
+            }
+            _ => return Err(invalid("invalid deflate symbol")),
+        }
+    }
+}
+
+// Fail if adding `len` bytes to the output would make it longer than `limit`.
+fn check_limit(output: &[u8], len: usize, limit: usize) -> Result<()> {
+    if len > limit - output.len() {
+        return Err(invalid("zlib stream is longer than expected"));
+    }
+    Ok(())
+}
+
+// The fixed Huffman codes of deflate.
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    for (symbol, length) in lengths.iter_mut().enumerate() {
+        *length = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+// Read the Huffman codes of a dynamic block.
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman)> {
+    let literals = reader.bits(5)? as usize + 257;
+    let distances = reader.bits(5)? as usize + 1;
+    let code_lengths = reader.bits(4)? as usize + 4;
+
+    let mut lengths = [0u8; 19];
+    for &index in CODE_LENGTH_ORDER.iter().take(code_lengths) {
+        lengths[index] = reader.bits(3)? as u8;
+    }
+    let code = Huffman::new(&lengths);
+
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let (length, repeat) = match code.decode(reader)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 => match lengths.last() {
+                Some(&previous) => (previous, 3 + reader.bits(2)?),
+                None => return Err(invalid("invalid deflate code lengths")),
+            },
+            17 => (0, 3 + reader.bits(3)?),
+            18 => (0, 11 + reader.bits(7)?),
+            _ => return Err(invalid("invalid deflate code lengths")),
+        };
+        for _ in 0..repeat {
+            lengths.push(length);
+        }
+    }
+    if lengths.len() != literals + distances {
+        return Err(invalid("invalid deflate code lengths"));
+    }
+    Ok((Huffman::new(&lengths[..literals]), Huffman::new(&lengths[literals..])))
+}
+
+// A canonical Huffman code, decoded one bit at a time.
+struct Huffman {
+    // number of codes of each length.
+    counts: [u16; 16],
+    // symbols, ordered by their code.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for length in 1..16 {
+            offsets[length] = offsets[length - 1] + counts[length - 1];
+        }
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = i32::from(self.counts[length]);
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err(invalid("invalid deflate code"))
+    }
+}
+
+// Reads bits, least significant first, from a slice of bytes.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u8,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader { data,
+                    position: 0,
+                    buffer: 0,
+                    count: 0, }
+    }
+
+    fn bits(&mut self, count: u8) -> Result<u32> {
+        while self.count < count {
+            let byte = match self.data.get(self.position) {
+                Some(&byte) => byte,
+                None => return Err(invalid("deflate stream ended unexpectedly")),
+            };
+            self.position += 1;
+            self.buffer |= u32::from(byte) << self.count;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1u32 << count) - 1);
+        self.buffer >>= count;
+        self.count -= count;
+        Ok(value)
+    }
+
+    // Skip to the next byte boundary, and read whole bytes.
+    fn aligned_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        // bytes left in the buffer are given back, as they were never consumed.
+        self.position -= (self.count / 8) as usize;
+        self.buffer = 0;
+        self.count = 0;
+        let end = self.position + len;
+        if end > self.data.len() {
+            return Err(invalid("deflate stream ended unexpectedly"));
+        }
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &[u8] = b"the quick brown fox jumps over the lazy dog, \
+                          the quick brown fox jumps over the lazy dog!";
+
+    #[test]
+    fn zlib_stream_with_stored_blocks_is_decompressed() {
+        let mut zlib = vec![0x78, 0x01, 0x01, 5, 0, 0xFA, 0xFF];
+        zlib.extend_from_slice(b"hello");
+        zlib.extend_from_slice(&adler32(b"hello").to_be_bytes());
+        assert_eq!(zlib_decompress(&zlib, 5).unwrap(), b"hello".to_vec());
+    }
+
+    #[test]
+    fn zlib_stream_with_fixed_codes_is_decompressed() {
+        // zlib.compressobj(9, zlib.DEFLATED, 15, 9, zlib.Z_FIXED)
+        let zlib = [0x78, 0x01, 0x2B, 0xC9, 0x48, 0x55, 0x28, 0x2C, 0xCD, 0x4C, 0xCE, 0x56,
+                    0x48, 0x2A, 0xCA, 0x2F, 0xCF, 0x53, 0x48, 0xCB, 0xAF, 0x50, 0xC8, 0x2A,
+                    0xCD, 0x2D, 0x28, 0x56, 0xC8, 0x2F, 0x4B, 0x2D, 0x52, 0x28, 0x01, 0x4A,
+                    0xE7, 0x24, 0x56, 0x55, 0x2A, 0xA4, 0xE4, 0xA7, 0xEB, 0x80, 0x79, 0x44,
+                    0x2A, 0x56, 0x04, 0x00, 0xBF, 0x08, 0x20, 0x60];
+        assert_eq!(zlib_decompress(&zlib, TEXT.len()).unwrap(), TEXT.to_vec());
+    }
+
+    #[test]
+    fn zlib_stream_with_dynamic_codes_is_decompressed() {
+        // letters picked by a linear congruential generator, compressed by zlib.compress.
+        let mut seed = 1u32;
+        let data = (0..256).map(|_| {
+                               seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345)
+                                      & 0x7FFF_FFFF;
+                               b"eeeeeeettttaaoinshrdlu"[(seed >> 16) as usize % 22]
+                           })
+                           .collect::<Vec<u8>>();
+        let zlib = [0x78, 0xDA, 0x1D, 0x8E, 0x41, 0x0A, 0x00, 0x31, 0x08, 0x03, 0xDF, 0x2A,
+                    0x34, 0xA0, 0x20, 0x15, 0x34, 0xFE, 0x7F, 0xD3, 0xED, 0x4D, 0xCD, 0x4C,
+                    0xC3, 0xB9, 0xA0, 0x27, 0x72, 0x96, 0x09, 0x27, 0x4F, 0x61, 0x50, 0xF7,
+                    0x02, 0x36, 0xCD, 0x1B, 0x51, 0x57, 0x91, 0xA0, 0xDE, 0x35, 0x22, 0x1B,
+                    0x3D, 0x4A, 0xC6, 0x92, 0x38, 0xD4, 0xC1, 0x8A, 0x8D, 0xB4, 0x84, 0x88,
+                    0xC2, 0x46, 0x59, 0x14, 0xAB, 0x25, 0x70, 0xC9, 0xC4, 0x1A, 0x8F, 0xE9,
+                    0x0F, 0xFA, 0xE0, 0x36, 0x40, 0xD1, 0xE3, 0x39, 0x7C, 0xD6, 0x15, 0x43,
+                    0x13, 0x69, 0x7D, 0x40, 0xB8, 0x3F, 0x0B, 0xD6, 0x74, 0x10, 0x07, 0x9C,
+                    0xC5, 0x5B, 0x85, 0xCA, 0xBC, 0x11, 0x2F, 0xBB, 0xD3, 0x4F, 0xF7, 0x46,
+                    0xA7, 0x30, 0x53, 0x3A, 0x5D, 0xF4, 0xEA, 0xE8, 0x7F, 0xC3, 0x68, 0x48,
+                    0x4B, 0x58, 0x2A, 0x55, 0xAF, 0xFB, 0x76, 0x0C, 0xB4, 0x2D, 0x86, 0xA9,
+                    0x77, 0x04, 0xF6, 0xF7, 0x7D, 0x09, 0x66, 0x6B, 0x46];
+        assert_eq!(zlib_decompress(&zlib, data.len()).unwrap(), data);
+    }
+
+    #[test]
+    fn zlib_stream_with_a_bad_checksum_is_rejected() {
+        let mut zlib = vec![0x78, 0x01, 0x01, 5, 0, 0xFA, 0xFF];
+        zlib.extend_from_slice(b"hello");
+        zlib.extend_from_slice(&[0, 0, 0, 0]);
+        assert!(zlib_decompress(&zlib, 5).is_err());
+        assert!(zlib_decompress(&[0x78, 0x02, 0, 0, 0, 0], 5).is_err());
+    }
+
+    #[test]
+    fn zlib_stream_is_not_decompressed_past_its_limit() {
+        let mut zlib = vec![0x78, 0x01, 0x01, 5, 0, 0xFA, 0xFF];
+        zlib.extend_from_slice(b"hello");
+        zlib.extend_from_slice(&adler32(b"hello").to_be_bytes());
+        assert!(zlib_decompress(&zlib, 4).is_err());
+        // a thousand bytes, repeated with fixed codes, are stopped before they are copied.
+        let zlib = [0x78, 0x01, 0x4B, 0x4C, 0x1C, 0x05, 0xA3, 0x60, 0x14, 0x0C, 0x77, 0x00,
+                    0x00, 0xF9, 0xD8, 0x7A, 0xF8];
+        assert_eq!(zlib_decompress(&zlib, 1000).unwrap(), vec![b'a'; 1000]);
+        match zlib_decompress(&zlib, 999) {
+            Err(::error::ScreenError::InvalidImage { ref reason }) => {
+                assert_eq!(reason, "zlib stream is longer than expected")
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
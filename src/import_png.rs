@@ -0,0 +1,451 @@
+//! PNG decoding, of every color type, bit depth, and interlace method.
+use super::inflate::zlib_decompress;
+use super::{div_ceil, invalid, scale_sample, Image};
+use checksum::crc32;
+use error::Result;
+use PixelColor;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+// Column, and row, of the first pixel of each Adam7 pass, and the steps between pixels.
+const ADAM7: [(usize, usize, usize, usize); 7] = [(0, 0, 8, 8),
+                                                  (4, 0, 8, 8),
+                                                  (0, 4, 4, 8),
+                                                  (2, 0, 4, 4),
+                                                  (0, 2, 2, 4),
+                                                  (1, 0, 2, 2),
+                                                  (0, 1, 1, 2)];
+// Largest width, and height, of an image that is decoded.
+const MAX_DIMENSION: usize = 1 << 16;
+// Largest number of pixels of an image that is decoded, as many as 8192 by 8192 pixels.
+const MAX_PIXELS: usize = 1 << 26;
+
+// The layout of the pixels, as given by the `IHDR` chunk.
+struct Header {
+    width: usize,
+    height: usize,
+    depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    // Number of samples in each pixel.
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+
+    // Number of bytes between a byte, and the matching byte of the previous pixel.
+    fn filter_distance(&self) -> usize {
+        div_ceil(self.channels() * self.depth as usize, 8)
+    }
+
+    // Number of bytes in a line of `width` pixels, without its filter type.
+    fn line_bytes(&self, width: usize) -> usize {
+        div_ceil(width * self.channels() * self.depth as usize, 8)
+    }
+
+    // The Adam7 passes of an interlaced image, or a single pass over the whole image.
+    fn passes(&self) -> &'static [(usize, usize, usize, usize)] {
+        if self.interlaced {
+            &ADAM7
+        } else {
+            &[(0, 0, 1, 1)]
+        }
+    }
+
+    // Width, and height, of the pixels of a pass, which are both zero if the pass is empty.
+    fn pass_size(&self, (x0, y0, dx, dy): (usize, usize, usize, usize)) -> (usize, usize) {
+        if x0 >= self.width || y0 >= self.height {
+            return (0, 0);
+        }
+        (div_ceil(self.width - x0, dx), div_ceil(self.height - y0, dy))
+    }
+
+    // Number of bytes of the filtered passes, with the filter type of every line, or `None`
+    // if it does not fit in a `usize`.
+    fn filtered_bytes(&self) -> Option<usize> {
+        self.passes().iter().try_fold(0usize, |total, &pass| {
+                                 let (width, height) = self.pass_size(pass);
+                                 (self.line_bytes(width) + 1).checked_mul(height)?
+                                                             .checked_add(total)
+                             })
+    }
+}
+
+// Decode a PNG image. Transparent pixels are blended over black.
+pub fn decode_png(bytes: &[u8]) -> Result<Image> {
+    if !bytes.starts_with(&SIGNATURE) {
+        return Err(invalid("invalid PNG signature"));
+    }
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut transparency = Vec::new();
+    let mut compressed = Vec::new();
+    let mut rest = &bytes[8..];
+    loop {
+        if rest.len() < 12 {
+            return Err(invalid("PNG image ended unexpectedly"));
+        }
+        let len = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        if len.checked_add(12).map_or(true, |end| rest.len() < end) {
+            return Err(invalid("PNG image ended unexpectedly"));
+        }
+        let (kind, data) = (&rest[4..8], &rest[8..8 + len]);
+        let crc = &rest[8 + len..12 + len];
+        if crc32(kind.iter().chain(data.iter()).cloned()).to_be_bytes() != crc {
+            return Err(invalid("PNG chunk checksum mismatch"));
+        }
+        match kind {
+            b"IHDR" => header = Some(read_header(data)?),
+            b"PLTE" => palette = data.to_vec(),
+            b"tRNS" => transparency = data.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        rest = &rest[12 + len..];
+    }
+    let header = header.ok_or_else(|| invalid("PNG image has no IHDR chunk"))?;
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(invalid("PNG image has no PLTE chunk"));
+    }
+
+    // the data is never inflated past the size of the image, given by its header.
+    let expected = header.filtered_bytes()
+                         .ok_or_else(|| invalid("PNG image is too large"))?;
+    let raw = zlib_decompress(&compressed, expected)?;
+    if raw.len() < expected {
+        return Err(invalid("PNG image data is too short"));
+    }
+    let count = header.width
+                      .checked_mul(header.height)
+                      .ok_or_else(|| invalid("PNG image is too large"))?;
+    let mut pixels = vec![PixelColor::BLACK; count];
+    let mut offset = 0;
+    for &(x0, y0, dx, dy) in header.passes() {
+        let (width, height) = header.pass_size((x0, y0, dx, dy));
+        if height == 0 {
+            continue;
+        }
+        let len = (header.line_bytes(width) + 1) * height;
+        let data = raw.get(offset..offset + len)
+                      .ok_or_else(|| invalid("PNG image data is too short"))?;
+        offset += len;
+        let lines = unfilter(&header, width, data)?;
+        for (y, line) in lines.iter().enumerate() {
+            for x in 0..width {
+                let color = pixel_color(&header, line, x, &palette, &transparency)?;
+                pixels[(y0 + y * dy) * header.width + x0 + x * dx] = color;
+            }
+        }
+    }
+    Ok(Image::new(header.width as u32, header.height as u32, pixels))
+}
+
+fn read_header(data: &[u8]) -> Result<Header> {
+    if data.len() != 13 {
+        return Err(invalid("invalid PNG header"));
+    }
+    let number = |offset: usize| {
+        u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+    };
+    let header = Header { width: number(0) as usize,
+                          height: number(4) as usize,
+                          depth: data[8],
+                          color_type: data[9],
+                          interlaced: data[12] == 1, };
+    let valid_depth = match header.color_type {
+        0 => [1, 2, 4, 8, 16].contains(&header.depth),
+        3 => [1, 2, 4, 8].contains(&header.depth),
+        2 | 4 | 6 => [8, 16].contains(&header.depth),
+        _ => false,
+    };
+    if !valid_depth || header.width == 0 || header.height == 0 || data[10] != 0 || data[11] != 0
+       || data[12] > 1
+    {
+        return Err(invalid("unsupported PNG header"));
+    }
+    let pixels = header.width.checked_mul(header.height);
+    if header.width > MAX_DIMENSION || header.height > MAX_DIMENSION
+       || pixels.map_or(true, |pixels| pixels > MAX_PIXELS)
+    {
+        return Err(invalid("PNG image is too large"));
+    }
+    Ok(header)
+}
+
+// Undo the filter of every line of a pass, `width` pixels wide.
+fn unfilter(header: &Header, width: usize, data: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let distance = header.filter_distance();
+    let len = header.line_bytes(width);
+    let mut lines: Vec<Vec<u8>> = Vec::new();
+    for filtered in data.chunks(len + 1) {
+        let mut line = filtered[1..].to_vec();
+        let previous = lines.last().cloned().unwrap_or_else(|| vec![0; len]);
+        for i in 0..len {
+            let left = if i >= distance { line[i - distance] } else { 0 };
+            let up = previous[i];
+            let up_left = if i >= distance { previous[i - distance] } else { 0 };
+            let predictor = match filtered[0] {
+                0 => 0,
+                1 => left,
+                2 => up,
+                3 => ((u16::from(left) + u16::from(up)) / 2) as u8,
+                4 => paeth(left, up, up_left),
+                _ => return Err(invalid("invalid PNG filter type")),
+            };
+            line[i] = line[i].wrapping_add(predictor);
+        }
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+// The Paeth predictor: whichever of left, up, or up-left is closest to `left + up - up_left`.
+fn paeth(left: u8, up: u8, up_left: u8) -> u8 {
+    let estimate = i16::from(left) + i16::from(up) - i16::from(up_left);
+    let (a, b, c) = ((estimate - i16::from(left)).abs(),
+                     (estimate - i16::from(up)).abs(),
+                     (estimate - i16::from(up_left)).abs());
+    if a <= b && a <= c {
+        left
+    } else if b <= c {
+        up
+    } else {
+        up_left
+    }
+}
+
+// The color of the pixel at column `x` of an unfiltered line.
+fn pixel_color(header: &Header,
+               line: &[u8],
+               x: usize,
+               palette: &[u8],
+               transparency: &[u8])
+               -> Result<PixelColor> {
+    let depth = u32::from(header.depth);
+    let max = (1u32 << depth) - 1;
+    let samples = (0..header.channels()).map(|channel| {
+                                            let index = x * header.channels() + channel;
+                                            sample(line, index, header.depth)
+                                        })
+                                        .collect::<Vec<u32>>();
+    let (rgb, alpha) = match header.color_type {
+        0 => {
+            let gray = scale_sample(samples[0], max);
+            ([gray; 3], key_alpha(transparency, &samples))
+        }
+        2 => ([scale_sample(samples[0], max),
+               scale_sample(samples[1], max),
+               scale_sample(samples[2], max)],
+              key_alpha(transparency, &samples)),
+        3 => {
+            let index = samples[0] as usize;
+            let rgb = palette.get(index * 3..index * 3 + 3)
+                             .ok_or_else(|| invalid("PNG palette index is out of range"))?;
+            ([rgb[0], rgb[1], rgb[2]], transparency.get(index).cloned().unwrap_or(255))
+        }
+        4 => {
+            let gray = scale_sample(samples[0], max);
+            ([gray; 3], scale_sample(samples[1], max))
+        }
+        _ => ([scale_sample(samples[0], max),
+               scale_sample(samples[1], max),
+               scale_sample(samples[2], max)],
+              scale_sample(samples[3], max)),
+    };
+    let blend = |value: u8| ((u32::from(value) * u32::from(alpha) + 127) / 255) as u8;
+    Ok(PixelColor::new(blend(rgb[0]), blend(rgb[1]), blend(rgb[2])))
+}
+
+// The alpha of a gray, or truecolor, pixel: transparent if its samples are the single
+// transparent color given by the `tRNS` chunk.
+fn key_alpha(transparency: &[u8], samples: &[u32]) -> u8 {
+    let key = transparency.chunks(2)
+                          .map(|pair| u32::from(pair[0]) << 8 | u32::from(pair[pair.len() - 1]))
+                          .collect::<Vec<u32>>();
+    if key == samples {
+        0
+    } else {
+        255
+    }
+}
+
+// The sample at `index` of a line, with samples of `depth` bits packed most significant first.
+fn sample(line: &[u8], index: usize, depth: u8) -> u32 {
+    match depth {
+        16 => u32::from(u16::from_be_bytes([line[index * 2], line[index * 2 + 1]])),
+        8 => u32::from(line[index]),
+        _ => {
+            let per_byte = 8 / depth as usize;
+            let shift = 8 - depth as usize * (index % per_byte + 1);
+            u32::from((line[index / per_byte] >> shift) & ((1u16 << depth) - 1) as u8)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 3x5 RGBA image, with half-transparent pixels, and a line of each filter type.
+    const RGBA: [u8; 128] = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00,
+                             0x00, 0x0D, 0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x03,
+                             0x00, 0x00, 0x00, 0x05, 0x08, 0x06, 0x00, 0x00, 0x00, 0x80,
+                             0x71, 0x56, 0xA2, 0x00, 0x00, 0x00, 0x47, 0x49, 0x44, 0x41,
+                             0x54, 0x78, 0xDA, 0x63, 0x60, 0x38, 0xC1, 0xD0, 0xA0, 0x71,
+                             0x9E, 0xE1, 0x7F, 0xC0, 0x35, 0x86, 0xFF, 0x8C, 0x5C, 0xAB,
+                             0x18, 0xFE, 0x6B, 0xB0, 0x2B, 0x32, 0x00, 0x71, 0x23, 0x13,
+                             0xD7, 0x23, 0x06, 0x06, 0xAE, 0x47, 0x8A, 0x8D, 0x5C, 0x8F,
+                             0x9C, 0xEA, 0x99, 0x45, 0x34, 0x18, 0x18, 0x25, 0xBF, 0x3A,
+                             0xD5, 0x4B, 0x7E, 0x0D, 0x66, 0x60, 0x01, 0xCA, 0xD4, 0x73,
+                             0x01, 0x95, 0x71, 0xB1, 0x3B, 0x35, 0x02, 0x00, 0x8D, 0x85,
+                             0x12, 0x47, 0xE7, 0x4C, 0x2F, 0xBC, 0x00, 0x00, 0x00, 0x00,
+                             0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82];
+    // 5x1 palette image, with 2 bits per pixel, and a transparent blue.
+    const PALETTE: [u8; 107] = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00,
+                                0x00, 0x0D, 0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x05,
+                                0x00, 0x00, 0x00, 0x01, 0x02, 0x03, 0x00, 0x00, 0x00, 0x6B,
+                                0x90, 0x8C, 0x60, 0x00, 0x00, 0x00, 0x0C, 0x50, 0x4C, 0x54,
+                                0x45, 0xFF, 0x00, 0x00, 0x00, 0xFF, 0x00, 0x00, 0x00, 0xFF,
+                                0xFF, 0xFF, 0xFF, 0xFB, 0x00, 0x60, 0xF6, 0x00, 0x00, 0x00,
+                                0x03, 0x74, 0x52, 0x4E, 0x53, 0xFF, 0xFF, 0x00, 0xD7, 0xCA,
+                                0x0D, 0x41, 0x00, 0x00, 0x00, 0x0B, 0x49, 0x44, 0x41, 0x54,
+                                0x78, 0xDA, 0x63, 0x90, 0x6E, 0x00, 0x00, 0x00, 0xB9, 0x00,
+                                0x9C, 0xC2, 0x2A, 0xB6, 0xAC, 0x00, 0x00, 0x00, 0x00, 0x49,
+                                0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60, 0x82];
+    // 5x5 interlaced gray image, with 16 bits per pixel, and a transparent gray.
+    const GRAY16: [u8; 141] = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00,
+                               0x00, 0x0D, 0x49, 0x48, 0x44, 0x52, 0x00, 0x00, 0x00, 0x05,
+                               0x00, 0x00, 0x00, 0x05, 0x10, 0x00, 0x00, 0x00, 0x01, 0x8F,
+                               0x93, 0x95, 0xEC, 0x00, 0x00, 0x00, 0x02, 0x74, 0x52, 0x4E,
+                               0x53, 0x12, 0x34, 0x2F, 0xD3, 0x49, 0x5E, 0x00, 0x00, 0x00,
+                               0x46, 0x49, 0x44, 0x41, 0x54, 0x78, 0xDA, 0x63, 0x60, 0x60,
+                               0x60, 0xF1, 0x53, 0x60, 0xE8, 0x59, 0x70, 0xEB, 0x00, 0x93,
+                               0xBA, 0x00, 0xF3, 0x82, 0x15, 0x0C, 0x6E, 0x01, 0xB9, 0x09,
+                               0x53, 0x0A, 0x18, 0x85, 0x3B, 0xD4, 0x05, 0x98, 0xDC, 0x02,
+                               0xDC, 0x02, 0x98, 0x4B, 0xF6, 0x98, 0x6F, 0x60, 0x50, 0xD6,
+                               0x30, 0xDB, 0xE0, 0x65, 0x11, 0x7B, 0xA0, 0xD0, 0x83, 0x31,
+                               0xB3, 0x42, 0xA4, 0x43, 0xB8, 0xA3, 0x69, 0xCD, 0xD2, 0x14,
+                               0x00, 0x11, 0x44, 0x13, 0x18, 0x18, 0x68, 0x6D, 0x8D, 0x00,
+                               0x00, 0x00, 0x00, 0x49, 0x45, 0x4E, 0x44, 0xAE, 0x42, 0x60,
+                               0x82];
+
+    #[test]
+    fn filtered_rgba_png_is_decoded_over_black() {
+        let image = decode_png(&RGBA).unwrap();
+        assert_eq!((image.width(), image.height()), (3, 5));
+        assert_eq!(image.pixel(0, 0), PixelColor::new(0, 100, 0));
+        assert_eq!(image.pixel(2, 1), PixelColor::new(45, 92, 33));
+        assert_eq!(image.pixel(0, 3), PixelColor::new(15, 55, 0));
+        assert_eq!(image.pixel(1, 4), PixelColor::new(80, 87, 132));
+        assert_eq!(image.pixel(2, 4), PixelColor::new(60, 47, 4));
+    }
+
+    #[test]
+    fn packed_palette_png_is_decoded() {
+        let image = decode_png(&PALETTE).unwrap();
+        assert_eq!(image,
+                   Image::new(5,
+                              1,
+                              vec![PixelColor::RED,
+                                   PixelColor::GREEN,
+                                   PixelColor::BLACK,
+                                   PixelColor::WHITE,
+                                   PixelColor::BLACK]));
+    }
+
+    #[test]
+    fn interlaced_16_bit_gray_png_is_decoded() {
+        let image = decode_png(&GRAY16).unwrap();
+        let gray = |value| PixelColor::new(value, value, value);
+        assert_eq!(image.pixel(0, 0), gray(0));
+        assert_eq!(image.pixel(2, 1), gray(74));
+        assert_eq!(image.pixel(1, 2), gray(89));
+        assert_eq!(image.pixel(3, 3), PixelColor::BLACK);
+        assert_eq!(image.pixel(4, 4), gray(218));
+    }
+
+    #[test]
+    fn corrupt_png_is_rejected() {
+        let mut png = RGBA;
+        png[40] ^= 0xFF;
+        match decode_png(&png) {
+            Err(::error::ScreenError::InvalidImage { ref reason }) => {
+                assert_eq!(reason, "PNG chunk checksum mismatch")
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(decode_png(&RGBA[..60]).is_err());
+    }
+
+    // The RGBA image, with its header claiming the given dimensions.
+    fn resized_rgba(width: u32, height: u32) -> Vec<u8> {
+        let mut png = RGBA.to_vec();
+        png[16..20].copy_from_slice(&width.to_be_bytes());
+        png[20..24].copy_from_slice(&height.to_be_bytes());
+        let crc = crc32(png[12..29].iter().cloned());
+        png[29..33].copy_from_slice(&crc.to_be_bytes());
+        png
+    }
+
+    #[test]
+    fn png_larger_than_its_data_is_rejected_before_allocating() {
+        let reason = |png: &[u8]| match decode_png(png) {
+            Err(::error::ScreenError::InvalidImage { reason }) => reason,
+            other => panic!("unexpected result {:?}", other),
+        };
+        assert_eq!(reason(&resized_rgba(1 << 30, 1 << 30)), "PNG image is too large");
+        assert_eq!(reason(&resized_rgba(1 << 12, 1 << 12)), "PNG image data is too short");
+        assert_eq!(reason(&resized_rgba(3, 1 << 16)), "PNG image data is too short");
+    }
+
+    #[test]
+    fn png_with_too_many_pixels_is_rejected() {
+        let reason = |png: &[u8]| match decode_png(png) {
+            Err(::error::ScreenError::InvalidImage { reason }) => reason,
+            other => panic!("unexpected result {:?}", other),
+        };
+        assert_eq!(reason(&resized_rgba(1 << 16, 1 << 16)), "PNG image is too large");
+        assert_eq!(reason(&resized_rgba(1 << 13, (1 << 13) + 1)), "PNG image is too large");
+        assert_eq!(reason(&resized_rgba(1 << 16, 1 << 10)), "PNG image data is too short");
+        assert_eq!(reason(&resized_rgba(1 << 13, 1 << 13)), "PNG image data is too short");
+    }
+
+    #[test]
+    fn png_data_is_not_inflated_past_the_size_of_the_image() {
+        match decode_png(&resized_rgba(1, 1)) {
+            Err(::error::ScreenError::InvalidImage { ref reason }) => {
+                assert_eq!(reason, "zlib stream is longer than expected")
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn png_with_maximal_chunk_length_is_rejected() {
+        let mut png = RGBA.to_vec();
+        png[33..37].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(decode_png(&png).is_err());
+        png[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(decode_png(&png).is_err());
+    }
+
+    #[cfg(feature = "export")]
+    #[test]
+    fn exported_png_is_imported_back() {
+        let mut frame = ::PixelFrame::BLUE;
+        frame[9] = PixelColor::new(1, 2, 3);
+        let mut png = Vec::new();
+        ::export::write_png(&frame, 3, &mut png).unwrap();
+        let image = decode_png(&png).unwrap();
+        assert_eq!((image.width(), image.height()), (24, 24));
+        assert_eq!(image.pixel(4, 5), PixelColor::new(1, 2, 3));
+        assert_eq!(image.resample(::import::Dither::None), frame);
+    }
+}
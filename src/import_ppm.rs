@@ -0,0 +1,117 @@
+//! PPM decoding, of both the plain (`P3`), and binary (`P6`), variants.
+use super::{invalid, scale_sample, Image};
+use error::Result;
+use PixelColor;
+
+// Decode a PPM image, with samples of up to 16 bits.
+pub fn decode_ppm(bytes: &[u8]) -> Result<Image> {
+    let mut reader = Tokens { bytes, position: 2 };
+    let width = reader.number()?;
+    let height = reader.number()?;
+    let max = reader.number()?;
+    if width == 0 || height == 0 || max == 0 || max > 0xFFFF {
+        return Err(invalid("invalid PPM header"));
+    }
+    let samples = (width as usize).checked_mul(height as usize)
+                                  .and_then(|pixels| pixels.checked_mul(3))
+                                  .ok_or_else(|| invalid("PPM image is too large"))?;
+
+    let samples = if bytes[1] == b'6' {
+        // a single whitespace separates the header from the binary samples.
+        let data = bytes.get(reader.position + 1..).unwrap_or(&[]);
+        let size = if max < 256 { 1 } else { 2 };
+        if samples.checked_mul(size).map_or(true, |len| data.len() < len) {
+            return Err(invalid("PPM image ended unexpectedly"));
+        }
+        data.chunks(size)
+            .take(samples)
+            .map(|sample| sample.iter().fold(0, |value, &byte| value << 8 | u32::from(byte)))
+            .collect::<Vec<u32>>()
+    } else {
+        (0..samples).map(|_| reader.number()).collect::<Result<Vec<u32>>>()?
+    };
+    if samples.iter().any(|&sample| sample > max) {
+        return Err(invalid("PPM sample is greater than its maximum value"));
+    }
+
+    let pixels = samples.chunks(3)
+                        .map(|rgb| {
+                                 PixelColor::new(scale_sample(rgb[0], max),
+                                                 scale_sample(rgb[1], max),
+                                                 scale_sample(rgb[2], max))
+                             })
+                        .collect();
+    Ok(Image::new(width, height, pixels))
+}
+
+// Reads the decimal numbers of a PPM header, skipping whitespace and comments.
+struct Tokens<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn number(&mut self) -> Result<u32> {
+        loop {
+            match self.bytes.get(self.position) {
+                Some(b'#') => {
+                    while self.bytes.get(self.position).is_some_and(|&byte| byte != b'\n') {
+                        self.position += 1;
+                    }
+                }
+                Some(byte) if byte.is_ascii_whitespace() => self.position += 1,
+                _ => break,
+            }
+        }
+        let start = self.position;
+        while self.bytes.get(self.position).is_some_and(u8::is_ascii_digit) {
+            self.position += 1;
+        }
+        if start == self.position || self.position - start > 9 {
+            return Err(invalid("invalid PPM number"));
+        }
+        Ok(self.bytes[start..self.position].iter()
+                                          .fold(0, |value, &digit| {
+                                              value * 10 + u32::from(digit - b'0')
+                                          }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_ppm_is_decoded() {
+        let image = decode_ppm(b"P6\n# a comment\n2 1\n255\n\x01\x02\x03\xFF\x80\x00").unwrap();
+        assert_eq!(image,
+                   Image::new(2, 1, vec![PixelColor::new(1, 2, 3), PixelColor::new(255, 128, 0)]));
+    }
+
+    #[test]
+    fn binary_ppm_with_16_bit_samples_is_decoded() {
+        let image = decode_ppm(b"P6 1 1 65535\n\xFF\xFF\x80\x00\x00\x00").unwrap();
+        assert_eq!(image.pixel(0, 0), PixelColor::new(255, 128, 0));
+    }
+
+    #[test]
+    fn plain_ppm_is_decoded_and_scaled() {
+        let image = decode_ppm(b"P3\n1 2 15\n15 0 0\n# green\n0 15 5\n").unwrap();
+        assert_eq!(image,
+                   Image::new(1, 2, vec![PixelColor::RED, PixelColor::new(0, 255, 85)]));
+    }
+
+    #[test]
+    fn truncated_ppm_is_rejected() {
+        assert!(decode_ppm(b"P6\n2 2\n255\n\x01\x02\x03").is_err());
+        assert!(decode_ppm(b"P3\n1 1\n255\n1 2").is_err());
+        assert!(decode_ppm(b"P3\n1 1\n15\n1 2 16").is_err());
+        assert!(decode_ppm(b"P6\n0 1\n255\n").is_err());
+    }
+
+    #[test]
+    fn ppm_with_maximal_dimensions_is_rejected() {
+        assert!(decode_ppm(b"P6\n999999999 999999999\n65535\n\x01\x02\x03").is_err());
+        assert!(decode_ppm(b"P3\n999999999 999999999\n65535\n1 2 3").is_err());
+    }
+}
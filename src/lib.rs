@@ -15,26 +15,101 @@
 //!
 //!   Internally, it stores a `PixelFrame` meant to be rendered on the LED Matrix.
 //!
-//!   With the `linux-framebuffer` feature, enabled by default, `Screen` will have two methods:
+//!   With the `linux-framebuffer` feature, enabled by default, `Screen` will have these methods:
 //!
 //!     1. `Screen::open` which opens the framebuffer
-//!        file-descriptor given as the only argument.
+//!        file-descriptor given as the only argument, and negotiates its `PixelFormat`.
+//!
+//!     1. `Screen::open_sense_hat` which finds, and opens, the framebuffer
+//!        of the Sense HAT, whatever its device number.
 //!
 //!     1. `Screen::write_frame` which takes a
 //!        `&FrameLine` and writes the raw bytes onto the framebuffer, effectively displaying the
 //!        `PixelFrame` on the LED Matrix.
 //!
+//!     1. `Screen::draw`, and `Screen::present` which draw on a back-buffer, and then show it
+//!        on the LED Matrix. Frames that are the same as the last frame written are skipped.
+//!
+//!     1. `Screen::read_frame` which reads back the `FrameLine` currently displayed on the
+//!        LED Matrix, whether it was written by this process, or by any other.
+//!
+//!     1. `Screen::set_rotation`, `Screen::set_flip_h`, and `Screen::set_flip_v` which set
+//!        the orientation of the LED Matrix, transparently applied to every frame written
+//!        and read back.
+//!
+//!     1. `Screen::set_low_light`, `Screen::set_gamma`, and `Screen::reset_gamma` which
+//!        control the gamma table of the LED Matrix driver, to dim the LEDs without losing
+//!        color precision.
+//!
+//!     1. `Screen::set_drop_action`, and `Screen::install_signal_handler` which leave the
+//!        LED Matrix blank, or showing a saved frame, when the `Screen` is dropped, or when
+//!        the process is interrupted.
+//!
+//!   [`ScaledScreen`](./screen/scaled/struct.ScaledScreen.html) draws frames on any other
+//!   framebuffer, such as an HDMI display, scaled up as a grid of square, or round, LEDs.
+//!
+//!   With the `emulator` feature, an
+//!   [`EmulatedFramebuffer`](./screen/emulator/struct.EmulatedFramebuffer.html) is a regular
+//!   file, with fake screen information, that a `Screen` can be opened on, to test the exact
+//!   bytes written by a `Screen` in CI, without the Sense HAT.
+//!
+//!   Every `Screen` operation returns a `Result`, with a
+//!   [`ScreenError`](./error/enum.ScreenError.html) describing what went wrong.
+//!
+//! * [`Display`](./display/trait.Display.html) is the trait for anything that renders frames.
+//!   It is implemented by `Screen`, and by [`MemoryScreen`](./display/struct.MemoryScreen.html),
+//!   an in-memory display that records every frame, for testing without the hardware.
+//!
+//! * [`SharedScreen`](./shared/struct.SharedScreen.html) is a cloneable handle that shares one
+//!   `Display` between threads. Frames are written at a `Priority`, so that an alert pre-empts
+//!   background content, which is shown again once the alert expires.
+//!
+//!   Requires `feature = "shared"`, which is enabled by default.
+//!
+//! * [`RecordingScreen`](./recording/struct.RecordingScreen.html) wraps any `Display`, and
+//!   records every frame written, with a timestamp, in a binary, or JSON-lines, session log.
+//!   `recording::replay` plays a log back on any `Display`, at its original, or scaled, speed.
+//!
+//!   Requires `feature = "recording"`, which is enabled by default.
+//!
+//! * [`TerminalScreen`](./terminal/struct.TerminalScreen.html) draws frames as colored blocks
+//!   in a terminal, with 24-bit, or 256-color, ANSI escapes, redrawing each frame in place.
+//!   `PixelFrame::to_ansi_string` renders a single frame, for logging.
+//!
+//!   Requires `feature = "terminal"`, which is enabled by default.
+//!
 //! * [`PixelFrame`](./frame/struct.PixelFrame.html) is a collection of 64 `PixelColor`, representing the 8-row by 8-column LED
-//! Matrix.
+//!   Matrix.
 //! * [`PixelColor`](./color/struct.PixelColor.html) is a 24-bit representation of an RGB color, encoded in three bytes.
+//!   Colors are also created from, and converted to, HSV, and HSL, with `PixelColor::from_hsv`,
+//!   and `PixelColor::from_hsl`, and have their hue rotated with `PixelColor::rotate_hue`.
+//!   `PixelColor::with_brightness` scales a color by its perceived lightness, keeping its hue
+//!   on the LED Matrix.
+//!   Colors are added, subtracted, and scaled, with saturating operators, and interpolated with
+//!   `PixelColor::lerp`. `PixelColor::quantized` rounds a color to the nearest one that the
+//!   LED Matrix shows exactly, as `PixelFrame::frame_line_rounded` encodes it, and
+//!   `PixelFrame::approx_eq` compares frames within a tolerance.
+//!   They are parsed from `#RGB`, `#RRGGBB`, `rgb(r, g, b)`, or CSS color names, and displayed
+//!   as `#RRGGBB`.
+//! * [`RgbaColor`](./color/blend/struct.RgbaColor.html) is a `PixelColor` with an alpha
+//!   channel. A [`BlendMode`](./color/blend/enum.BlendMode.html) composites colors, and
+//!   `PixelFrame::blend` composites frames, with additive, multiply, screen, or difference
+//!   blending.
+//! * [`Gradient`](./color/gradient/struct.Gradient.html) interpolates between color stops, in
+//!   RGB, HSV, or linear light. `PixelFrame::from_gradient` fills a frame with a gradient, laid
+//!   in a [`GradientDirection`](./frame/gradient/enum.GradientDirection.html).
+//! * [`Palette`](./color/palette/struct.Palette.html) holds up to 256 colors, with PICO-8, CGA,
+//!   and color-blind-safe presets. An [`IndexedFrame`](./frame/indexed/struct.IndexedFrame.html)
+//!   of 64 palette indices is rendered with any palette, for palette-swap animations.
 //!
 //! Low-level constructs
 //! --------------------
 //! * [`Rgb565`](./color/struct.Rgb565.html) is a 16-bit representation of an RGB color, encoded in two bytes. This is the
-//! format. `Rgb565` converts into/from `PixelColor`.
-//! supported by the LED Matrix's framebuffer device.
+//!   format supported by the LED Matrix's framebuffer device. `Rgb565` converts into/from `PixelColor`.
+//! * [`PixelFormat`](./color/format/enum.PixelFormat.html) is the layout of a pixel in the
+//!   framebuffer device: RGB565, BGR565, RGB888, or XRGB8888, in the native byte order.
 //! * [`FrameLine`](./frame/struct.FrameLine.html) is the raw-byte rendering of the `PixelFrame`,
-//! properly encoded and ready to be written into the framebuffer device.
+//!   properly encoded and ready to be written into the framebuffer device.
 //!
 //! Frame operations
 //! ----------------
@@ -57,6 +132,30 @@
 //!
 //!   `Offset` with a value of `n = 8`, return a `PixelFrame` offset out of view, represented with black pixels (LEDs are off).
 //!
+//! * [`Animator`](./animation/struct.Animator.html)
+//!
+//!   Requires `feature = "animation"`, which is enabled by default.
+//!
+//!   Plays any iterator of `PixelFrame`s, such as a `FrameSequence`, on a `Display`, at a
+//!   given frame rate, or with per-frame durations. Animations may loop, and can be paused,
+//!   resumed, or stopped from another thread with an `AnimationHandle`.
+//!
+//! * [`export`](./export/index.html)
+//!
+//!   Requires `feature = "export"`, which is enabled by default.
+//!
+//!   Write a `PixelFrame` as a PPM, or PNG, image, upscaled so that every LED is a block of
+//!   pixels. A `GifRecorder` writes a sequence of frames, each with its own delay, as an
+//!   animated GIF.
+//!
+//! * [`import`](./import/index.html)
+//!
+//!   Requires `feature = "import"`, which is enabled by default.
+//!
+//!   Read a PPM, BMP, or PNG image as a `PixelFrame`, with `PixelFrame::from_image_file`.
+//!   Images of any size are resampled to 8x8, optionally with a `Dither` that targets the
+//!   5-6-5 colors of the LED Matrix. Sprite sheets are split into a `Vec<PixelFrame>`.
+//!
 //! * [`Clip`](./frame/clip/struct.Clip.html)
 //!
 //!   Requires `feature = "clip"`, which is enabled by default.
@@ -69,28 +168,69 @@ extern crate font8x8;
 extern crate lazy_static;
 #[cfg(feature = "linux-framebuffer")]
 pub extern crate framebuffer;
+#[cfg(feature = "linux-framebuffer")]
+extern crate libc;
 #[cfg(feature = "serde-support")]
 extern crate serde;
 #[cfg(feature = "serde-support")]
 #[macro_use]
 extern crate serde_derive;
 
+// Frame-rate controlled animations
+#[cfg(feature = "animation")]
+pub mod animation;
 // RGB color with RGB565 support
 pub mod color;
+// Displays for rendering frames
+pub mod display;
+// PNG, and zlib, checksums
+#[cfg(any(feature = "export", feature = "import"))]
+mod checksum;
+// Screen errors
+pub mod error;
+// PPM, PNG, and GIF export
+#[cfg(feature = "export")]
+pub mod export;
 // Screen frames
 pub mod frame;
 // 8x8 fonts
 #[cfg(feature = "fonts")]
 pub mod fonts;
+// PPM, BMP, and PNG import
+#[cfg(feature = "import")]
+pub mod import;
 #[cfg(feature = "linux-framebuffer")]
 #[path = "framebuffer.rs"]
 pub mod screen;
+// Session recording, and replay
+#[cfg(feature = "recording")]
+pub mod recording;
 // Scrolls for collections of PixelFrames
 #[cfg(feature = "scroll")]
 pub mod scroll;
+// Displays shared between threads
+#[cfg(feature = "shared")]
+pub mod shared;
+// Terminal previews with ANSI colors
+#[cfg(feature = "terminal")]
+pub mod terminal;
 
 // Re-exports
-pub use self::color::{BackgroundColor, PixelColor, StrokeColor};
+#[cfg(feature = "animation")]
+pub use self::animation::{AnimationHandle, Animator, FrameTiming};
+
+pub use self::color::blend::{BlendMode, RgbaColor};
+pub use self::color::format::PixelFormat;
+pub use self::color::gradient::{ColorSpace, Gradient};
+pub use self::color::palette::Palette;
+pub use self::color::{BackgroundColor, ParseColorError, PixelColor, StrokeColor};
+
+pub use self::display::{Display, MemoryScreen};
+
+pub use self::error::ScreenError;
+
+#[cfg(feature = "export")]
+pub use self::export::GifRecorder;
 
 #[cfg(feature = "fonts")]
 pub use self::fonts::{
@@ -100,16 +240,34 @@ pub use self::fonts::{
 #[cfg(feature = "clip")]
 pub use self::frame::clip::Clip;
 
+#[cfg(feature = "import")]
+pub use self::import::{Dither, Image};
+
 #[cfg(any(feature = "offset", feature = "clip"))]
 pub use self::frame::Offset;
 
 #[cfg(feature = "rotate")]
 pub use self::frame::rotate::Rotate;
 
+pub use self::frame::gradient::GradientDirection;
+pub use self::frame::indexed::IndexedFrame;
 pub use self::frame::{FrameLine, PixelFrame};
 
+#[cfg(feature = "emulator")]
+pub use self::screen::emulator::EmulatedFramebuffer;
 #[cfg(feature = "linux-framebuffer")]
-pub use self::screen::Screen;
+pub use self::screen::scaled::{Geometry, LedShape, ScaledScreen};
+#[cfg(feature = "linux-framebuffer")]
+pub use self::screen::{DropAction, GammaPreset, Screen};
+
+#[cfg(feature = "recording")]
+pub use self::recording::{Recording, RecordingFormat, RecordingScreen};
 
 #[cfg(feature = "scroll")]
 pub use self::scroll::Scroll;
+
+#[cfg(feature = "shared")]
+pub use self::shared::{Priority, SharedScreen};
+
+#[cfg(feature = "terminal")]
+pub use self::terminal::{ColorMode, TerminalScreen};
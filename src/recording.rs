@@ -0,0 +1,524 @@
+//! Record the frames written to a display, and replay them later.
+//!
+//! A `RecordingScreen` wraps any `Display`, such as `Screen`, forwarding every frame to it,
+//! and appending the frame to a session log, with the time elapsed since the recording
+//! started. The log is flushed after every frame, so it survives a crash of the process.
+//!
+//! Logs are either compact binary files, or JSON lines that are easy to inspect, and to edit.
+//! Both store every pixel as its 16-bit `Rgb565` value, so frames are replayed exactly.
+//!
+//! ```no_run
+//! # extern crate sensehat_screen;
+//! # use sensehat_screen::recording::replay;
+//! # use sensehat_screen::{Display, MemoryScreen, PixelFrame, RecordingFormat, RecordingScreen};
+//! # fn main() {
+//!     // any `Display`, such as a `Screen` on the Sense HAT
+//!     let screen = MemoryScreen::new();
+//!     let mut recorder = RecordingScreen::create(screen, "session.log", RecordingFormat::Binary)
+//!         .unwrap();
+//!     recorder.write_pixel_frame(&PixelFrame::RED).unwrap();
+//!     recorder.write_pixel_frame(&PixelFrame::BLUE).unwrap();
+//!     let (mut screen, _) = recorder.into_inner().unwrap();
+//!
+//!     // play the session back, twice as fast.
+//!     replay("session.log", &mut screen, 2.0).unwrap();
+//! # }
+//! ```
+use super::animation::{Clock, SystemClock};
+use super::color::format::PixelFormat;
+use super::display::Display;
+use super::error::{Result, ScreenError};
+use super::FrameLine;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Cursor, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+// First bytes of a binary recording.
+const MAGIC: [u8; 8] = *b"SHSCREC1";
+// Bytes of a binary record: a timestamp, and 64 pixels.
+const RECORD_LEN: usize = 8 + 64 * 2;
+
+/// The file format of a recording.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum RecordingFormat {
+    /// A header, followed by a record of 136 bytes for each frame: the timestamp in
+    /// microseconds, as a little-endian `u64`, and the 64 pixels, as little-endian `Rgb565`
+    /// values. This is the default.
+    #[default]
+    Binary,
+    /// One JSON object for each frame, on its own line, such as
+    /// `{"time_us":125000,"pixels":[63488,...]}`.
+    JsonLines,
+}
+
+/// A `Display` that forwards every frame to another display, and records it, with a
+/// timestamp, in a session log.
+#[derive(Debug)]
+pub struct RecordingScreen<D: Display, W: Write = BufWriter<File>, C: Clock = SystemClock> {
+    display: D,
+    writer: W,
+    format: RecordingFormat,
+    clock: C,
+    start: Duration,
+}
+
+impl<D: Display> RecordingScreen<D> {
+    /// Create a new `RecordingScreen` for the display, that records frames to a new file at
+    /// `path`, truncating any existing one.
+    pub fn create<P: AsRef<Path>>(display: D, path: P, format: RecordingFormat) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::create(path).map_err(|source| {
+                                              ScreenError::Open { path: path.to_path_buf(),
+                                                                  source, }
+                                          })?;
+        RecordingScreen::with_writer(display, BufWriter::new(file), format, SystemClock::new())
+    }
+}
+
+impl<D: Display, W: Write, C: Clock> RecordingScreen<D, W, C> {
+    /// Create a new `RecordingScreen` for the display, that records frames to the writer, with
+    /// timestamps measured by the clock. Binary recordings start by writing their header.
+    pub fn with_writer(display: D, mut writer: W, format: RecordingFormat, clock: C)
+                       -> Result<Self> {
+        if format == RecordingFormat::Binary {
+            writer.write_all(&MAGIC)?;
+            writer.flush()?;
+        }
+        let start = clock.now();
+        Ok(RecordingScreen { display,
+                             writer,
+                             format,
+                             clock,
+                             start, })
+    }
+
+    /// Returns the format of the recording.
+    pub fn format(&self) -> RecordingFormat {
+        self.format
+    }
+
+    /// Returns a reference to the wrapped display.
+    pub fn get_ref(&self) -> &D {
+        &self.display
+    }
+
+    /// Returns a mutable reference to the wrapped display. Frames written directly to it are
+    /// not recorded.
+    pub fn get_mut(&mut self) -> &mut D {
+        &mut self.display
+    }
+
+    /// Consume the `RecordingScreen`, and return the wrapped display, and the writer, after
+    /// flushing it.
+    pub fn into_inner(mut self) -> Result<(D, W)> {
+        self.writer.flush()?;
+        Ok((self.display, self.writer))
+    }
+
+    // Append a frame to the recording, with the time elapsed since it started.
+    fn record(&mut self, frame: &FrameLine) -> Result<()> {
+        let time = self.clock.now().saturating_sub(self.start);
+        let time_us = time.as_micros().min(u128::from(u64::MAX)) as u64;
+        let pixels = frame_pixels(frame);
+        match self.format {
+            RecordingFormat::Binary => {
+                let mut record = Vec::with_capacity(RECORD_LEN);
+                record.extend_from_slice(&time_us.to_le_bytes());
+                for pixel in pixels.iter() {
+                    record.extend_from_slice(&pixel.to_le_bytes());
+                }
+                self.writer.write_all(&record)?;
+            }
+            RecordingFormat::JsonLines => {
+                let pixels = pixels.iter()
+                                   .map(|pixel| pixel.to_string())
+                                   .collect::<Vec<String>>()
+                                   .join(",");
+                writeln!(self.writer, "{{\"time_us\":{},\"pixels\":[{}]}}", time_us, pixels)?;
+            }
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<D: Display, W: Write, C: Clock> Display for RecordingScreen<D, W, C> {
+    /// Write the frame to the wrapped display, and record it once it was written.
+    fn write_frame(&mut self, frame: &FrameLine) -> Result<()> {
+        self.display.write_frame(frame)?;
+        self.record(frame)
+    }
+
+    fn read_frame(&self) -> Result<Option<FrameLine>> {
+        self.display.read_frame()
+    }
+}
+
+/// The frames of a recording, with the time at which each one was written.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    frames: Vec<(Duration, FrameLine)>,
+}
+
+impl Recording {
+    /// Open, and read, a recording file of either format.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|source| {
+                                             ScreenError::Open { path: path.to_path_buf(),
+                                                                 source, }
+                                         })?;
+        Recording::read(BufReader::new(file))
+    }
+
+    /// Read a recording of either format, detected from its first bytes.
+    pub fn read<R: BufRead>(mut reader: R) -> Result<Self> {
+        // the first bytes may take more than one fill of the buffer.
+        let mut start = Vec::with_capacity(MAGIC.len());
+        while start.len() < MAGIC.len() {
+            let buffer = reader.fill_buf()?;
+            let len = buffer.len().min(MAGIC.len() - start.len());
+            if len == 0 {
+                break;
+            }
+            start.extend_from_slice(&buffer[..len]);
+            reader.consume(len);
+        }
+        let binary = start == MAGIC;
+        let reader = Cursor::new(start).chain(reader);
+        let frames = if binary {
+            read_binary(reader)?
+        } else {
+            read_json_lines(reader)?
+        };
+        Ok(Recording { frames })
+    }
+
+    /// Returns the recorded frames, with the time elapsed between the start of the recording
+    /// and each frame.
+    pub fn frames(&self) -> &[(Duration, FrameLine)] {
+        &self.frames
+    }
+
+    /// Play the recording on the display, at `speed` times the original speed. Blocks until
+    /// every frame has been written, and returns the number of frames written.
+    ///
+    /// # Panics
+    /// If `speed` is not a positive number.
+    pub fn replay<D: Display + ?Sized>(&self, display: &mut D, speed: f32) -> Result<usize> {
+        self.replay_with_clock(display, speed, &SystemClock::new())
+    }
+
+    /// Play the recording on the display, like `replay`, waiting on the given clock.
+    ///
+    /// # Panics
+    /// If `speed` is not a positive number.
+    pub fn replay_with_clock<D: Display + ?Sized, C: Clock>(&self,
+                                                            display: &mut D,
+                                                            speed: f32,
+                                                            clock: &C)
+                                                            -> Result<usize> {
+        assert!(speed > 0.0 && speed.is_finite());
+        let start = clock.now();
+        for &(time, ref frame) in &self.frames {
+            let scaled = (time.as_nanos() as f64 / f64::from(speed)).round() as u64;
+            let deadline = start + Duration::from_nanos(scaled);
+            let now = clock.now();
+            if deadline > now {
+                clock.sleep(deadline - now);
+            }
+            display.write_frame(frame)?;
+        }
+        Ok(self.frames.len())
+    }
+}
+
+/// Open the recording at `path`, and play it on the display, at `speed` times the original
+/// speed. Returns the number of frames written.
+///
+/// # Panics
+/// If `speed` is not a positive number.
+pub fn replay<P: AsRef<Path>, D: Display + ?Sized>(path: P,
+                                                   display: &mut D,
+                                                   speed: f32)
+                                                   -> Result<usize> {
+    Recording::open(path)?.replay(display, speed)
+}
+
+// The error of a recording that could not be read.
+fn invalid(reason: &str) -> ScreenError {
+    ScreenError::InvalidRecording { reason: reason.to_string() }
+}
+
+// The `Rgb565` value of every pixel of a frame.
+fn frame_pixels(frame: &FrameLine) -> [u16; 64] {
+    let bytes = frame.encode(PixelFormat::Rgb565);
+    let mut pixels = [0u16; 64];
+    for (pixel, bytes) in pixels.iter_mut().zip(bytes.chunks(2)) {
+        *pixel = u16::from_ne_bytes([bytes[0], bytes[1]]);
+    }
+    pixels
+}
+
+// The frame with the given `Rgb565` value for every pixel.
+fn pixels_frame(pixels: &[u16]) -> FrameLine {
+    let bytes = pixels.iter()
+                      .flat_map(|pixel| pixel.to_ne_bytes().to_vec())
+                      .collect::<Vec<u8>>();
+    FrameLine::decode(PixelFormat::Rgb565, &bytes)
+}
+
+fn read_binary<R: Read>(mut reader: R) -> Result<Vec<(Duration, FrameLine)>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if !bytes.starts_with(&MAGIC) {
+        return Err(invalid("invalid binary recording header"));
+    }
+    let records = &bytes[MAGIC.len()..];
+    if records.len() % RECORD_LEN != 0 {
+        return Err(invalid("binary recording ended unexpectedly"));
+    }
+    let mut frames = Vec::with_capacity(records.len() / RECORD_LEN);
+    for record in records.chunks(RECORD_LEN) {
+        let mut time = [0u8; 8];
+        time.copy_from_slice(&record[..8]);
+        let pixels = record[8..].chunks(2)
+                                .map(|pixel| u16::from_le_bytes([pixel[0], pixel[1]]))
+                                .collect::<Vec<u16>>();
+        frames.push((Duration::from_micros(u64::from_le_bytes(time)), pixels_frame(&pixels)));
+    }
+    Ok(frames)
+}
+
+fn read_json_lines<R: BufRead>(reader: R) -> Result<Vec<(Duration, FrameLine)>> {
+    let mut frames = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        frames.push(parse_json_line(&line)?);
+    }
+    Ok(frames)
+}
+
+// Parse a line such as `{"time_us":125000,"pixels":[63488,...]}`, with any whitespace.
+fn parse_json_line(line: &str) -> Result<(Duration, FrameLine)> {
+    let line = line.split_whitespace().collect::<String>();
+    let body = line.strip_prefix('{')
+                   .and_then(|line| line.strip_suffix('}'))
+                   .ok_or_else(|| invalid("recording line is not a JSON object"))?;
+    let (mut time, mut pixels) = (None, None);
+    let mut rest = body;
+    while !rest.is_empty() {
+        let (key, value, remaining) = json_field(rest)?;
+        match key {
+            "time_us" => {
+                time = Some(value.parse::<u64>()
+                                 .map_err(|_| invalid("invalid recording timestamp"))?)
+            }
+            "pixels" => {
+                let list = value.strip_prefix('[')
+                                .and_then(|value| value.strip_suffix(']'))
+                                .ok_or_else(|| invalid("recording pixels are not a JSON array"))?;
+                let list = list.split(',')
+                               .map(|pixel| pixel.parse::<u16>())
+                               .collect::<::std::result::Result<Vec<u16>, _>>()
+                               .map_err(|_| invalid("invalid recording pixel"))?;
+                pixels = Some(list);
+            }
+            _ => {}
+        }
+        rest = remaining;
+    }
+    match (time, pixels) {
+        (Some(time), Some(ref pixels)) if pixels.len() == 64 => {
+            Ok((Duration::from_micros(time), pixels_frame(pixels)))
+        }
+        (Some(_), Some(_)) => Err(invalid("recording frames must have 64 pixels")),
+        _ => Err(invalid("recording line is missing \"time_us\", or \"pixels\"")),
+    }
+}
+
+// Split the first `"key":value` field off the body of a JSON object, without whitespace.
+fn json_field(body: &str) -> Result<(&str, &str, &str)> {
+    let malformed = || invalid("malformed JSON in recording");
+    let body = body.strip_prefix('"').ok_or_else(malformed)?;
+    let key_end = body.find('"').ok_or_else(malformed)?;
+    let (key, body) = (&body[..key_end], &body[key_end + 1..]);
+    let body = body.strip_prefix(':').ok_or_else(malformed)?;
+    let value_end = if body.starts_with('[') {
+        body.find(']').ok_or_else(malformed)? + 1
+    } else {
+        body.find(',').unwrap_or(body.len())
+    };
+    let (value, rest) = body.split_at(value_end);
+    let rest = match rest.strip_prefix(',') {
+        Some(rest) if !rest.is_empty() => rest,
+        Some(_) => return Err(malformed()),
+        None => rest,
+    };
+    Ok((key, value, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use animation::ManualClock;
+    use display::MemoryScreen;
+    use std::env;
+    use std::fs;
+    use std::process;
+    use std::sync::{Arc, Mutex};
+    use {PixelColor, PixelFrame};
+
+    fn session(format: RecordingFormat) -> Vec<u8> {
+        let clock = ManualClock::new();
+        clock.advance(Duration::from_secs(7));
+        let mut recorder =
+            RecordingScreen::with_writer(MemoryScreen::new(), Vec::new(), format, clock.clone())
+                .unwrap();
+        let mut frame = PixelFrame::BLUE;
+        frame[5] = PixelColor::new(0x12, 0x34, 0x56);
+        recorder.write_pixel_frame(&frame).unwrap();
+        clock.advance(Duration::from_millis(125));
+        recorder.write_pixel_frame(&PixelFrame::RED).unwrap();
+        clock.advance(Duration::from_micros(1_500_250));
+        recorder.clear().unwrap();
+        let (display, log) = recorder.into_inner().unwrap();
+        assert_eq!(display.frames().len(), 3);
+        log
+    }
+
+    fn expected_frames() -> Vec<(Duration, FrameLine)> {
+        let mut frame = PixelFrame::BLUE;
+        frame[5] = PixelColor::new(0x12, 0x34, 0x56);
+        vec![(Duration::from_secs(0), frame.frame_line()),
+             (Duration::from_millis(125), PixelFrame::RED.frame_line()),
+             (Duration::from_micros(1_625_250), PixelFrame::BLACK.frame_line())]
+    }
+
+    #[test]
+    fn binary_recording_is_read_back() {
+        let log = session(RecordingFormat::Binary);
+        assert_eq!(&log[..8], b"SHSCREC1");
+        assert_eq!(log.len(), 8 + 3 * RECORD_LEN);
+        // the red frame, 125 ms in, with pixels as little-endian `Rgb565`.
+        let record = &log[8 + RECORD_LEN..8 + 2 * RECORD_LEN];
+        assert_eq!(&record[..8], &125_000u64.to_le_bytes());
+        assert_eq!(&record[8..10], &[0x00, 0xF8]);
+        let recording = Recording::read(&log[..]).unwrap();
+        assert_eq!(recording.frames(), &expected_frames()[..]);
+    }
+
+    #[test]
+    fn binary_recording_is_detected_across_buffer_fills() {
+        let log = session(RecordingFormat::Binary);
+        let recording = Recording::read(BufReader::with_capacity(3, &log[..])).unwrap();
+        assert_eq!(recording.frames(), &expected_frames()[..]);
+    }
+
+    #[test]
+    fn json_lines_recording_is_read_back() {
+        let log = session(RecordingFormat::JsonLines);
+        let text = String::from_utf8(log.clone()).unwrap();
+        let lines = text.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], format!("{{\"time_us\":125000,\"pixels\":[{}]}}",
+                                     vec!["63488"; 64].join(",")));
+        let recording = Recording::read(&log[..]).unwrap();
+        assert_eq!(recording.frames(), &expected_frames()[..]);
+    }
+
+    #[test]
+    fn json_lines_are_read_with_any_whitespace_and_field_order() {
+        let pixels = vec!["31"; 64].join(", ");
+        let text = format!("\n{{ \"pixels\": [ {} ], \"note\": 1, \"time_us\": 42 }}\n",
+                           pixels);
+        let recording = Recording::read(text.as_bytes()).unwrap();
+        assert_eq!(recording.frames(),
+                   &[(Duration::from_micros(42), PixelFrame::BLUE.frame_line())]);
+    }
+
+    #[test]
+    fn invalid_recordings_are_rejected() {
+        let mut log = session(RecordingFormat::Binary);
+        log.pop();
+        assert!(Recording::read(&log[..]).is_err());
+        assert!(Recording::read(&b"SHSCREC2"[..]).is_err());
+        let short = format!("{{\"time_us\":1,\"pixels\":[{}]}}", vec!["0"; 63].join(","));
+        assert!(Recording::read(short.as_bytes()).is_err());
+        assert!(Recording::read(&b"{\"time_us\":1}"[..]).is_err());
+        assert!(Recording::read(&b"{\"time_us\":-1,\"pixels\":[]}"[..]).is_err());
+        match Recording::read(&b"SHS"[..]) {
+            Err(ScreenError::InvalidRecording { ref reason }) => {
+                assert_eq!(reason, "recording line is not a JSON object")
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        match Recording::read(&b"not json"[..]) {
+            Err(ScreenError::InvalidRecording { ref reason }) => {
+                assert_eq!(reason, "recording line is not a JSON object")
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    // A clock that records every sleep.
+    #[derive(Clone, Default)]
+    struct SleepLog {
+        clock: ManualClock,
+        sleeps: Arc<Mutex<Vec<Duration>>>,
+    }
+
+    impl Clock for SleepLog {
+        fn now(&self) -> Duration {
+            self.clock.now()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.sleeps.lock().unwrap().push(duration);
+            self.clock.sleep(duration);
+        }
+    }
+
+    #[test]
+    fn recording_is_replayed_at_scaled_speed() {
+        let recording = Recording::read(&session(RecordingFormat::Binary)[..]).unwrap();
+        let clock = SleepLog::default();
+        let mut display = MemoryScreen::new();
+        assert_eq!(recording.replay_with_clock(&mut display, 2.0, &clock).unwrap(), 3);
+        let frames = expected_frames().into_iter()
+                                      .map(|(_, frame)| frame)
+                                      .collect::<Vec<FrameLine>>();
+        assert_eq!(display.frames(), &frames[..]);
+        assert_eq!(*clock.sleeps.lock().unwrap(),
+                   vec![Duration::from_micros(62_500), Duration::from_micros(750_125)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn recording_is_not_replayed_at_zero_speed() {
+        let recording = Recording::default();
+        let _ = recording.replay_with_clock(&mut MemoryScreen::new(), 0.0, &ManualClock::new());
+    }
+
+    #[test]
+    fn recording_file_is_replayed() {
+        let path = env::temp_dir().join(format!("sensehat-screen-recording-{}", process::id()));
+        let mut recorder =
+            RecordingScreen::create(MemoryScreen::new(), &path, RecordingFormat::JsonLines)
+                .unwrap();
+        assert_eq!(recorder.format(), RecordingFormat::JsonLines);
+        recorder.write_pixel_frame(&PixelFrame::GREEN).unwrap();
+        assert_eq!(recorder.get_ref().frames().len(), 1);
+        drop(recorder);
+
+        let mut display = MemoryScreen::new();
+        assert_eq!(replay(&path, &mut display, 1.0).unwrap(), 1);
+        assert_eq!(display.frames(), &[PixelFrame::GREEN.frame_line()]);
+        fs::remove_file(&path).unwrap();
+        assert!(replay(&path, &mut display, 1.0).is_err());
+    }
+}
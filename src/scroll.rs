@@ -104,7 +104,7 @@ impl Scroll {
     }
 
     /// Return the number of pixel frames in the scroll.
-    #[cfg_attr(feature = "cargo-clippy", allow(len_without_is_empty))]
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         self.0.len()
     }
@@ -138,7 +138,7 @@ impl Index<usize> for Scroll {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "fonts"))]
 mod tests {
     use super::super::{fonts::FontCollection, PixelColor};
     use super::*;
@@ -283,7 +283,7 @@ mod tests {
         let scroll = Scroll::new(&font_pixel_frames("bás", PixelColor::YELLOW, PixelColor::BLACK));
 
         let mut seq = scroll.left_to_right();
-        let first_frame = seq.nth(0).unwrap();
+        let first_frame = seq.next().unwrap();
         assert_eq!(first_frame, scroll[0]);
 
         let mut seq = scroll.left_to_right();
@@ -370,7 +370,7 @@ mod tests {
         let scroll = Scroll::new(&font_pixel_frames("áàä", PixelColor::WHITE, PixelColor::BLUE));
 
         let mut seq = scroll.right_to_left();
-        let first_frame = seq.nth(0).unwrap();
+        let first_frame = seq.next().unwrap();
         assert_eq!(first_frame, scroll[0]);
 
         let mut seq = scroll.right_to_left();
@@ -457,7 +457,7 @@ mod tests {
         let scroll = Scroll::new(&font_pixel_frames("bás", PixelColor::YELLOW, PixelColor::BLACK));
 
         let mut seq = scroll.top_to_bottom();
-        let first_frame = seq.nth(0).unwrap();
+        let first_frame = seq.next().unwrap();
         assert_eq!(first_frame, scroll[0]);
 
         let mut seq = scroll.top_to_bottom();
@@ -544,7 +544,7 @@ mod tests {
         let scroll = Scroll::new(&font_pixel_frames("áàä", PixelColor::WHITE, PixelColor::BLUE));
 
         let mut seq = scroll.bottom_to_top();
-        let first_frame = seq.nth(0).unwrap();
+        let first_frame = seq.next().unwrap();
         assert_eq!(first_frame, scroll[0]);
 
         let mut seq = scroll.bottom_to_top();
@@ -0,0 +1,420 @@
+//! A thread-safe handle to share one display between many producers.
+//!
+//! Every producer writes its frames at a `Priority`. The display shows the frame of the highest
+//! priority that is still active, so an alert can pre-empt a background clock, and the clock
+//! comes back on its own once the alert expires, or is cleared.
+//!
+//! ```no_run
+//! # extern crate sensehat_screen;
+//! # use sensehat_screen::{MemoryScreen, PixelFrame, Priority, SharedScreen};
+//! # use std::time::Duration;
+//! # fn main() {
+//!     // any `Display`, such as a `Screen` on the Sense HAT
+//!     let screen = SharedScreen::new(MemoryScreen::new());
+//!
+//!     let clock = screen.clone();
+//!     ::std::thread::spawn(move || loop {
+//!         clock.write_frame_at(Priority::BACKGROUND, &PixelFrame::BLUE.frame_line()).unwrap();
+//!         ::std::thread::sleep(Duration::from_secs(1));
+//!     });
+//!
+//!     // shows red for two seconds, then the clock is shown again.
+//!     screen.write_frame_for(Priority::ALERT,
+//!                            &PixelFrame::RED.frame_line(),
+//!                            Duration::from_secs(2))
+//!           .unwrap();
+//! # }
+//! ```
+use super::display::Display;
+use super::error::Result;
+use super::{FrameLine, PixelFrame};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// The priority of the frames written to a `SharedScreen`. Higher priorities pre-empt lower
+/// ones.
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Priority(pub u8);
+
+impl Priority {
+    /// The lowest priority, for content that is shown when nothing else is, such as a clock.
+    pub const BACKGROUND: Priority = Priority(0);
+    /// The priority used by the `Display` implementation of `SharedScreen`, unless set with
+    /// `SharedScreen::with_priority`.
+    pub const NORMAL: Priority = Priority(128);
+    /// The highest priority, for content that pre-empts everything else.
+    pub const ALERT: Priority = Priority(255);
+}
+
+impl Default for Priority {
+    fn default() -> Self {
+        Priority::NORMAL
+    }
+}
+
+// A frame written at some priority, and when it expires, if ever.
+#[derive(Debug)]
+struct Layer {
+    frame: FrameLine,
+    expires: Option<Instant>,
+}
+
+// Everything behind the lock of a `SharedScreen`.
+struct State<D> {
+    display: D,
+    layers: BTreeMap<Priority, Layer>,
+    shown: Option<FrameLine>,
+    handles: usize,
+}
+
+impl<D: Display> State<D> {
+    // Remove the layers that expire at, or before, `now`. Returns `true` if any was removed.
+    fn expire(&mut self, now: Instant) -> bool {
+        let before = self.layers.len();
+        self.layers
+            .retain(|_, layer| layer.expires.map_or(true, |expires| expires > now));
+        self.layers.len() != before
+    }
+
+    // The earliest time at which a layer expires, if any.
+    fn next_expiry(&self) -> Option<Instant> {
+        self.layers.values().filter_map(|layer| layer.expires).min()
+    }
+
+    // Show the frame of the highest priority layer, or a blank frame if there are no layers.
+    // Nothing is written if that frame is already shown.
+    fn render(&mut self) -> Result<()> {
+        let frame = self.layers
+                        .values()
+                        .next_back()
+                        .map_or_else(|| PixelFrame::BLACK.frame_line(), |layer| layer.frame);
+        if self.shown != Some(frame) {
+            self.display.write_frame(&frame)?;
+            self.shown = Some(frame);
+        }
+        Ok(())
+    }
+}
+
+struct Shared<D> {
+    state: Mutex<State<D>>,
+    changed: Condvar,
+    expiring: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl<D> Shared<D> {
+    // A poisoned lock only means that a producer panicked; the state is still consistent.
+    fn lock(&self) -> MutexGuard<'_, State<D>> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// A cloneable, thread-safe handle to a `Display`, that shows the frame with the highest
+/// `Priority` written by any of its clones.
+///
+/// A background thread returns to the lower priority frames when the higher priority ones
+/// expire. Dropping the last handle stops it, and waits for it, so that the display is
+/// dropped before the handle is, as when a `Screen` clears the LED Matrix. Errors from writes
+/// made by the background thread are ignored.
+pub struct SharedScreen<D: Display + Send + 'static> {
+    shared: Arc<Shared<D>>,
+    priority: Priority,
+}
+
+impl<D: Display + Send + 'static> SharedScreen<D> {
+    /// Create a new `SharedScreen` that owns the given display. Nothing is written until the
+    /// first frame arrives.
+    pub fn new(display: D) -> Self {
+        let state = State { display,
+                            layers: BTreeMap::new(),
+                            shown: None,
+                            handles: 1, };
+        let shared = Arc::new(Shared { state: Mutex::new(state),
+                                       changed: Condvar::new(),
+                                       expiring: Mutex::new(None), });
+        let expiring = Arc::clone(&shared);
+        let thread = thread::spawn(move || expire_layers(&expiring));
+        *shared.expiring.lock().unwrap_or_else(|err| err.into_inner()) = Some(thread);
+        SharedScreen { shared,
+                       priority: Priority::default(), }
+    }
+
+    /// Returns a new handle to the same display, whose `Display` implementation writes at the
+    /// given priority.
+    pub fn with_priority(&self, priority: Priority) -> Self {
+        let mut handle = self.clone();
+        handle.priority = priority;
+        handle
+    }
+
+    /// Returns the priority used by the `Display` implementation of this handle.
+    pub fn priority(&self) -> Priority {
+        self.priority
+    }
+
+    /// Write a frame at the given priority, until it is replaced, or cleared. It is shown
+    /// right away, unless a frame with a higher priority is active.
+    pub fn write_frame_at(&self, priority: Priority, frame: &FrameLine) -> Result<()> {
+        self.set_layer(priority, Layer { frame: *frame,
+                                         expires: None, })
+    }
+
+    /// Write a frame at the given priority, that expires after `duration`. Once it expires,
+    /// the frame with the next highest priority is shown.
+    pub fn write_frame_for(&self,
+                           priority: Priority,
+                           frame: &FrameLine,
+                           duration: Duration)
+                           -> Result<()> {
+        self.set_layer(priority,
+                       Layer { frame: *frame,
+                               expires: Some(Instant::now() + duration), })
+    }
+
+    /// Remove the frame written at the given priority, showing the frame with the next highest
+    /// priority, or turning off every LED when there is none.
+    pub fn clear_priority(&self, priority: Priority) -> Result<()> {
+        let mut state = self.shared.lock();
+        if state.layers.remove(&priority).is_none() {
+            return Ok(());
+        }
+        self.shared.changed.notify_one();
+        state.render()
+    }
+
+    /// Returns the priority of the frame that is currently shown, if any.
+    pub fn current_priority(&self) -> Option<Priority> {
+        let now = Instant::now();
+        let state = self.shared.lock();
+        state.layers
+             .iter()
+             .rev()
+             .find(|&(_, layer)| layer.expires.map_or(true, |expires| expires > now))
+             .map(|(&priority, _)| priority)
+    }
+
+    /// Run a closure with exclusive access to the display, for operations that are not part of
+    /// the `Display` trait, such as setting the gamma table of a `Screen`.
+    pub fn with_display<F, T>(&self, f: F) -> T
+        where F: FnOnce(&mut D) -> T
+    {
+        f(&mut self.shared.lock().display)
+    }
+
+    fn set_layer(&self, priority: Priority, layer: Layer) -> Result<()> {
+        let mut state = self.shared.lock();
+        state.expire(Instant::now());
+        if layer.expires.is_some() {
+            self.shared.changed.notify_one();
+        }
+        state.layers.insert(priority, layer);
+        state.render()
+    }
+}
+
+impl<D: Display + Send + 'static> Clone for SharedScreen<D> {
+    fn clone(&self) -> Self {
+        self.shared.lock().handles += 1;
+        SharedScreen { shared: Arc::clone(&self.shared),
+                       priority: self.priority, }
+    }
+}
+
+impl<D: Display + Send + 'static> Drop for SharedScreen<D> {
+    fn drop(&mut self) {
+        let last = {
+            let mut state = self.shared.lock();
+            state.handles -= 1;
+            state.handles == 0
+        };
+        self.shared.changed.notify_one();
+        // once the background thread ends, this handle holds the last reference to the
+        // display, which is dropped with it.
+        if last {
+            let thread = self.shared
+                             .expiring
+                             .lock()
+                             .unwrap_or_else(|err| err.into_inner())
+                             .take();
+            if let Some(thread) = thread {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+impl<D: Display + Send + 'static> Display for SharedScreen<D> {
+    /// Write a frame at the priority of this handle.
+    fn write_frame(&mut self, frame: &FrameLine) -> Result<()> {
+        self.write_frame_at(self.priority, frame)
+    }
+
+    /// Remove the frame written at the priority of this handle, as `clear_priority` does, so
+    /// that frames of lower priorities are shown again.
+    fn clear(&mut self) -> Result<()> {
+        self.clear_priority(self.priority)
+    }
+
+    /// Returns the frame that is currently shown, which may have been written at any priority.
+    fn read_frame(&self) -> Result<Option<FrameLine>> {
+        Ok(self.shared.lock().shown)
+    }
+}
+
+// Runs on the background thread, until the last handle is dropped.
+fn expire_layers<D: Display>(shared: &Shared<D>) {
+    let mut state = shared.lock();
+    while state.handles > 0 {
+        let now = Instant::now();
+        if state.expire(now) {
+            let _ = state.render();
+        }
+        state = match state.next_expiry() {
+            Some(expires) => {
+                let timeout = expires.saturating_duration_since(now);
+                shared.changed
+                      .wait_timeout(state, timeout)
+                      .unwrap_or_else(|err| err.into_inner())
+                      .0
+            }
+            None => shared.changed.wait(state).unwrap_or_else(|err| err.into_inner()),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use display::MemoryScreen;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    // Shares a `MemoryScreen`, and returns the frames written to it so far.
+    fn written(screen: &SharedScreen<MemoryScreen>) -> Vec<FrameLine> {
+        screen.with_display(|display| display.frames().to_vec())
+    }
+
+    // Wait for the background thread, for at most one second.
+    fn wait_for<F: Fn() -> bool>(done: F) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(1) {
+            if done() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        done()
+    }
+
+    #[test]
+    fn priority_orders_background_normal_and_alert() {
+        assert!(Priority::BACKGROUND < Priority::NORMAL);
+        assert!(Priority::NORMAL < Priority::ALERT);
+        assert_eq!(Priority::default(), Priority::NORMAL);
+    }
+
+    #[test]
+    fn shared_screen_shows_the_highest_priority_frame() {
+        let screen = SharedScreen::new(MemoryScreen::new());
+        screen.write_frame_at(Priority::BACKGROUND, &PixelFrame::BLUE.frame_line())
+              .unwrap();
+        screen.write_frame_at(Priority::ALERT, &PixelFrame::RED.frame_line())
+              .unwrap();
+        screen.write_frame_at(Priority::BACKGROUND, &PixelFrame::GREEN.frame_line())
+              .unwrap();
+        assert_eq!(written(&screen),
+                   vec![PixelFrame::BLUE.frame_line(), PixelFrame::RED.frame_line()]);
+        assert_eq!(screen.current_priority(), Some(Priority::ALERT));
+    }
+
+    #[test]
+    fn shared_screen_returns_to_lower_priority_when_cleared() {
+        let screen = SharedScreen::new(MemoryScreen::new());
+        screen.write_frame_at(Priority::BACKGROUND, &PixelFrame::BLUE.frame_line())
+              .unwrap();
+        screen.write_frame_at(Priority::ALERT, &PixelFrame::RED.frame_line())
+              .unwrap();
+        screen.clear_priority(Priority::ALERT).unwrap();
+        assert_eq!(screen.read_frame().unwrap(), Some(PixelFrame::BLUE.frame_line()));
+        screen.clear_priority(Priority::BACKGROUND).unwrap();
+        assert_eq!(screen.read_frame().unwrap(), Some(PixelFrame::BLACK.frame_line()));
+        assert_eq!(screen.current_priority(), None);
+    }
+
+    #[test]
+    fn shared_screen_handle_clears_its_own_priority() {
+        let screen = SharedScreen::new(MemoryScreen::new());
+        screen.write_frame_at(Priority::BACKGROUND, &PixelFrame::BLUE.frame_line())
+              .unwrap();
+        let mut handle = screen.with_priority(Priority::ALERT);
+        handle.write_pixel_frame(&PixelFrame::RED).unwrap();
+        handle.clear().unwrap();
+        assert_eq!(screen.read_frame().unwrap(), Some(PixelFrame::BLUE.frame_line()));
+        assert_eq!(screen.current_priority(), Some(Priority::BACKGROUND));
+    }
+
+    #[test]
+    fn shared_screen_returns_to_lower_priority_when_expired() {
+        let screen = SharedScreen::new(MemoryScreen::new());
+        screen.write_frame_at(Priority::BACKGROUND, &PixelFrame::BLUE.frame_line())
+              .unwrap();
+        screen.write_frame_for(Priority::ALERT,
+                               &PixelFrame::RED.frame_line(),
+                               Duration::from_millis(20))
+              .unwrap();
+        assert_eq!(screen.read_frame().unwrap(), Some(PixelFrame::RED.frame_line()));
+        assert!(wait_for(|| {
+                             screen.read_frame().unwrap() == Some(PixelFrame::BLUE.frame_line())
+                         }));
+        assert_eq!(written(&screen),
+                   vec![PixelFrame::BLUE.frame_line(),
+                        PixelFrame::RED.frame_line(),
+                        PixelFrame::BLUE.frame_line()]);
+    }
+
+    #[test]
+    fn shared_screen_is_written_from_many_threads() {
+        let screen = SharedScreen::new(MemoryScreen::new());
+        let producers = (0..4u8).map(|n| {
+                                    let mut handle = screen.with_priority(Priority(n));
+                                    thread::spawn(move || {
+                                        for _ in 0..10 {
+                                            handle.write_pixel_frame(&PixelFrame::YELLOW)
+                                                  .unwrap();
+                                        }
+                                    })
+                                })
+                                .collect::<Vec<_>>();
+        for producer in producers {
+            producer.join().unwrap();
+        }
+        assert_eq!(written(&screen), vec![PixelFrame::YELLOW.frame_line()]);
+        assert_eq!(screen.current_priority(), Some(Priority(3)));
+    }
+
+    #[test]
+    fn shared_screen_drops_the_display_with_the_last_handle() {
+        struct Dropped(Arc<AtomicBool>);
+
+        impl Display for Dropped {
+            fn write_frame(&mut self, _frame: &FrameLine) -> Result<()> {
+                Ok(())
+            }
+        }
+
+        impl Drop for Dropped {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let screen = SharedScreen::new(Dropped(Arc::clone(&dropped)));
+        let clone = screen.clone();
+        drop(screen);
+        assert!(!dropped.load(Ordering::SeqCst));
+        drop(clone);
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+}
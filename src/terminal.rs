@@ -0,0 +1,268 @@
+//! Preview frames in a terminal, with ANSI colors.
+//!
+//! A `TerminalScreen` draws every pixel as two colored spaces, so that an 8x8 frame looks
+//! square in most terminals. It is handy for watching animations over SSH, or on machines
+//! without a Sense HAT.
+//!
+//! ```no_run
+//! # extern crate sensehat_screen;
+//! # use sensehat_screen::{Display, PixelFrame, TerminalScreen};
+//! # fn main() {
+//!     let mut terminal = TerminalScreen::new();
+//!     terminal.write_pixel_frame(&PixelFrame::RED).unwrap();
+//!     // the next frame is drawn over the previous one.
+//!     terminal.write_pixel_frame(&PixelFrame::BLUE).unwrap();
+//! # }
+//! ```
+use super::display::Display;
+use super::error::Result;
+use super::{FrameLine, PixelColor, PixelFrame};
+use std::env;
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Write};
+
+// Intensities of the 6x6x6 color cube of 256-color terminals.
+const CUBE_LEVELS: [u8; 6] = [0x00, 0x5F, 0x87, 0xAF, 0xD7, 0xFF];
+
+/// The colors supported by a terminal.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ColorMode {
+    /// 24-bit colors, with `ESC[48;2;R;G;Bm` escapes.
+    TrueColor,
+    /// The 256-color palette, with `ESC[48;5;Nm` escapes.
+    Ansi256,
+}
+
+impl ColorMode {
+    /// Detect the colors supported by the terminal from the `COLORTERM` environment variable.
+    pub fn detect() -> Self {
+        ColorMode::from_colorterm(env::var("COLORTERM").ok().as_deref())
+    }
+
+    /// Returns `TrueColor` if the value of `COLORTERM` is `truecolor`, or `24bit`, and
+    /// `Ansi256` otherwise.
+    pub fn from_colorterm(colorterm: Option<&str>) -> Self {
+        match colorterm {
+            Some("truecolor") | Some("24bit") => ColorMode::TrueColor,
+            _ => ColorMode::Ansi256,
+        }
+    }
+}
+
+impl PixelFrame {
+    /// Render the frame as 8 lines of colored blocks, with 24-bit ANSI escapes. Useful for
+    /// logging frames to a terminal.
+    ///
+    /// ```
+    /// # extern crate sensehat_screen;
+    /// # use sensehat_screen::PixelFrame;
+    /// # fn main() {
+    ///     let ansi = PixelFrame::RED.to_ansi_string();
+    ///     assert_eq!(ansi.lines().count(), 8);
+    ///     assert!(ansi.starts_with("\x1b[48;2;255;0;0m  "));
+    /// # }
+    /// ```
+    pub fn to_ansi_string(&self) -> String {
+        ansi_string(self, ColorMode::TrueColor)
+    }
+}
+
+/// Render the frame as 8 lines of colored blocks, with the escapes of the given `ColorMode`.
+/// Every line ends by resetting the colors.
+pub fn ansi_string(frame: &PixelFrame, mode: ColorMode) -> String {
+    let mut ansi = String::new();
+    for row in frame.as_rows().iter() {
+        for &color in row.iter() {
+            match mode {
+                ColorMode::TrueColor => {
+                    write!(ansi, "\x1b[48;2;{};{};{}m  ", color.red, color.green, color.blue)
+                        .unwrap()
+                }
+                ColorMode::Ansi256 => write!(ansi, "\x1b[48;5;{}m  ", ansi256(color)).unwrap(),
+            }
+        }
+        ansi.push_str("\x1b[0m\n");
+    }
+    ansi
+}
+
+/// Returns the index of the closest color in the 256-color palette, from either the 6x6x6
+/// color cube, or the grayscale ramp.
+pub fn ansi256(color: PixelColor) -> u8 {
+    let cube = |value: u8| {
+        CUBE_LEVELS.iter()
+                   .enumerate()
+                   .min_by_key(|&(_, &level)| (i32::from(level) - i32::from(value)).abs())
+                   .map(|(index, _)| index as u8)
+                   .unwrap()
+    };
+    let (r, g, b) = (cube(color.red), cube(color.green), cube(color.blue));
+    let cube_color = PixelColor::new(CUBE_LEVELS[r as usize],
+                                     CUBE_LEVELS[g as usize],
+                                     CUBE_LEVELS[b as usize]);
+
+    // the grayscale ramp goes from 0x08 to 0xEE, in steps of 10.
+    let average = (u32::from(color.red) + u32::from(color.green) + u32::from(color.blue)) / 3;
+    let gray = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_level = 8 + 10 * gray;
+    let gray_color = PixelColor::new(gray_level, gray_level, gray_level);
+
+    if distance(color, gray_color) < distance(color, cube_color) {
+        232 + gray
+    } else {
+        16 + 36 * r + 6 * g + b
+    }
+}
+
+// Squared distance between two colors.
+fn distance(a: PixelColor, b: PixelColor) -> i32 {
+    let d = |x: u8, y: u8| i32::from(x) - i32::from(y);
+    let (dr, dg, db) = (d(a.red, b.red), d(a.green, b.green), d(a.blue, b.blue));
+    dr * dr + dg * dg + db * db
+}
+
+/// A `Display` that draws frames on a terminal, or any other writer, with ANSI escapes.
+#[derive(Debug)]
+pub struct TerminalScreen<W: Write = io::Stdout> {
+    writer: W,
+    mode: ColorMode,
+    redraw_in_place: bool,
+    drawn: bool,
+}
+
+impl TerminalScreen<io::Stdout> {
+    /// Create a new `TerminalScreen` that draws on the standard output, with the colors
+    /// detected by `ColorMode::detect`, and redraws every frame in place.
+    pub fn new() -> Self {
+        TerminalScreen::with_writer(io::stdout(), ColorMode::detect())
+    }
+}
+
+impl Default for TerminalScreen<io::Stdout> {
+    fn default() -> Self {
+        TerminalScreen::new()
+    }
+}
+
+impl<W: Write> TerminalScreen<W> {
+    /// Create a new `TerminalScreen` that draws on the given writer, with the given colors,
+    /// and redraws every frame in place.
+    pub fn with_writer(writer: W, mode: ColorMode) -> Self {
+        TerminalScreen { writer,
+                         mode,
+                         redraw_in_place: true,
+                         drawn: false, }
+    }
+
+    /// Returns the colors used to draw frames.
+    pub fn color_mode(&self) -> ColorMode {
+        self.mode
+    }
+
+    /// Set the colors used to draw frames.
+    pub fn set_color_mode(&mut self, mode: ColorMode) {
+        self.mode = mode;
+    }
+
+    /// Returns `true` if every frame is drawn over the previous one.
+    pub fn redraw_in_place(&self) -> bool {
+        self.redraw_in_place
+    }
+
+    /// Set whether every frame is drawn over the previous one, by moving the cursor up, or
+    /// below it, as a log of frames.
+    pub fn set_redraw_in_place(&mut self, redraw_in_place: bool) {
+        self.redraw_in_place = redraw_in_place;
+    }
+
+    /// Returns a reference to the writer.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Consume the `TerminalScreen`, and return the writer.
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Display for TerminalScreen<W> {
+    fn write_frame(&mut self, frame: &FrameLine) -> Result<()> {
+        self.write_pixel_frame(&frame.pixel_frame())
+    }
+
+    fn write_pixel_frame(&mut self, frame: &PixelFrame) -> Result<()> {
+        if self.redraw_in_place && self.drawn {
+            self.writer.write_all(b"\x1b[8A")?;
+        }
+        self.writer.write_all(ansi_string(frame, self.mode).as_bytes())?;
+        self.writer.flush()?;
+        self.drawn = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_mode_is_detected_from_colorterm() {
+        assert_eq!(ColorMode::from_colorterm(Some("truecolor")), ColorMode::TrueColor);
+        assert_eq!(ColorMode::from_colorterm(Some("24bit")), ColorMode::TrueColor);
+        assert_eq!(ColorMode::from_colorterm(Some("yes")), ColorMode::Ansi256);
+        assert_eq!(ColorMode::from_colorterm(None), ColorMode::Ansi256);
+    }
+
+    #[test]
+    fn pixel_frame_is_rendered_with_24_bit_colors() {
+        let mut frame = PixelFrame::BLACK;
+        frame[7] = PixelColor::new(1, 2, 3);
+        let ansi = frame.to_ansi_string();
+        let lines = ansi.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[0],
+                   format!("{}\x1b[48;2;1;2;3m  \x1b[0m", "\x1b[48;2;0;0;0m  ".repeat(7)));
+        assert_eq!(lines[7], format!("{}\x1b[0m", "\x1b[48;2;0;0;0m  ".repeat(8)));
+    }
+
+    #[test]
+    fn pixel_frame_is_rendered_with_256_colors() {
+        let ansi = ansi_string(&PixelFrame::RED, ColorMode::Ansi256);
+        let line = format!("{}\x1b[0m\n", "\x1b[48;5;196m  ".repeat(8));
+        assert_eq!(ansi, line.repeat(8));
+    }
+
+    #[test]
+    fn colors_are_matched_to_the_256_color_palette() {
+        assert_eq!(ansi256(PixelColor::BLACK), 16);
+        assert_eq!(ansi256(PixelColor::WHITE), 231);
+        assert_eq!(ansi256(PixelColor::RED), 196);
+        assert_eq!(ansi256(PixelColor::GREEN), 46);
+        assert_eq!(ansi256(PixelColor::BLUE), 21);
+        assert_eq!(ansi256(PixelColor::new(0x5F, 0x87, 0xAF)), 67);
+        assert_eq!(ansi256(PixelColor::new(0x80, 0x80, 0x80)), 244);
+        assert_eq!(ansi256(PixelColor::new(0x30, 0x30, 0x30)), 236);
+    }
+
+    #[test]
+    fn terminal_screen_redraws_frames_in_place() {
+        let mut terminal = TerminalScreen::with_writer(Vec::new(), ColorMode::TrueColor);
+        terminal.write_pixel_frame(&PixelFrame::RED).unwrap();
+        terminal.write_frame(&PixelFrame::BLUE.frame_line()).unwrap();
+        let expected = format!("{}\x1b[8A{}",
+                               PixelFrame::RED.to_ansi_string(),
+                               PixelFrame::BLUE.frame_line().pixel_frame().to_ansi_string());
+        assert_eq!(String::from_utf8(terminal.into_inner()).unwrap(), expected);
+    }
+
+    #[test]
+    fn terminal_screen_logs_frames_one_below_the_other() {
+        let mut terminal = TerminalScreen::with_writer(Vec::new(), ColorMode::Ansi256);
+        terminal.set_redraw_in_place(false);
+        terminal.write_pixel_frame(&PixelFrame::RED).unwrap();
+        terminal.write_pixel_frame(&PixelFrame::RED).unwrap();
+        let frame = ansi_string(&PixelFrame::RED, ColorMode::Ansi256);
+        assert_eq!(terminal.get_ref(), &frame.repeat(2).into_bytes());
+    }
+}